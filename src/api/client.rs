@@ -1,16 +1,35 @@
+use crate::utils::id::generate_uuid;
+use crate::utils::{measure_time, retry_with_delay};
 use crate::Config;
-use reqwest::{Client, ClientBuilder, StatusCode};
+use reqwest::{Client, ClientBuilder, Method, RequestBuilder, StatusCode};
 use serde::{de::DeserializeOwned, Serialize};
+use std::sync::Arc;
 use std::time::Duration;
 
 use super::error::ApiError;
 use super::request::ApiRequest;
-use super::response::ApiResponse;
+use super::response::{ApiResponse, REQUEST_ID_HEADER};
+
+/// Initial delay between retries in milliseconds
+const RETRY_INITIAL_DELAY_MS: u64 = 100;
+
+/// Longest `Retry-After` the client will wait out before giving up on a request
+const MAX_RETRY_AFTER: Duration = Duration::from_secs(30);
+
+/// Source of bearer tokens minted for each outgoing request
+///
+/// Implementations can issue short-lived tokens, such as signed JWTs, in
+/// place of a static API key.
+pub trait TokenProvider: Send + Sync {
+    /// Get a token to send with the next request
+    fn token(&self) -> Result<String, ApiError>;
+}
 
 /// API client for making requests to external services
 pub struct ApiClient {
     client: Client,
     config: Config,
+    token_provider: Option<Arc<dyn TokenProvider>>,
 }
 
 impl ApiClient {
@@ -21,7 +40,13 @@ impl ApiClient {
             .build()
             .map_err(|e| ApiError::ClientCreationError(e.to_string()))?;
 
-        Ok(Self { client, config })
+        Ok(Self { client, config, token_provider: None })
+    }
+
+    /// Authenticate requests with tokens from a provider instead of the API key
+    pub fn with_token_provider(mut self, provider: Arc<dyn TokenProvider>) -> Self {
+        self.token_provider = Some(provider);
+        self
     }
 
     /// Get the current configuration
@@ -44,20 +69,15 @@ impl ApiClient {
     where
         T: DeserializeOwned,
     {
-        let url = format!("{}/{}", self.config.api_url, endpoint);
-        
-        let mut request = self.client.get(&url);
-        
-        if let Some(api_key) = &self.config.api_key {
-            request = request.header("Authorization", format!("Bearer {}", api_key));
-        }
-        
-        let response = request
-            .send()
-            .await
-            .map_err(|e| ApiError::RequestError(e.to_string()))?;
-            
-        Self::process_response(response).await
+        self.get_response(endpoint).await.map(ApiResponse::into_body)
+    }
+
+    /// Execute a GET request, returning the full response
+    pub async fn get_response<T>(&self, endpoint: &str) -> Result<ApiResponse<T>, ApiError>
+    where
+        T: DeserializeOwned,
+    {
+        self.execute(ApiRequest::<()>::get(endpoint)).await
     }
 
     /// Execute a POST request with a JSON body
@@ -66,27 +86,91 @@ impl ApiClient {
         T: DeserializeOwned,
         R: Serialize,
     {
-        let url = format!("{}/{}", self.config.api_url, endpoint);
-        
-        let mut request = self.client.post(&url);
-        
-        if let Some(api_key) = &self.config.api_key {
-            request = request.header("Authorization", format!("Bearer {}", api_key));
+        self.post_response(endpoint, body).await.map(ApiResponse::into_body)
+    }
+
+    /// Execute a POST request with a JSON body, returning the full response
+    pub async fn post_response<T, R>(&self, endpoint: &str, body: &R) -> Result<ApiResponse<T>, ApiError>
+    where
+        T: DeserializeOwned,
+        R: Serialize,
+    {
+        self.execute(ApiRequest::post(endpoint).with_body(body)).await
+    }
+
+    /// Execute a custom API request
+    ///
+    /// Idempotent requests are retried with exponential backoff on transient
+    /// failures, up to `Config::max_retries` times.
+    pub async fn execute<T, R>(&self, request: ApiRequest<R>) -> Result<ApiResponse<T>, ApiError>
+    where
+        T: DeserializeOwned,
+        R: Serialize,
+    {
+        let request_id = request
+            .headers()
+            .get(REQUEST_ID_HEADER)
+            .cloned()
+            .unwrap_or_else(generate_uuid);
+
+        let max_retries = match *request.method() {
+            Method::GET | Method::PUT | Method::DELETE => self.config.max_retries,
+            _ => 0,
+        };
+
+        let attempts = std::sync::atomic::AtomicU32::new(0);
+        let (result, elapsed) = measure_time(|| {
+            retry_with_delay(
+                || {
+                    attempts.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+                    self.send(&request, &request_id)
+                },
+                Self::retry_delay,
+                max_retries,
+                RETRY_INITIAL_DELAY_MS,
+            )
+        })
+        .await;
+
+        let retries = attempts.into_inner().saturating_sub(1);
+        if retries > 0 {
+            log::debug!("Request {} completed after {} retries", request_id, retries);
         }
-        
-        let response = request
-            .json(body)
+
+        result.map(|response| {
+            response
+                .with_elapsed(elapsed)
+                .with_retries(retries)
+                .with_request_id(&request_id)
+        })
+    }
+
+    // Helper method to perform a single attempt of a request
+    async fn send<T, R>(&self, request: &ApiRequest<R>, request_id: &str) -> Result<ApiResponse<T>, ApiError>
+    where
+        T: DeserializeOwned,
+        R: Serialize,
+    {
+        let response = self
+            .build(request, request_id)?
             .send()
             .await
-            .map_err(|e| ApiError::RequestError(e.to_string()))?;
-            
+            .map_err(|e| {
+                if e.is_timeout() {
+                    ApiError::Timeout
+                } else if e.is_connect() {
+                    ApiError::ConnectionError(e.to_string())
+                } else {
+                    ApiError::RequestError(e.to_string())
+                }
+            })?;
+
         Self::process_response(response).await
     }
 
-    /// Execute a custom API request
-    pub async fn execute<T, R>(&self, request: ApiRequest<R>) -> Result<ApiResponse<T>, ApiError>
+    // Helper method to build the HTTP request
+    fn build<R>(&self, request: &ApiRequest<R>, request_id: &str) -> Result<RequestBuilder, ApiError>
     where
-        T: DeserializeOwned,
         R: Serialize,
     {
         // Build the full URL
@@ -96,57 +180,74 @@ impl ApiClient {
         };
 
         // Create the HTTP request based on the method
-        let req_builder = match request.method() {
-            reqwest::Method::GET => self.client.get(&url),
-            reqwest::Method::POST => self.client.post(&url),
-            reqwest::Method::PUT => self.client.put(&url),
-            reqwest::Method::DELETE => self.client.delete(&url),
-            reqwest::Method::PATCH => self.client.patch(&url),
+        let req_builder = match *request.method() {
+            Method::GET => self.client.get(&url),
+            Method::POST => self.client.post(&url),
+            Method::PUT => self.client.put(&url),
+            Method::DELETE => self.client.delete(&url),
+            Method::PATCH => self.client.patch(&url),
             _ => return Err(ApiError::UnsupportedMethod),
         };
 
-        // Add the API key if present
-        let mut req_builder = if let Some(api_key) = &self.config.api_key {
+        // Add a freshly minted token, or the API key if present
+        let mut req_builder = if let Some(provider) = &self.token_provider {
+            req_builder.header("Authorization", format!("Bearer {}", provider.token()?))
+        } else if let Some(api_key) = &self.config.api_key {
             req_builder.header("Authorization", format!("Bearer {}", api_key))
         } else {
             req_builder
         };
 
         // Add headers
+        req_builder = req_builder.header(REQUEST_ID_HEADER, request_id);
         for (key, value) in request.headers() {
             req_builder = req_builder.header(key, value);
         }
 
         // Add query parameters
-        req_builder = req_builder.query(&request.query_params());
+        if !request.query_params().is_empty() {
+            req_builder = req_builder.query(request.query_params());
+        }
 
         // Add body if present
-        let response = if let Some(body) = request.body() {
-            req_builder.json(body)
-        } else {
-            req_builder
-        }
-        .send()
-        .await
-        .map_err(|e| ApiError::RequestError(e.to_string()))?;
+        Ok(match request.body() {
+            Some(body) => req_builder.json(body),
+            None => req_builder,
+        })
+    }
 
-        // Process the response
+    // Helper method to process API responses
+    async fn process_response<T>(response: reqwest::Response) -> Result<ApiResponse<T>, ApiError>
+    where
+        T: DeserializeOwned,
+    {
         let status = response.status();
         let headers = response.headers().clone();
 
         match status {
-            StatusCode::OK | StatusCode::CREATED | StatusCode::ACCEPTED => {
-                let body = response
-                    .json::<T>()
+            status if status.is_success() => {
+                let bytes = response
+                    .bytes()
                     .await
                     .map_err(|e| ApiError::ResponseParseError(e.to_string()))?;
 
+                // An empty body (e.g. 204 No Content) reads as JSON null, so `()` and `Option` targets still work
+                let body = match bytes.iter().all(u8::is_ascii_whitespace) {
+                    true => serde_json::from_slice::<T>(b"null"),
+                    false => serde_json::from_slice::<T>(&bytes),
+                }
+                .map_err(|e| ApiError::ResponseParseError(e.to_string()))?;
+
                 Ok(ApiResponse::new(status, headers, body))
             }
             StatusCode::NOT_FOUND => Err(ApiError::ResourceNotFound),
             StatusCode::UNAUTHORIZED => Err(ApiError::Unauthorized),
             StatusCode::FORBIDDEN => Err(ApiError::Forbidden),
-            StatusCode::TOO_MANY_REQUESTS => Err(ApiError::RateLimitExceeded),
+            StatusCode::TOO_MANY_REQUESTS => Err(ApiError::RateLimitExceeded(Self::retry_after(&headers))),
+            StatusCode::CONFLICT | StatusCode::PRECONDITION_FAILED => {
+                let error_text = response.text().await.unwrap_or_default();
+                Err(ApiError::Conflict(error_text))
+            }
             _ => {
                 let error_text = response
                     .text()
@@ -158,33 +259,156 @@ impl ApiClient {
         }
     }
 
-    // Helper method to process API responses
-    async fn process_response<T>(response: reqwest::Response) -> Result<T, ApiError>
-    where
-        T: DeserializeOwned,
-    {
-        let status = response.status();
-        
-        match status {
-            StatusCode::OK | StatusCode::CREATED | StatusCode::ACCEPTED => {
-                let body = response
-                    .json::<T>()
-                    .await
-                    .map_err(|e| ApiError::ResponseParseError(e.to_string()))?;
-                Ok(body)
-            }
-            StatusCode::NOT_FOUND => Err(ApiError::ResourceNotFound),
-            StatusCode::UNAUTHORIZED => Err(ApiError::Unauthorized),
-            StatusCode::FORBIDDEN => Err(ApiError::Forbidden),
-            StatusCode::TOO_MANY_REQUESTS => Err(ApiError::RateLimitExceeded),
-            _ => {
-                let error_text = response
-                    .text()
-                    .await
-                    .unwrap_or_else(|_| "Unknown error".to_string());
-                    
-                Err(ApiError::ServerError(status.as_u16(), error_text))
+    // Helper method to read a Retry-After header, in seconds or as an HTTP date
+    fn retry_after(headers: &reqwest::header::HeaderMap) -> Option<Duration> {
+        let value = headers.get(reqwest::header::RETRY_AFTER)?.to_str().ok()?.trim();
+        if let Ok(seconds) = value.parse::<u64>() {
+            return Some(Duration::from_secs(seconds));
+        }
+
+        let date = chrono::DateTime::parse_from_rfc2822(value).ok()?;
+        Some((date.with_timezone(&chrono::Utc) - chrono::Utc::now()).to_std().unwrap_or_default())
+    }
+
+    // Helper method to decide how long to wait before retrying an error, if at all
+    fn retry_delay(error: &ApiError, backoff: Duration) -> Option<Duration> {
+        match error {
+            ApiError::Timeout | ApiError::ConnectionError(_) | ApiError::ServerError(502..=504, _) => Some(backoff),
+            ApiError::RateLimitExceeded(None) => Some(backoff),
+            ApiError::RateLimitExceeded(Some(wait)) => (*wait <= MAX_RETRY_AFTER).then_some(*wait),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_config(api_url: String) -> Config {
+        Config {
+            api_url,
+            api_key: None,
+            timeout: Duration::from_secs(1),
+            max_retries: 2,
+            ..Config::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_get_response_exposes_metadata() {
+        let mut server = mockito::Server::new_async().await;
+        let _m = server
+            .mock("GET", "/items")
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .with_header("x-ratelimit-remaining", "42")
+            .with_header("x-request-id", "req-123")
+            .with_body("[1,2,3]")
+            .create_async()
+            .await;
+
+        let client = ApiClient::new(test_config(server.url())).unwrap();
+        let response: ApiResponse<Vec<u32>> = client.get_response("items").await.unwrap();
+
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.rate_limit_remaining(), Some(42));
+        assert_eq!(response.request_id(), Some("req-123"));
+        assert_eq!(response.retries(), 0);
+        assert_eq!(response.body(), &vec![1, 2, 3]);
+    }
+
+    #[tokio::test]
+    async fn test_get_retries_transient_errors() {
+        let mut server = mockito::Server::new_async().await;
+        let m = server
+            .mock("GET", "/flaky")
+            .with_status(503)
+            .expect(3)
+            .create_async()
+            .await;
+
+        let client = ApiClient::new(test_config(server.url())).unwrap();
+        let result: Result<u32, ApiError> = client.get("flaky").await;
+
+        assert!(matches!(result, Err(ApiError::ServerError(503, _))));
+        m.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn test_token_provider_mints_per_request() {
+        struct Counter(std::sync::atomic::AtomicU32);
+
+        impl TokenProvider for Counter {
+            fn token(&self) -> Result<String, ApiError> {
+                let n = self.0.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+                Ok(format!("token-{}", n))
             }
         }
+
+        let mut server = mockito::Server::new_async().await;
+        let first = server.mock("GET", "/items").match_header("authorization", "Bearer token-0").with_status(503).create_async().await;
+        let second = server.mock("GET", "/items").match_header("authorization", "Bearer token-1").with_body("7").create_async().await;
+
+        let mut config = test_config(server.url());
+        config.api_key = Some("static-key".to_string());
+        let client = ApiClient::new(config).unwrap().with_token_provider(Arc::new(Counter(Default::default())));
+        let result: u32 = client.get("items").await.unwrap();
+
+        assert_eq!(result, 7);
+        first.assert_async().await;
+        second.assert_async().await;
     }
-}
\ No newline at end of file
+
+    #[tokio::test]
+    async fn test_empty_success_body_reads_as_null() {
+        let mut server = mockito::Server::new_async().await;
+        let _m = server.mock("DELETE", "/items/1").with_status(204).expect(2).create_async().await;
+
+        let client = ApiClient::new(test_config(server.url())).unwrap();
+        let unit: ApiResponse<()> = client.execute(ApiRequest::<()>::delete("items/1")).await.unwrap();
+        let none: ApiResponse<Option<u32>> = client.execute(ApiRequest::<()>::delete("items/1")).await.unwrap();
+
+        assert_eq!(unit.status(), StatusCode::NO_CONTENT);
+        assert_eq!(none.body(), &None);
+    }
+
+    #[tokio::test]
+    async fn test_rate_limit_honors_retry_after() {
+        let mut server = mockito::Server::new_async().await;
+        let limited = server.mock("GET", "/items").with_status(429).with_header("retry-after", "0").expect(1).create_async().await;
+        let ok = server.mock("GET", "/items").with_body("7").create_async().await;
+
+        let client = ApiClient::new(test_config(server.url())).unwrap();
+        let response: ApiResponse<u32> = client.get_response("items").await.unwrap();
+        assert_eq!(response.body(), &7);
+        assert_eq!(response.retries(), 1);
+        limited.assert_async().await;
+        ok.assert_async().await;
+
+        let mut server = mockito::Server::new_async().await;
+        let m = server.mock("GET", "/items").with_status(429).with_header("retry-after", "3600").expect(1).create_async().await;
+
+        let client = ApiClient::new(test_config(server.url())).unwrap();
+        let result: Result<u32, ApiError> = client.get("items").await;
+        assert!(matches!(result, Err(ApiError::RateLimitExceeded(Some(wait))) if wait == Duration::from_secs(3600)));
+        m.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn test_post_is_not_retried() {
+        let mut server = mockito::Server::new_async().await;
+        let m = server
+            .mock("POST", "/items")
+            .with_status(503)
+            .expect(1)
+            .create_async()
+            .await;
+
+        let client = ApiClient::new(test_config(server.url())).unwrap();
+        let result: Result<u32, ApiError> = client.post("items", &1).await;
+
+        assert!(matches!(result, Err(ApiError::ServerError(503, _))));
+        m.assert_async().await;
+    }
+}
@@ -1,7 +1,7 @@
 use thiserror::Error;
 
 /// API error types
-#[derive(Error, Debug)]
+#[derive(Error, Debug, Clone)]
 pub enum ApiError {
     /// Error during API client creation
     #[error("Failed to create API client: {0}")]
@@ -19,6 +19,14 @@ pub enum ApiError {
     #[error("Resource not found")]
     ResourceNotFound,
 
+    /// Bearer token could not be obtained for a request
+    #[error("Failed to obtain access token: {0}")]
+    TokenError(String),
+
+    /// Resource changed since the version the request was based on (HTTP 409 or 412)
+    #[error("Conflicting update: {0}")]
+    Conflict(String),
+
     /// Authentication error (HTTP 401)
     #[error("Authentication required")]
     Unauthorized,
@@ -27,9 +35,9 @@ pub enum ApiError {
     #[error("Access forbidden")]
     Forbidden,
 
-    /// Rate limit exceeded (HTTP 429)
+    /// Rate limit exceeded (HTTP 429), with the server's `Retry-After` delay if it sent one
     #[error("API rate limit exceeded")]
-    RateLimitExceeded,
+    RateLimitExceeded(Option<std::time::Duration>),
 
     /// Server error with status code and message
     #[error("Server error {0}: {1}")]
@@ -58,4 +66,18 @@ pub enum ApiError {
     /// Unknown error
     #[error("Unknown error: {0}")]
     Unknown(String),
+}
+
+impl ApiError {
+    /// Check if the error is temporary: the API was unreachable, rate limited or failing
+    pub fn is_transient(&self) -> bool {
+        matches!(
+            self,
+            ApiError::Timeout
+                | ApiError::ConnectionError(_)
+                | ApiError::NetworkError(_)
+                | ApiError::RateLimitExceeded(_)
+                | ApiError::ServerError(500..=599, _)
+        )
+    }
 }
\ No newline at end of file
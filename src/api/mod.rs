@@ -7,8 +7,10 @@ pub mod error;
 pub mod request;
 pub mod response;
 
-pub use client::ApiClient;
+pub use client::{ApiClient, TokenProvider};
 pub use error::ApiError;
+pub use request::ApiRequest;
+pub use response::ApiResponse;
 
 /// API version used for requests
 pub const API_VERSION: &str = "v1";
@@ -1,19 +1,69 @@
 use reqwest::{StatusCode, header::HeaderMap};
+use std::time::Duration;
 
-/// API response structure with status, headers, and body
+/// Header used to correlate requests and responses
+pub const REQUEST_ID_HEADER: &str = "x-request-id";
+
+/// API response structure with status, headers, body, and request metadata
 pub struct ApiResponse<T> {
     status: StatusCode,
     headers: HeaderMap,
     body: T,
+    elapsed: Duration,
+    retries: u32,
+    request_id: Option<String>,
 }
 
 impl<T> ApiResponse<T> {
     /// Create a new API response
     pub fn new(status: StatusCode, headers: HeaderMap, body: T) -> Self {
+        let request_id = headers
+            .get(REQUEST_ID_HEADER)
+            .and_then(|v| v.to_str().ok())
+            .map(|v| v.to_string());
+
         Self {
             status,
             headers,
             body,
+            elapsed: Duration::ZERO,
+            retries: 0,
+            request_id,
+        }
+    }
+
+    /// Set the total time taken by the call, including retries
+    pub fn with_elapsed(mut self, elapsed: Duration) -> Self {
+        self.elapsed = elapsed;
+        self
+    }
+
+    /// Set the number of retries performed before this response
+    pub fn with_retries(mut self, retries: u32) -> Self {
+        self.retries = retries;
+        self
+    }
+
+    /// Set the request ID, unless the server already echoed one
+    pub fn with_request_id(mut self, request_id: &str) -> Self {
+        if self.request_id.is_none() {
+            self.request_id = Some(request_id.to_string());
+        }
+        self
+    }
+
+    /// Transform the response body, keeping the metadata
+    pub fn map<U, F>(self, f: F) -> ApiResponse<U>
+    where
+        F: FnOnce(T) -> U,
+    {
+        ApiResponse {
+            status: self.status,
+            headers: self.headers,
+            body: f(self.body),
+            elapsed: self.elapsed,
+            retries: self.retries,
+            request_id: self.request_id,
         }
     }
 
@@ -57,6 +107,27 @@ impl<T> ApiResponse<T> {
         self.header("content-type")
     }
 
+    /// Get the total time taken by the call, including retries
+    pub fn elapsed(&self) -> Duration {
+        self.elapsed
+    }
+
+    /// Get the number of retries performed before this response
+    pub fn retries(&self) -> u32 {
+        self.retries
+    }
+
+    /// Get the request ID used to correlate this call
+    pub fn request_id(&self) -> Option<&str> {
+        self.request_id.as_deref()
+    }
+
+    /// Get the rate limit ceiling from headers
+    pub fn rate_limit_limit(&self) -> Option<u32> {
+        self.header("x-ratelimit-limit")
+            .and_then(|v| v.parse::<u32>().ok())
+    }
+
     /// Get the rate limit remaining from headers
     pub fn rate_limit_remaining(&self) -> Option<u32> {
         self.header("x-ratelimit-remaining")
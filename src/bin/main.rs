@@ -1,6 +1,25 @@
 use clap::{Parser, Subcommand};
+use rust_project_example::core::processor::{
+    AuditLogProcessor, DocumentProcessor, ProcessorRegistry, SchemaProcessor, UserProcessor,
+};
+use rust_project_example::core::condition::Condition;
+use rust_project_example::core::policy::Policy;
+use rust_project_example::core::query::ListQuery;
+use rust_project_example::core::resource_types::{CustomTypeProcessor, TypeRegistry};
+use rust_project_example::core::service::ResourceService;
+use rust_project_example::core::user_service::UserService;
+use rust_project_example::core::Service;
+use rust_project_example::models::persistence::RepositoryFactory;
+use rust_project_example::models::{
+    AclEntry, Permission, Principal, Resource, ResourceData, ResourceType, User, UserRole,
+};
+use rust_project_example::server::{self, RestServer, WebhookConfig, WebhookReceiver};
+use rust_project_example::utils::id::generate_prefixed_id;
 use rust_project_example::{self, create_config};
 use std::error::Error;
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use std::sync::Arc;
 
 #[derive(Parser)]
 #[command(author, version, about, long_about = None)]
@@ -10,9 +29,17 @@ struct Cli {
     api_url: Option<String>,
 
     /// API key for authentication
-    #[arg(short, long)]
+    #[arg(short = 'k', long)]
     api_key: Option<String>,
 
+    /// Directory of the persistent response cache
+    #[arg(long)]
+    cache_dir: Option<PathBuf>,
+
+    /// Keep the response cache in memory only
+    #[arg(long)]
+    no_cache: bool,
+
     #[command(subcommand)]
     command: Commands,
 }
@@ -30,17 +57,121 @@ enum Commands {
         /// Maximum number of items to show
         #[arg(short, long, default_value_t = 10)]
         limit: usize,
+
+        /// Page number, starting at 1
+        #[arg(short, long)]
+        page: Option<usize>,
+
+        /// Filter, e.g. 'type:document owner:alice created>=2024-01-01'
+        #[arg(short, long)]
+        query: Option<String>,
+
+        /// Sort field, prefixed with '-' for descending order
+        #[arg(short, long, allow_hyphen_values = true)]
+        sort: Option<String>,
+
+        /// Comma-separated fields to show, e.g. 'id,name,data.status'
+        #[arg(short, long)]
+        fields: Option<String>,
     },
     /// Create a new resource
     Create {
         /// Resource name
         #[arg(short, long)]
         name: String,
-        
+
         /// Resource type
         #[arg(short, long)]
         resource_type: String,
     },
+    /// Grant a user or group permissions on a resource
+    Share {
+        /// Resource ID to share
+        #[arg(short, long)]
+        id: String,
+
+        /// Principal to grant access to, e.g. 'user:alice' or 'group:ops'
+        #[arg(long)]
+        principal: String,
+
+        /// Comma-separated permissions, e.g. 'read_resource,update_resource'
+        #[arg(short, long, value_delimiter = ',', required = true)]
+        permissions: Vec<String>,
+
+        /// Number of days until the access expires
+        #[arg(long)]
+        expires_in_days: Option<u32>,
+    },
+    /// Remove a user's or group's access to a resource
+    Unshare {
+        /// Resource ID to unshare
+        #[arg(short, long)]
+        id: String,
+
+        /// Principal to remove, e.g. 'user:alice' or 'group:ops'
+        #[arg(long)]
+        principal: String,
+    },
+    /// Show the access control entries of a resource and who granted them
+    Acl {
+        /// Resource ID to inspect
+        #[arg(short, long)]
+        id: String,
+    },
+    /// List the revisions of a resource, or show what changed between two of them
+    History {
+        /// Resource ID to inspect
+        #[arg(short, long)]
+        id: String,
+
+        /// Compare two revisions, e.g. '1..3'
+        #[arg(short, long)]
+        diff: Option<String>,
+    },
+    /// Restore a past revision of a resource as a new revision
+    Restore {
+        /// Resource ID to restore
+        #[arg(short, long)]
+        id: String,
+
+        /// Revision number to restore
+        #[arg(short, long)]
+        revision: u64,
+    },
+    /// Run a local webhook receiver
+    Webhook {
+        /// Address to listen on
+        #[arg(short, long, default_value = server::DEFAULT_BIND_ADDR)]
+        bind: SocketAddr,
+
+        /// Shared secret used to verify webhook signatures
+        #[arg(short, long)]
+        secret: String,
+
+        /// Accepted timestamp difference in seconds
+        #[arg(short, long, default_value_t = server::webhook::DEFAULT_TOLERANCE_SECS)]
+        tolerance: u64,
+    },
+    /// Run a local REST backend with in-memory repositories
+    Serve {
+        /// Address to listen on
+        #[arg(short, long, default_value = server::DEFAULT_BIND_ADDR)]
+        bind: SocketAddr,
+
+        /// API key granting admin access to the backend
+        #[arg(long)]
+        admin_key: String,
+
+        /// Policy file (TOML or JSON) defining custom roles
+        #[arg(long)]
+        policy: Option<PathBuf>,
+
+        /// Types file (TOML or JSON) defining custom resource types
+        #[arg(long)]
+        types: Option<PathBuf>,
+    },
+    /// Remove all entries from the persistent response cache
+    ClearCache,
 }
 
 #[tokio::main]
@@ -50,25 +181,173 @@ async fn main() -> Result<(), Box<dyn Error>> {
 
     // Parse command line arguments
     let cli = Cli::parse();
-    
+
     // Create configuration
-    let config = create_config(cli.api_url, cli.api_key);
-    
+    let mut config = create_config(cli.api_url, cli.api_key);
+    if !cli.no_cache {
+        config.cache.dir = cli.cache_dir.or_else(default_cache_dir);
+    }
+
     // Process commands
     match &cli.command {
         Commands::Fetch { id } => {
             println!("Fetching resource with ID: {}", id);
-            // Implementation would use the client to fetch data
+            let service = ResourceService::new(config)?;
+            let resource = service.get(id).await?;
+            println!("{}", serde_json::to_string_pretty(&resource)?);
         }
-        Commands::List { limit } => {
+        Commands::List { limit, page, query, sort, fields } => {
             println!("Listing up to {} resources:", limit);
-            // Implementation would use the client to list resources
+            let list_query = ListQuery {
+                limit: Some(*limit),
+                page: *page,
+                condition: query.as_deref().map(Condition::parse).transpose()?,
+                sort: sort.clone(),
+                fields: fields.as_deref().map(str::parse).transpose()?,
+                ..ListQuery::default()
+            };
+
+            let service = ResourceService::new(config)?;
+            for resource in service.list_with(&list_query).await? {
+                match list_query.fields {
+                    Some(_) => println!("{}", list_query.project(&resource)),
+                    None => println!("{}\t{}\t{}", resource.id, resource.data.resource_type, resource.data.name),
+                }
+            }
         }
         Commands::Create { name, resource_type } => {
             println!("Creating a new {} resource named: {}", resource_type, name);
-            // Implementation would use the client to create a resource
+            let resource_type: ResourceType = resource_type.parse()?;
+            let id = generate_prefixed_id(&resource_type.to_string().chars().take(3).collect::<String>());
+            let service = ResourceService::new(config)?;
+            let resource = service.create(Resource::new(&id, ResourceData::new(name, resource_type))).await?;
+            println!("Created resource {}", resource.id);
+        }
+        Commands::Share { id, principal, permissions, expires_in_days } => {
+            let principal: Principal = principal.parse()?;
+            let permissions = permissions
+                .iter()
+                .map(|p| p.parse::<Permission>())
+                .collect::<Result<Vec<_>, _>>()?;
+            let mut entry = AclEntry::new(principal, permissions);
+            if let Some(days) = expires_in_days {
+                let expiry = chrono::Duration::try_days(i64::from(*days))
+                    .and_then(|ttl| chrono::Utc::now().checked_add_signed(ttl))
+                    .ok_or_else(|| format!("Expiry of {} days is too far in the future", days))?;
+                entry = entry.with_expiry(expiry);
+            }
+
+            let service = ResourceService::new(config)?;
+            service.share(id, entry.clone()).await?;
+            println!("Shared resource {} with {}", id, entry.principal);
+        }
+        Commands::Unshare { id, principal } => {
+            let principal: Principal = principal.parse()?;
+            let service = ResourceService::new(config)?;
+            service.unshare(id, &principal).await?;
+            println!("Removed access for {} to resource {}", principal, id);
+        }
+        Commands::Acl { id } => {
+            let service = ResourceService::new(config)?;
+            let resource = service.get(id).await?;
+            for entry in &resource.acl {
+                let mut permissions: Vec<String> = entry.permissions.iter().map(ToString::to_string).collect();
+                permissions.sort();
+                let expiry = entry.expires_at.as_deref().unwrap_or("never");
+                println!("{}\t{}\texpires {}", entry.principal, permissions.join(","), expiry);
+            }
+            println!("History:");
+            for event in &resource.acl_log {
+                println!("{}\t{}\t{}\tby {}", event.at, event.action, event.entry.principal, event.actor);
+            }
+        }
+        Commands::History { id, diff } => {
+            let service = ResourceService::new(config)?;
+            match diff {
+                Some(range) => {
+                    let (from, to) = range
+                        .split_once("..")
+                        .and_then(|(from, to)| Some((from.parse().ok()?, to.parse().ok()?)))
+                        .ok_or_else(|| format!("Invalid revision range: {}", range))?;
+                    for change in service.diff(id, from, to).await? {
+                        println!("{}", change);
+                    }
+                }
+                None => {
+                    for revision in service.revisions(id).await? {
+                        let author = revision.changed_by.as_deref().unwrap_or("unknown");
+                        println!("{}\t{}\tby {}\t{}", revision.number, revision.updated_at, author, revision.data.name);
+                    }
+                }
+            }
+        }
+        Commands::Restore { id, revision } => {
+            let service = ResourceService::new(config)?;
+            service.restore(id, *revision).await?;
+            println!("Restored resource {} to revision {}", id, revision);
+        }
+        Commands::Webhook { bind, secret, tolerance } => {
+            let registry = default_registry(Arc::new(TypeRegistry::new())).await;
+            let webhook_config = WebhookConfig::new(secret)
+                .with_tolerance(std::time::Duration::from_secs(*tolerance));
+            WebhookReceiver::new(webhook_config, Arc::new(registry))
+                .serve(*bind)
+                .await?;
+        }
+        Commands::Serve { bind, admin_key, policy, types } => {
+            let policy = match policy {
+                Some(path) => Policy::load(path)?,
+                None => Policy::default(),
+            };
+            let types = Arc::new(match types {
+                Some(path) => TypeRegistry::load(path)?,
+                None => TypeRegistry::new(),
+            });
+
+            let factory = RepositoryFactory::new_in_memory();
+            let admin = User::new("admin", "admin@localhost", "Administrator")
+                .with_role(UserRole::Admin)
+                .with_email_verified(true);
+            factory.user_repository().save(admin).await?;
+
+            RestServer::new(&factory, Arc::new(default_registry(types.clone()).await))
+                .with_api_key(admin_key, "admin")
+                .with_policy(Arc::new(policy))
+                .with_types(types)
+                .serve(*bind)
+                .await?;
+        }
+        Commands::ClearCache => {
+            match config.scoped_cache().dir {
+                Some(dir) => {
+                    let resources = ResourceService::new(config.clone())?;
+                    resources.invalidate_cache().await;
+                    UserService::with_client(resources.client()).invalidate_cache().await;
+                    println!("Cleared cache in {}", dir.display());
+                }
+                None => println!("No persistent cache configured"),
+            }
         }
     }
 
     Ok(())
-}
\ No newline at end of file
+}
+
+// Resolve the per-user cache directory from the environment
+fn default_cache_dir() -> Option<PathBuf> {
+    let base = std::env::var_os("XDG_CACHE_HOME")
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".cache")))?;
+    Some(base.join(env!("CARGO_PKG_NAME")))
+}
+
+// Build a registry with the built-in processors and those of custom types
+async fn default_registry(types: Arc<TypeRegistry>) -> ProcessorRegistry {
+    let registry = ProcessorRegistry::new();
+    registry.register(ResourceType::Document, Box::new(DocumentProcessor)).await;
+    registry.register(ResourceType::User, Box::new(UserProcessor)).await;
+    registry.register(ResourceType::Any, Box::new(SchemaProcessor::default())).await;
+    registry.register(ResourceType::Any, Box::new(CustomTypeProcessor::new(types))).await;
+    registry.register(ResourceType::Any, Box::new(AuditLogProcessor)).await;
+    registry
+}
@@ -0,0 +1,606 @@
+use crate::models::persistence::{
+    GroupRepository, PersistenceError, Repository, RepositoryFactory, ResourceRepository, TokenRepository,
+};
+use crate::models::token::hash_secret;
+use crate::models::{AccountStatus, Token, TokenKind, User};
+use crate::utils::id::{generate_prefixed_id, generate_secret, generate_uuid};
+use chrono::Utc;
+use std::sync::Arc;
+use std::time::Duration;
+
+use super::credentials::PasswordManager;
+use super::error::CoreError;
+use super::mail::{Email, MailSender};
+
+/// Message returned for every verification or reset token that can't be used
+const INVALID_TOKEN: &str = "Invalid or expired token";
+
+/// Email verification, password reset and account lifecycle
+///
+/// Verification and reset mail the user a single-use token that expires, of
+/// which only the hash is stored. Tokens are tied to the address they were
+/// sent to, and requesting a new one revokes those still outstanding.
+///
+/// Disabled and soft-deleted accounts are refused logins and lose their
+/// tokens. Soft-deleted accounts are kept for a retention window, after
+/// which `purge_deleted` erases them.
+pub struct AccountService {
+    users: Arc<dyn Repository<User, String> + Send + Sync>,
+    tokens: Arc<dyn TokenRepository + Send + Sync>,
+    resources: Arc<dyn ResourceRepository + Send + Sync>,
+    groups: Arc<dyn GroupRepository + Send + Sync>,
+    mailer: Arc<dyn MailSender>,
+    passwords: PasswordManager,
+    verification_ttl: Duration,
+    reset_ttl: Duration,
+    link_base: Option<String>,
+    retention: Duration,
+}
+
+impl AccountService {
+    /// Create a service over the factory's repositories, sending mail with `mailer`
+    ///
+    /// Verification tokens last 24 hours, reset tokens one hour, and
+    /// soft-deleted accounts are kept for 30 days.
+    pub fn new(factory: &RepositoryFactory, mailer: Arc<dyn MailSender>) -> Self {
+        Self {
+            users: factory.user_repository(),
+            tokens: factory.token_repository(),
+            resources: factory.resource_repository(),
+            groups: factory.group_repository(),
+            mailer,
+            passwords: PasswordManager::default(),
+            verification_ttl: Duration::from_secs(24 * 60 * 60),
+            reset_ttl: Duration::from_secs(60 * 60),
+            link_base: None,
+            retention: Duration::from_secs(30 * 24 * 60 * 60),
+        }
+    }
+
+    /// Set new passwords with the given manager
+    pub fn with_password_manager(mut self, passwords: PasswordManager) -> Self {
+        self.passwords = passwords;
+        self
+    }
+
+    /// Set how long email verification tokens last
+    pub fn with_verification_ttl(mut self, ttl: Duration) -> Self {
+        self.verification_ttl = ttl;
+        self
+    }
+
+    /// Set how long password reset tokens last
+    pub fn with_reset_ttl(mut self, ttl: Duration) -> Self {
+        self.reset_ttl = ttl;
+        self
+    }
+
+    /// Set how long soft-deleted accounts are kept before they are erased
+    pub fn with_retention(mut self, retention: Duration) -> Self {
+        self.retention = retention;
+        self
+    }
+
+    /// Send links under the given URL instead of bare tokens
+    pub fn with_link_base(mut self, url: &str) -> Self {
+        self.link_base = Some(url.trim_end_matches('/').to_string());
+        self
+    }
+
+    /// Mail a user a token confirming their current email address
+    pub async fn request_email_verification(&self, user_id: &str) -> Result<Token, CoreError> {
+        let user = self.find(user_id).await?;
+        if !user.enabled {
+            return Err(CoreError::PermissionDenied(format!("User {} is {}", user.id, user.status())));
+        }
+        if user.email_verified {
+            return Err(CoreError::Validation(format!("Email of user {} is already verified", user.id)));
+        }
+
+        let (secret, token) = self.issue(&user, TokenKind::EmailVerification, self.verification_ttl).await?;
+        let body = format!(
+            "Hello {},\n\nConfirm your email address with {}\n\nThis expires in {}. If you did not sign up, ignore this message.",
+            user.name,
+            self.link("verify-email", &secret),
+            describe(self.verification_ttl)
+        );
+        self.mailer.send(&Email::new(&user.email, "Confirm your email address", &body)).await?;
+
+        Ok(token)
+    }
+
+    /// Confirm an email verification token, marking the user's email verified
+    pub async fn confirm_email(&self, secret: &str) -> Result<User, CoreError> {
+        let (token, mut user) = self.redeemable(secret, TokenKind::EmailVerification).await?;
+        self.spend(token).await?;
+
+        user.email_verified = true;
+        self.users.save(user).await.map_err(database_error)
+    }
+
+    /// Mail a password reset token to the user with the given address
+    ///
+    /// Succeeds without sending anything when no enabled user has the
+    /// address, so callers can't probe for accounts.
+    pub async fn request_password_reset(&self, email: &str) -> Result<(), CoreError> {
+        let users = self.users.find_all().await.map_err(database_error)?;
+        let user = match users.into_iter().find(|u| u.enabled && u.email.eq_ignore_ascii_case(email.trim())) {
+            Some(user) => user,
+            None => {
+                log::info!("Password reset requested for unknown address {}", email);
+                return Ok(());
+            }
+        };
+
+        let (secret, _) = self.issue(&user, TokenKind::PasswordReset, self.reset_ttl).await?;
+        let body = format!(
+            "Hello {},\n\nReset your password with {}\n\nThis expires in {}. If you did not ask for a reset, ignore this message.",
+            user.name,
+            self.link("reset-password", &secret),
+            describe(self.reset_ttl)
+        );
+        self.mailer.send(&Email::new(&user.email, "Reset your password", &body)).await
+    }
+
+    /// Confirm a password reset token, setting a new password
+    ///
+    /// A password the policy rejects leaves the token usable. The reset also
+    /// clears any lockout, marks the email verified since the user proved
+    /// they receive it, and revokes the user's other tokens.
+    pub async fn reset_password(&self, secret: &str, new_password: &str) -> Result<User, CoreError> {
+        let (token, user) = self.redeemable(secret, TokenKind::PasswordReset).await?;
+        let mut user = self.passwords.update_password(user, new_password).await?;
+        self.spend(token).await?;
+
+        user.failed_logins = 0;
+        user.locked_until = None;
+        user.email_verified = true;
+
+        self.revoke_tokens(&user.id, None).await?;
+        self.users.save(user).await.map_err(database_error)
+    }
+
+    /// Disable an active account, revoking its tokens
+    pub async fn disable(&self, user_id: &str, reason: &str) -> Result<User, CoreError> {
+        if reason.trim().is_empty() {
+            return Err(CoreError::Validation("A reason is required to disable an account".to_string()));
+        }
+
+        let mut user = self.find(user_id).await?;
+        if user.status() != AccountStatus::Active {
+            return Err(CoreError::Validation(format!("User {} is {}", user.id, user.status())));
+        }
+
+        user.disable(reason.trim());
+        self.revoke_tokens(&user.id, None).await?;
+        self.users.save(user).await.map_err(database_error)
+    }
+
+    /// Soft-delete an account, revoking its tokens
+    ///
+    /// The account can be reactivated until it is erased, at the end of the
+    /// retention window.
+    pub async fn soft_delete(&self, user_id: &str) -> Result<User, CoreError> {
+        let mut user = self.find(user_id).await?;
+        if user.status() == AccountStatus::Deleted {
+            return Err(CoreError::Validation(format!("User {} is already deleted", user.id)));
+        }
+
+        user.soft_delete();
+        self.revoke_tokens(&user.id, None).await?;
+        self.users.save(user).await.map_err(database_error)
+    }
+
+    /// Reactivate a disabled or soft-deleted account
+    pub async fn reactivate(&self, user_id: &str) -> Result<User, CoreError> {
+        let mut user = self.find(user_id).await?;
+        match user.status() {
+            AccountStatus::Disabled | AccountStatus::Deleted => {}
+            status => return Err(CoreError::Validation(format!("User {} is {}", user.id, status))),
+        }
+
+        user.reactivate();
+        self.users.save(user).await.map_err(database_error)
+    }
+
+    /// Erase an account, replacing it with an anonymous placeholder
+    ///
+    /// Resources the user owned, and ACL entries and history naming them,
+    /// are reassigned to the placeholder, so they keep an owner without
+    /// identifying anyone. The user's tokens and group memberships are
+    /// removed. Returns the placeholder.
+    ///
+    /// The account itself is replaced last, and retrying an erasure that
+    /// failed partway finishes it with the same placeholder.
+    pub async fn erase(&self, user_id: &str) -> Result<User, CoreError> {
+        let user = self.find(user_id).await?;
+        if user.status() == AccountStatus::Erased {
+            return Err(CoreError::Validation(format!("User {} is already erased", user.id)));
+        }
+
+        // Remember the placeholder ID first, so a retry after a failure reuses it
+        let placeholder = match &user.erased_as {
+            Some(id) => id.clone(),
+            None => {
+                let mut user = user.clone();
+                let id = generate_prefixed_id("erased");
+                user.erased_as = Some(id.clone());
+                self.users.save(user).await.map_err(database_error)?;
+                id
+            }
+        };
+
+        for mut resource in self.resources.find_all().await.map_err(database_error)? {
+            if resource.replace_user(&user.id, &placeholder) {
+                self.resources.save(resource).await.map_err(database_error)?;
+            }
+        }
+        self.resources.reassign_revisions(&user.id, &placeholder).await.map_err(database_error)?;
+        for mut group in self.groups.find_by_member(&user.id).await.map_err(database_error)? {
+            group.remove_member(&user.id);
+            self.groups.save(group).await.map_err(database_error)?;
+        }
+        for token in self.tokens.find_by_user(&user.id).await.map_err(database_error)? {
+            self.tokens.delete(&token.id).await.map_err(database_error)?;
+        }
+
+        // Only swap the account for its placeholder once nothing refers to it
+        let erased = self.users.save(user.anonymized(&placeholder)).await.map_err(database_error)?;
+        self.users.delete(&user.id).await.map_err(database_error)?;
+
+        log::info!("Erased user account, now {}", erased.id);
+        Ok(erased)
+    }
+
+    /// Erase soft-deleted accounts whose retention window has passed, returning how many were erased
+    pub async fn purge_deleted(&self) -> Result<usize, CoreError> {
+        let retention = chrono::Duration::from_std(self.retention)
+            .map_err(|e| CoreError::Configuration(format!("Invalid retention window: {}", e)))?;
+        let cutoff = Utc::now() - retention;
+
+        let mut erased = 0;
+        for user in self.users.find_all().await.map_err(database_error)? {
+            // Unreadable deletion times are treated as due
+            let due = user.status() == AccountStatus::Deleted
+                && user.deleted_at.as_deref().is_none_or(|at| {
+                    chrono::DateTime::parse_from_rfc3339(at).map_or(true, |at| at <= cutoff)
+                });
+            if due {
+                self.erase(&user.id).await?;
+                erased += 1;
+            }
+        }
+
+        Ok(erased)
+    }
+
+    // Helper to load a user or fail with NotFound
+    async fn find(&self, user_id: &str) -> Result<User, CoreError> {
+        self.users
+            .find_by_id(&user_id.to_string())
+            .await
+            .map_err(database_error)?
+            .ok_or_else(|| CoreError::NotFound(format!("User {}", user_id)))
+    }
+
+    // Helper to revoke a user's active tokens, optionally only those of one kind
+    async fn revoke_tokens(&self, user_id: &str, kind: Option<TokenKind>) -> Result<(), CoreError> {
+        let now = Utc::now();
+        for mut token in self.tokens.find_by_user(user_id).await.map_err(database_error)? {
+            if kind.is_none_or(|kind| token.kind == kind) && token.is_active_at(now) {
+                token.revoke();
+                self.tokens.save(token).await.map_err(database_error)?;
+            }
+        }
+        Ok(())
+    }
+
+    // Helper to store a new token for a user, revoking outstanding ones of the same kind
+    //
+    // The token is named after the address it is sent to, so it stops working
+    // if the address changes.
+    async fn issue(&self, user: &User, kind: TokenKind, ttl: Duration) -> Result<(String, Token), CoreError> {
+        self.revoke_tokens(&user.id, Some(kind)).await?;
+
+        let now = Utc::now();
+        let ttl = chrono::Duration::from_std(ttl)
+            .map_err(|e| CoreError::Configuration(format!("Invalid token lifetime: {}", e)))?;
+        let secret = generate_secret(kind.secret_prefix());
+        let token = Token::new(&generate_uuid(), &user.id, kind, &secret)
+            .with_name(&user.email)
+            .with_expiry(now + ttl);
+
+        let token = self.tokens.save(token).await.map_err(database_error)?;
+        Ok((secret, token))
+    }
+
+    // Helper to find an unused token of the given kind, with its user
+    async fn redeemable(&self, secret: &str, kind: TokenKind) -> Result<(Token, User), CoreError> {
+        let invalid = || CoreError::Validation(INVALID_TOKEN.to_string());
+
+        let token = self
+            .tokens
+            .find_by_secret_hash(&hash_secret(secret))
+            .await
+            .map_err(database_error)?
+            .filter(|t| t.kind == kind && t.is_active_at(Utc::now()))
+            .ok_or_else(invalid)?;
+
+        let user = self
+            .users
+            .find_by_id(&token.user_id)
+            .await
+            .map_err(database_error)?
+            .ok_or_else(invalid)?;
+        if !user.enabled || token.name.as_deref() != Some(user.email.as_str()) {
+            return Err(invalid());
+        }
+
+        Ok((token, user))
+    }
+
+    // Helper to use up a token so it can't be redeemed again
+    async fn spend(&self, mut token: Token) -> Result<(), CoreError> {
+        token.revoke();
+        self.tokens.save(token).await.map_err(database_error)?;
+        Ok(())
+    }
+
+    // Helper to present a token as a link, or bare if no link base is set
+    fn link(&self, path: &str, secret: &str) -> String {
+        match &self.link_base {
+            Some(base) => format!("{}/{}?token={}", base, path, secret),
+            None => format!("the code {}", secret),
+        }
+    }
+}
+
+// Helper to describe a token lifetime in whole hours or minutes
+fn describe(ttl: Duration) -> String {
+    let minutes = ttl.as_secs() / 60;
+    match (minutes / 60, minutes % 60) {
+        (1, 0) => "1 hour".to_string(),
+        (hours, 0) if hours > 0 => format!("{} hours", hours),
+        _ if minutes == 1 => "1 minute".to_string(),
+        _ => format!("{} minutes", minutes),
+    }
+}
+
+// Helper to report a repository failure
+fn database_error(error: PersistenceError) -> CoreError {
+    CoreError::Database(error.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::credentials::HashingConfig;
+    use crate::core::mail::FileMailSender;
+    use regex::Regex;
+
+    struct Setup {
+        accounts: AccountService,
+        factory: RepositoryFactory,
+        outbox: FileMailSender,
+    }
+
+    async fn setup() -> Setup {
+        let factory = RepositoryFactory::new_in_memory();
+        factory.user_repository().save(User::new("alice", "alice@example.com", "Alice")).await.unwrap();
+
+        let outbox = FileMailSender::new(std::env::temp_dir().join(format!("outbox-{}", generate_uuid())));
+        let passwords = PasswordManager::new().with_hashing(HashingConfig { memory_kib: 64, iterations: 1, parallelism: 1 });
+        let accounts = AccountService::new(&factory, Arc::new(outbox.clone())).with_password_manager(passwords);
+        Setup { accounts, factory, outbox }
+    }
+
+    // Read the secrets with a prefix out of every message sent so far, oldest first
+    fn sent_secrets(outbox: &FileMailSender, prefix: &str) -> Vec<String> {
+        let pattern = Regex::new(&format!("{}[0-9a-f]{{64}}", prefix)).unwrap();
+        let mut files: Vec<_> = std::fs::read_dir(outbox.dir())
+            .map(|entries| entries.map(|e| e.unwrap().path()).collect())
+            .unwrap_or_default();
+        files.sort();
+        files
+            .iter()
+            .filter_map(|path| pattern.find(&std::fs::read_to_string(path).unwrap()).map(|m| m.as_str().to_string()))
+            .collect()
+    }
+
+    async fn user(factory: &RepositoryFactory) -> User {
+        factory.user_repository().find_by_id(&"alice".to_string()).await.unwrap().unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_email_verification() {
+        let Setup { accounts, factory, outbox } = setup().await;
+        let accounts = accounts.with_link_base("https://app.example.com/");
+
+        accounts.request_email_verification("alice").await.unwrap();
+        let mail = std::fs::read_to_string(std::fs::read_dir(outbox.dir()).unwrap().next().unwrap().unwrap().path()).unwrap();
+        assert!(mail.starts_with("To: alice@example.com\r\n"));
+        assert!(mail.contains("https://app.example.com/verify-email?token=evt_"));
+        assert!(mail.contains("expires in 24 hours"));
+
+        // Requesting again revokes the first token
+        accounts.request_email_verification("alice").await.unwrap();
+        let secrets = sent_secrets(&outbox, "evt_");
+        assert!(accounts.confirm_email(&secrets[0]).await.is_err());
+
+        let verified = accounts.confirm_email(&secrets[1]).await.unwrap();
+        assert!(verified.email_verified);
+        assert!(user(&factory).await.email_verified);
+        assert!(matches!(accounts.confirm_email(&secrets[1]).await, Err(CoreError::Validation(_))));
+        assert!(accounts.request_email_verification("alice").await.is_err());
+
+        // Tokens stop working when the address changes
+        let changed = User { email: "alice@example.org".to_string(), email_verified: false, ..user(&factory).await };
+        factory.user_repository().save(changed).await.unwrap();
+        accounts.request_email_verification("alice").await.unwrap();
+        let moved = User { email: "alice@example.net".to_string(), ..user(&factory).await };
+        factory.user_repository().save(moved).await.unwrap();
+        let latest = sent_secrets(&outbox, "evt_").pop().unwrap();
+        assert!(accounts.confirm_email(&latest).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_password_reset() {
+        let Setup { accounts, factory, outbox } = setup().await;
+        let sessions = crate::core::session::SessionManager::new(&factory);
+        let session = sessions.issue_session(&user(&factory).await).await.unwrap();
+
+        accounts.request_password_reset("nobody@example.com").await.unwrap();
+        assert!(sent_secrets(&outbox, "prt_").is_empty());
+
+        accounts.request_password_reset("Alice@Example.com").await.unwrap();
+        let secret = sent_secrets(&outbox, "prt_").pop().unwrap();
+
+        // Reset tokens can't be used to authenticate, or to verify an email
+        assert!(sessions.resolve(&secret).await.is_err());
+        assert!(accounts.confirm_email(&secret).await.is_err());
+
+        // A rejected password leaves the token usable, but only once
+        let weak = accounts.reset_password(&secret, "short").await;
+        assert!(matches!(weak, Err(CoreError::Validation(msg)) if msg.contains("password")));
+        let reset = accounts.reset_password(&secret, "Correct-Horse-42").await.unwrap();
+        assert!(reset.has_password());
+        assert!(reset.email_verified);
+        assert!(sessions.resolve(&session.secret).await.is_err());
+        assert!(accounts.reset_password(&secret, "Another-Horse-43").await.is_err());
+
+        let expiring = AccountService::new(&factory, Arc::new(outbox.clone())).with_reset_ttl(Duration::ZERO);
+        expiring.request_password_reset("alice@example.com").await.unwrap();
+        let secret = sent_secrets(&outbox, "prt_").pop().unwrap();
+        assert!(expiring.reset_password(&secret, "Another-Horse-43").await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_account_lifecycle() {
+        use crate::models::{AclEntry, Group, Permission, Principal, Resource, ResourceData, ResourceType};
+
+        let Setup { accounts, factory, .. } = setup().await;
+        let passwords = PasswordManager::new().with_hashing(HashingConfig { memory_kib: 64, iterations: 1, parallelism: 1 });
+        let mut alice = user(&factory).await;
+        passwords.set_password(&mut alice, "Correct-Horse-42").unwrap();
+        factory.user_repository().save(alice.clone()).await.unwrap();
+        let sessions = crate::core::session::SessionManager::new(&factory);
+        let session = sessions.issue_session(&alice).await.unwrap();
+        let users = factory.user_repository();
+
+        // Disabled accounts lose their sessions and are refused logins
+        assert!(accounts.disable("alice", " ").await.is_err());
+        let disabled = accounts.disable("alice", "Chargeback").await.unwrap();
+        assert_eq!(disabled.status(), AccountStatus::Disabled);
+        assert_eq!(disabled.disabled_reason.as_deref(), Some("Chargeback"));
+        assert!(sessions.resolve(&session.secret).await.is_err());
+        let refused = passwords.login(users.as_ref(), "alice", "Correct-Horse-42").await;
+        assert!(matches!(refused, Err(CoreError::PermissionDenied(msg)) if msg.contains("disabled")));
+
+        accounts.reactivate("alice").await.unwrap();
+        assert!(passwords.login(users.as_ref(), "alice", "Correct-Horse-42").await.is_ok());
+        assert!(accounts.reactivate("alice").await.is_err());
+
+        // Soft-deleted accounts are kept until the retention window passes
+        let mut plan = Resource::new("prj-1", ResourceData::new("Plan", ResourceType::Project)).with_owner("alice");
+        plan.updated_by = Some("alice".to_string());
+        plan.grant(AclEntry::new(Principal::User("alice".to_string()), [Permission::ReadResource]), "alice");
+        factory.resource_repository().save(plan).await.unwrap();
+        factory.group_repository().save(Group::team("ops", "Ops").with_member("alice")).await.unwrap();
+
+        let deleted = accounts.soft_delete("alice").await.unwrap();
+        assert_eq!(deleted.status(), AccountStatus::Deleted);
+        assert!(passwords.login(users.as_ref(), "alice", "Correct-Horse-42").await.is_err());
+        assert_eq!(accounts.purge_deleted().await.unwrap(), 0);
+
+        let accounts = accounts.with_retention(Duration::ZERO);
+        assert_eq!(accounts.purge_deleted().await.unwrap(), 1);
+        assert!(users.find_by_id(&"alice".to_string()).await.unwrap().is_none());
+
+        // Owned resources keep an owner, but no longer point at the person
+        let plan = factory.resource_repository().find_by_id(&"prj-1".to_string()).await.unwrap().unwrap();
+        let placeholder = plan.owner_id.clone().unwrap();
+        assert!(placeholder.starts_with("erased-"));
+        assert_eq!(plan.acl[0].principal, Principal::User(placeholder.clone()));
+        assert_eq!(plan.acl_log[0].actor, placeholder);
+        assert!(!serde_json::to_string(&plan).unwrap().contains("alice"));
+        let history = factory.resource_repository().revisions("prj-1").await.unwrap();
+        assert_eq!(history[0].changed_by.as_deref(), Some(placeholder.as_str()));
+
+        let erased = users.find_by_id(&placeholder).await.unwrap().unwrap();
+        assert_eq!(erased.status(), AccountStatus::Erased);
+        assert!(!erased.has_password() && !erased.email.contains("alice"));
+        assert!(factory.group_repository().find_by_member("alice").await.unwrap().is_empty());
+        assert!(sessions.tokens_for("alice").await.unwrap().is_empty());
+        assert!(matches!(accounts.reactivate("alice").await, Err(CoreError::NotFound(_))));
+    }
+
+    #[tokio::test]
+    async fn test_interrupted_erasure_is_retried() {
+        use crate::models::{AclEntry, Group, Permission, Principal, Resource, ResourceData, ResourceType};
+        use std::sync::atomic::{AtomicBool, Ordering};
+
+        // Group repository whose next save fails once
+        struct Flaky(Arc<dyn GroupRepository + Send + Sync>, AtomicBool);
+
+        #[async_trait::async_trait]
+        impl Repository<Group, String> for Flaky {
+            async fn save(&self, group: Group) -> Result<Group, PersistenceError> {
+                if self.1.swap(false, Ordering::SeqCst) {
+                    return Err(PersistenceError::ConnectionError("Connection reset".to_string()));
+                }
+                self.0.save(group).await
+            }
+            async fn find_by_id(&self, id: &String) -> Result<Option<Group>, PersistenceError> {
+                self.0.find_by_id(id).await
+            }
+            async fn delete(&self, id: &String) -> Result<bool, PersistenceError> {
+                self.0.delete(id).await
+            }
+            async fn find_all(&self) -> Result<Vec<Group>, PersistenceError> {
+                self.0.find_all().await
+            }
+            async fn count(&self) -> Result<usize, PersistenceError> {
+                self.0.count().await
+            }
+        }
+
+        #[async_trait::async_trait]
+        impl GroupRepository for Flaky {
+            async fn find_by_member(&self, user_id: &str) -> Result<Vec<Group>, PersistenceError> {
+                self.0.find_by_member(user_id).await
+            }
+        }
+
+        let Setup { mut accounts, factory, .. } = setup().await;
+        accounts.groups = Arc::new(Flaky(factory.group_repository(), AtomicBool::new(true)));
+        let resources = factory.resource_repository();
+        let users = factory.user_repository();
+        let mut plan = Resource::new("prj-1", ResourceData::new("Plan", ResourceType::Project)).with_owner("alice");
+        plan.updated_by = Some("alice".to_string());
+        plan.grant(AclEntry::new(Principal::User("alice".to_string()), [Permission::ReadResource]), "alice");
+        let mut plan = resources.save(plan).await.unwrap();
+        plan.data.name = "Plan v2".to_string();
+        resources.save(plan).await.unwrap();
+        factory.group_repository().save(Group::team("ops", "Ops").with_member("alice")).await.unwrap();
+
+        // A failure partway keeps the account, and no placeholder account exists yet
+        assert!(matches!(accounts.erase("alice").await, Err(CoreError::Database(_))));
+        assert_eq!(users.count().await.unwrap(), 1);
+        let placeholder = user(&factory).await.erased_as.unwrap();
+
+        let erased = accounts.erase("alice").await.unwrap();
+        assert_eq!(erased.id, placeholder);
+        let remaining: Vec<String> = users.find_all().await.unwrap().into_iter().map(|u| u.id).collect();
+        assert_eq!(remaining, vec![erased.id]);
+
+        // Nothing left names the user: owned resources, their ACL history, or revisions
+        let plan = resources.find_by_id(&"prj-1".to_string()).await.unwrap().unwrap();
+        assert_eq!(plan.owner_id.as_deref(), Some(placeholder.as_str()));
+        assert!(plan.acl_log.iter().all(|change| change.actor == placeholder));
+        assert!(!serde_json::to_string(&plan).unwrap().contains("alice"));
+        let history = resources.revisions("prj-1").await.unwrap();
+        assert_eq!(history.len(), 2);
+        assert!(!serde_json::to_string(&history).unwrap().contains("alice"));
+        assert!(factory.group_repository().find_by_member("alice").await.unwrap().is_empty());
+    }
+}
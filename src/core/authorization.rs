@@ -0,0 +1,280 @@
+use crate::models::{Group, Permission, Resource, User};
+use async_trait::async_trait;
+use std::sync::Arc;
+
+use super::error::CoreError;
+use super::policy::Policy;
+use super::service::Service;
+
+/// Authorization layer around a resource service, acting on behalf of a user
+///
+/// Every operation is checked against a `Policy`, by default the built-in
+/// one, including the scope of the grant and the ACL of the resource
+/// involved. Listings only contain the resources the user may read, so a page
+/// can hold fewer results than its limit. Checks take the user's groups into
+/// account once they are provided with `with_groups`.
+pub struct AuthorizedService {
+    inner: Arc<dyn Service<Resource> + Send + Sync>,
+    user: User,
+    groups: Vec<Group>,
+    policy: Arc<Policy>,
+}
+
+impl AuthorizedService {
+    /// Wrap a service so that every call is checked against the user's permissions
+    pub fn new(inner: Arc<dyn Service<Resource> + Send + Sync>, user: User) -> Self {
+        Self {
+            inner,
+            user,
+            groups: Vec::new(),
+            policy: Arc::new(Policy::default()),
+        }
+    }
+
+    /// Evaluate checks against the given policy
+    pub fn with_policy(mut self, policy: Arc<Policy>) -> Self {
+        self.policy = policy;
+        self
+    }
+
+    /// Act as a member of the given groups, e.g. from `GroupRepository::find_by_member`
+    pub fn with_groups(mut self, groups: Vec<Group>) -> Self {
+        self.groups = groups;
+        self
+    }
+
+    /// Get the acting user
+    pub fn user(&self) -> &User {
+        &self.user
+    }
+
+    /// Check if the acting user may read a resource
+    pub fn can_read(&self, resource: &Resource) -> bool {
+        self.policy
+            .evaluate_with_groups(&self.user, &self.groups, &Permission::ReadResource, Some(resource))
+            .is_allowed()
+    }
+
+    // Helper to check a permission, optionally on a resource
+    fn check(&self, permission: Permission, resource: Option<&Resource>) -> Result<(), CoreError> {
+        self.policy.check_with_groups(&self.user, &self.groups, &permission, resource)
+    }
+
+    // Helper to fetch a stored resource and check a permission on it
+    //
+    // ACL entries can grant what the user's role does not, so the role alone
+    // only decides when the resource can't be fetched.
+    async fn fetch_checked(&self, id: &str, permission: Permission) -> Result<Resource, CoreError> {
+        self.checked(self.inner.get(id).await, permission)
+    }
+
+    // Helper to check a permission on the outcome of a fetch
+    fn checked(&self, fetched: Result<Resource, CoreError>, permission: Permission) -> Result<Resource, CoreError> {
+        let resource = match fetched {
+            Ok(resource) => resource,
+            Err(e) => return Err(self.check(permission, None).err().unwrap_or(e)),
+        };
+        self.check(permission, Some(&resource))?;
+
+        Ok(resource)
+    }
+
+    // Helper to keep the listed resources the user may read
+    //
+    // Without read access, only resources shared through ACL entries are listed.
+    fn readable(&self, resources: Vec<Resource>) -> Result<Vec<Resource>, CoreError> {
+        let denied = self.check(Permission::ReadResource, None).err();
+        let readable: Vec<Resource> = resources.into_iter().filter(|r| self.can_read(r)).collect();
+        match denied {
+            Some(e) if readable.is_empty() => Err(e),
+            _ => Ok(readable),
+        }
+    }
+}
+
+#[async_trait]
+impl Service<Resource> for AuthorizedService {
+    async fn create(&self, mut resource: Resource) -> Result<Resource, CoreError> {
+        // New resources belong to the acting user unless assigned explicitly
+        if resource.owner_id.is_none() {
+            resource.owner_id = Some(self.user.id.clone());
+        }
+        resource.updated_by = Some(self.user.id.clone());
+        self.check(Permission::CreateResource, Some(&resource.without_acl()))?;
+
+        self.inner.create(resource).await
+    }
+
+    async fn get(&self, id: &str) -> Result<Resource, CoreError> {
+        self.fetch_checked(id, Permission::ReadResource).await
+    }
+
+    async fn update(&self, id: &str, mut resource: Resource) -> Result<Resource, CoreError> {
+        // The stored resource decides, and a new owner must be covered too,
+        // without help from ACL entries
+        let existing = self.fetch_checked(id, Permission::UpdateResource).await?;
+        if !resource.has_same_owners(&existing) {
+            self.check(Permission::UpdateResource, Some(&resource.without_acl()))?;
+        }
+        resource.updated_by = Some(self.user.id.clone());
+
+        self.inner.update(id, resource).await
+    }
+
+    async fn delete(&self, id: &str) -> Result<bool, CoreError> {
+        self.fetch_checked(id, Permission::DeleteResource).await?;
+
+        self.inner.delete(id).await
+    }
+
+    async fn list(&self, limit: Option<usize>, filter: Option<&str>) -> Result<Vec<Resource>, CoreError> {
+        self.readable(self.inner.list(limit, filter).await?)
+    }
+
+    async fn get_uncached(&self, id: &str) -> Result<Resource, CoreError> {
+        self.checked(self.inner.get_uncached(id).await, Permission::ReadResource)
+    }
+
+    async fn list_uncached(&self) -> Result<Vec<Resource>, CoreError> {
+        self.readable(self.inner.list_uncached().await?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{AclEntry, Principal, ResourceData, ResourceType, UserRole};
+    use std::collections::HashMap;
+    use tokio::sync::Mutex;
+
+    /// Service stand-in keeping resources in memory
+    #[derive(Default)]
+    struct MemoryService {
+        resources: Mutex<HashMap<String, Resource>>,
+    }
+
+    #[async_trait]
+    impl Service<Resource> for MemoryService {
+        async fn create(&self, data: Resource) -> Result<Resource, CoreError> {
+            self.resources.lock().await.insert(data.id.clone(), data.clone());
+            Ok(data)
+        }
+
+        async fn get(&self, id: &str) -> Result<Resource, CoreError> {
+            self.resources.lock().await.get(id).cloned().ok_or_else(|| CoreError::NotFound(id.to_string()))
+        }
+
+        async fn update(&self, id: &str, data: Resource) -> Result<Resource, CoreError> {
+            self.resources.lock().await.insert(id.to_string(), data.clone());
+            Ok(data)
+        }
+
+        async fn delete(&self, id: &str) -> Result<bool, CoreError> {
+            Ok(self.resources.lock().await.remove(id).is_some())
+        }
+
+        async fn list(&self, _limit: Option<usize>, _filter: Option<&str>) -> Result<Vec<Resource>, CoreError> {
+            Ok(self.resources.lock().await.values().cloned().collect())
+        }
+    }
+
+    fn editor(id: &str) -> User {
+        User::new(id, &format!("{}@example.com", id), id)
+    }
+
+    fn project(id: &str) -> Resource {
+        Resource::new(id, ResourceData::new(id, ResourceType::Project))
+    }
+
+    fn denied<T>(result: Result<T, CoreError>, needle: &str) -> bool {
+        matches!(result, Err(CoreError::PermissionDenied(msg)) if msg.contains(needle))
+    }
+
+    #[tokio::test]
+    async fn test_ownership_enforced() {
+        let inner: Arc<dyn Service<Resource> + Send + Sync> = Arc::new(MemoryService::default());
+        let alice = AuthorizedService::new(inner.clone(), editor("alice"));
+        let bob = AuthorizedService::new(inner.clone(), editor("bob"));
+        let admin = AuthorizedService::new(inner.clone(), User::new("root", "root@example.com", "Root").with_role(UserRole::Admin));
+
+        let created = alice.create(project("prj-1")).await.unwrap();
+        assert_eq!(created.owner_id, Some("alice".to_string()));
+        inner.create(project("prj-shared")).await.unwrap();
+
+        assert!(denied(bob.get("prj-1").await, "does not own"));
+        assert!(denied(bob.update("prj-1", created.clone()).await, "does not own"));
+        assert!(denied(bob.delete("prj-1").await, "does not own"));
+        assert!(denied(bob.create(project("prj-2").with_owner("alice")).await, "does not own"));
+
+        // Taking over a resource by rewriting its owner is rejected
+        let hijacked = created.clone().with_owner("bob");
+        assert!(denied(alice.update("prj-1", hijacked).await, "does not own"));
+
+        let mut listed: Vec<String> = bob.list(None, None).await.unwrap().into_iter().map(|r| r.id).collect();
+        listed.sort();
+        assert_eq!(listed, vec!["prj-shared"]);
+        assert_eq!(alice.list(None, None).await.unwrap().len(), 2);
+
+        assert!(admin.delete("prj-1").await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_missing_permission_is_named() {
+        let inner: Arc<dyn Service<Resource> + Send + Sync> = Arc::new(MemoryService::default());
+        let reader = User::new("reader", "reader@example.com", "Reader").with_role(UserRole::ReadOnly);
+        let service = AuthorizedService::new(inner, reader);
+
+        assert!(denied(service.create(project("prj-1")).await, "create_resource"));
+        assert!(denied(service.delete("prj-1").await, "delete_resource"));
+        assert!(service.list(None, None).await.unwrap().is_empty());
+
+        let mut disabled = editor("carol");
+        disabled.enabled = false;
+        let service = AuthorizedService::new(Arc::new(MemoryService::default()), disabled);
+        assert!(denied(service.list(None, None).await, "disabled"));
+    }
+
+    #[tokio::test]
+    async fn test_group_owned_resources() {
+        let inner: Arc<dyn Service<Resource> + Send + Sync> = Arc::new(MemoryService::default());
+        let team = vec![Group::team("design", "Design").with_member("dave")];
+        let dave = AuthorizedService::new(inner.clone(), editor("dave")).with_groups(team);
+        let erin = AuthorizedService::new(inner.clone(), editor("erin"));
+
+        inner.create(project("prj-team").with_owner_group("design")).await.unwrap();
+        inner.create(project("prj-shared").with_owner("erin").shared_with("design")).await.unwrap();
+
+        let mut renamed = dave.get("prj-team").await.unwrap();
+        renamed.data.name = "Renamed".to_string();
+        assert!(dave.update("prj-team", renamed.clone()).await.is_ok());
+        assert!(denied(dave.update("prj-team", renamed.with_owner_group("other")).await, "does not own"));
+        assert!(denied(erin.get("prj-team").await, "does not own"));
+
+        // Shared resources are readable but stay under their owner's control
+        assert_eq!(dave.list(None, None).await.unwrap().len(), 2);
+        assert!(denied(dave.delete("prj-shared").await, "does not own"));
+    }
+
+    #[tokio::test]
+    async fn test_acl_grants_access() {
+        let inner: Arc<dyn Service<Resource> + Send + Sync> = Arc::new(MemoryService::default());
+        let guest = User::new("guest", "guest@example.com", "Guest").with_role(UserRole::Custom("visitor".to_string()));
+        let service = AuthorizedService::new(inner.clone(), guest);
+
+        let mut plan = project("prj-1").with_owner("alice");
+        plan.grant(AclEntry::new(Principal::User("guest".to_string()), [Permission::ReadResource, Permission::UpdateResource]), "alice");
+        inner.create(plan).await.unwrap();
+        inner.create(project("prj-2").with_owner("alice")).await.unwrap();
+
+        // The role grants nothing, but the ACL does for this one resource
+        let mut fetched = service.get("prj-1").await.unwrap();
+        assert!(denied(service.get("prj-2").await, "missing permission read_resource"));
+        assert_eq!(service.list(None, None).await.unwrap().len(), 1);
+
+        fetched.data.name = "Renamed".to_string();
+        assert!(service.update("prj-1", fetched.clone()).await.is_ok());
+        assert!(denied(service.update("prj-1", fetched.with_owner("guest")).await, "missing permission update_resource"));
+        assert!(denied(service.delete("prj-1").await, "missing permission delete_resource"));
+        assert!(denied(service.get("prj-missing").await, "missing permission read_resource"));
+    }
+}
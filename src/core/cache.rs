@@ -0,0 +1,400 @@
+use crate::CacheConfig;
+use futures::future::{BoxFuture, FutureExt, Shared};
+use serde::{de::DeserializeOwned, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::future::Future;
+use std::hash::Hash;
+use std::time::{Duration, Instant};
+
+use super::disk_cache::DiskCache;
+
+/// Outcome of a cache lookup
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CacheLookup<V> {
+    /// Value is cached and fresh
+    Hit(V),
+    /// Value is expired but may be served while it is refreshed
+    Stale(V),
+    /// Key is known not to exist upstream
+    NotFound,
+    /// Nothing usable is cached
+    Miss,
+}
+
+/// Storage behind a keyed cache
+///
+/// Implementations follow the same semantics: values expire after their time
+/// to live, stay available as `Stale` for a configured window, and the least
+/// recently used entries are evicted once the backend is full.
+pub trait CacheBackend<K, V>: Send {
+    /// Look up a key, marking it as recently used
+    fn get(&mut self, key: &K) -> CacheLookup<V>;
+
+    /// Cache a value for the given time to live
+    fn insert(&mut self, key: K, value: V, ttl: Duration);
+
+    /// Record that a key does not exist for the given time to live
+    fn insert_missing(&mut self, key: K, ttl: Duration);
+
+    /// Remove a key from the cache
+    fn remove(&mut self, key: &K) -> bool;
+
+    /// Remove all entries
+    fn clear(&mut self);
+
+    /// Get the number of stored entries
+    fn len(&self) -> usize;
+
+    /// Check if the cache is empty
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// Open the backend for a cache namespace according to the cache policy
+///
+/// With a cache directory configured, the namespace is kept on disk in a
+/// subdirectory of that name. If the directory cannot be used, or none is
+/// configured, the namespace is kept in memory.
+pub fn open_backend<K, V>(config: &CacheConfig, namespace: &str) -> Box<dyn CacheBackend<K, V>>
+where
+    K: Eq + Hash + Clone + Send + Serialize + 'static,
+    V: Clone + Send + Serialize + DeserializeOwned + 'static,
+{
+    if let Some(dir) = &config.dir {
+        let path = dir.join(namespace);
+        match DiskCache::open(&path, config.capacity) {
+            Ok(cache) => {
+                return Box::new(
+                    cache
+                        .with_max_bytes(config.max_disk_bytes)
+                        .with_stale_window(config.stale_while_revalidate),
+                )
+            }
+            Err(e) => log::warn!("Failed to open cache directory {}, caching in memory: {}", path.display(), e),
+        }
+    }
+
+    Box::new(LruCache::new(config.capacity).with_stale_window(config.stale_while_revalidate))
+}
+
+/// Cache entry with its expiry and recency
+struct CacheEntry<V> {
+    value: Option<V>,
+    expires_at: Instant,
+    tick: u64,
+}
+
+/// Bounded cache with per-entry TTL and least-recently-used eviction
+///
+/// Entries can also record that a key does not exist, so repeated lookups of
+/// missing keys are answered without a round trip. Expired values stay
+/// available as `Stale` for the configured stale window.
+pub struct LruCache<K, V> {
+    entries: HashMap<K, CacheEntry<V>>,
+    recency: BTreeMap<u64, K>,
+    tick: u64,
+    capacity: usize,
+    stale_window: Duration,
+}
+
+impl<K, V> LruCache<K, V>
+where
+    K: Eq + Hash + Clone,
+    V: Clone,
+{
+    /// Create a new cache holding at most `capacity` entries
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            entries: HashMap::new(),
+            recency: BTreeMap::new(),
+            tick: 0,
+            capacity,
+            stale_window: Duration::ZERO,
+        }
+    }
+
+    /// Keep expired values available as `Stale` for the given window
+    pub fn with_stale_window(mut self, stale_window: Duration) -> Self {
+        self.stale_window = stale_window;
+        self
+    }
+
+    /// Look up a key, marking it as recently used
+    pub fn get(&mut self, key: &K) -> CacheLookup<V> {
+        let tick = self.next_tick();
+
+        let entry = match self.entries.get_mut(key) {
+            Some(entry) => entry,
+            None => return CacheLookup::Miss,
+        };
+
+        let now = Instant::now();
+        let fresh = entry.expires_at > now;
+        if !fresh && (entry.value.is_none() || entry.expires_at + self.stale_window <= now) {
+            self.remove(key);
+            return CacheLookup::Miss;
+        }
+
+        self.recency.remove(&entry.tick);
+        self.recency.insert(tick, key.clone());
+        entry.tick = tick;
+
+        match &entry.value {
+            Some(value) if fresh => CacheLookup::Hit(value.clone()),
+            Some(value) => CacheLookup::Stale(value.clone()),
+            None => CacheLookup::NotFound,
+        }
+    }
+
+    /// Cache a value for the given time to live
+    pub fn insert(&mut self, key: K, value: V, ttl: Duration) {
+        self.put(key, Some(value), ttl);
+    }
+
+    /// Record that a key does not exist for the given time to live
+    pub fn insert_missing(&mut self, key: K, ttl: Duration) {
+        self.put(key, None, ttl);
+    }
+
+    /// Remove a key from the cache
+    pub fn remove(&mut self, key: &K) -> bool {
+        match self.entries.remove(key) {
+            Some(entry) => {
+                self.recency.remove(&entry.tick);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Remove all entries
+    pub fn clear(&mut self) {
+        self.entries.clear();
+        self.recency.clear();
+    }
+
+    /// Get the number of entries, including expired ones not yet evicted
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Check if the cache is empty
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    // Helper to insert an entry, evicting the least recently used one if full
+    fn put(&mut self, key: K, value: Option<V>, ttl: Duration) {
+        if self.capacity == 0 {
+            return;
+        }
+
+        self.remove(&key);
+        while self.entries.len() >= self.capacity {
+            match self.recency.pop_first() {
+                Some((_, oldest)) => {
+                    self.entries.remove(&oldest);
+                }
+                None => break,
+            }
+        }
+
+        let tick = self.next_tick();
+        self.recency.insert(tick, key.clone());
+        self.entries.insert(
+            key,
+            CacheEntry {
+                value,
+                expires_at: Instant::now() + ttl,
+                tick,
+            },
+        );
+    }
+
+    // Helper to advance the recency counter
+    fn next_tick(&mut self) -> u64 {
+        self.tick += 1;
+        self.tick
+    }
+}
+
+impl<K, V> CacheBackend<K, V> for LruCache<K, V>
+where
+    K: Eq + Hash + Clone + Send,
+    V: Clone + Send,
+{
+    fn get(&mut self, key: &K) -> CacheLookup<V> {
+        LruCache::get(self, key)
+    }
+
+    fn insert(&mut self, key: K, value: V, ttl: Duration) {
+        LruCache::insert(self, key, value, ttl)
+    }
+
+    fn insert_missing(&mut self, key: K, ttl: Duration) {
+        LruCache::insert_missing(self, key, ttl)
+    }
+
+    fn remove(&mut self, key: &K) -> bool {
+        LruCache::remove(self, key)
+    }
+
+    fn clear(&mut self) {
+        LruCache::clear(self)
+    }
+
+    fn len(&self) -> usize {
+        LruCache::len(self)
+    }
+}
+
+/// Deduplicates concurrent computations of the same key
+///
+/// While a computation for a key is in flight, further callers for that key
+/// wait for it and receive a clone of its output instead of starting their own.
+pub struct SingleFlight<K, V> {
+    in_flight: std::sync::Mutex<HashMap<K, Shared<BoxFuture<'static, V>>>>,
+}
+
+impl<K, V> SingleFlight<K, V>
+where
+    K: Eq + Hash + Clone,
+    V: Clone + Send + Sync + 'static,
+{
+    /// Create a new empty group
+    pub fn new() -> Self {
+        Self {
+            in_flight: std::sync::Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Run `operation` for `key`, or join the computation already in flight
+    pub async fn run<F, Fut>(&self, key: K, operation: F) -> V
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = V> + Send + 'static,
+    {
+        let flight = {
+            let mut in_flight = self.in_flight.lock().expect("single-flight lock poisoned");
+            in_flight
+                .entry(key.clone())
+                .or_insert_with(|| operation().boxed().shared())
+                .clone()
+        };
+
+        let output = flight.clone().await;
+
+        // Whichever waiter finishes first retires the flight
+        let mut in_flight = self.in_flight.lock().expect("single-flight lock poisoned");
+        if in_flight.get(&key).is_some_and(|current| current.ptr_eq(&flight)) {
+            in_flight.remove(&key);
+        }
+
+        output
+    }
+
+    /// Check if a computation for `key` is in flight
+    pub fn is_in_flight(&self, key: &K) -> bool {
+        self.in_flight
+            .lock()
+            .expect("single-flight lock poisoned")
+            .contains_key(key)
+    }
+}
+
+impl<K, V> Default for SingleFlight<K, V>
+where
+    K: Eq + Hash + Clone,
+    V: Clone + Send + Sync + 'static,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TTL: Duration = Duration::from_secs(60);
+
+    #[test]
+    fn test_hit_miss_and_negative_entries() {
+        let mut cache = LruCache::new(10);
+        cache.insert("a", 1, TTL);
+        cache.insert_missing("b", TTL);
+
+        assert_eq!(cache.get(&"a"), CacheLookup::Hit(1));
+        assert_eq!(cache.get(&"b"), CacheLookup::NotFound);
+        assert_eq!(cache.get(&"c"), CacheLookup::Miss);
+
+        assert!(cache.remove(&"a"));
+        assert_eq!(cache.get(&"a"), CacheLookup::Miss);
+    }
+
+    #[test]
+    fn test_entries_expire() {
+        let mut cache = LruCache::new(10);
+        cache.insert("a", 1, Duration::ZERO);
+        cache.insert("b", 2, TTL);
+
+        assert_eq!(cache.get(&"a"), CacheLookup::Miss);
+        assert_eq!(cache.get(&"b"), CacheLookup::Hit(2));
+        assert_eq!(cache.len(), 1);
+    }
+
+    #[test]
+    fn test_stale_window() {
+        let mut cache = LruCache::new(10).with_stale_window(TTL);
+        cache.insert("a", 1, Duration::ZERO);
+        cache.insert_missing("b", Duration::ZERO);
+
+        assert_eq!(cache.get(&"a"), CacheLookup::Stale(1));
+        assert_eq!(cache.get(&"b"), CacheLookup::Miss);
+    }
+
+    #[tokio::test]
+    async fn test_single_flight_shares_result() {
+        let flights = std::sync::Arc::new(SingleFlight::new());
+        let calls = std::sync::Arc::new(std::sync::atomic::AtomicUsize::new(0));
+
+        let tasks: Vec<_> = (0..10)
+            .map(|_| {
+                let flights = flights.clone();
+                let calls = calls.clone();
+                tokio::spawn(async move {
+                    flights
+                        .run("key", || async move {
+                            calls.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+                            tokio::time::sleep(Duration::from_millis(50)).await;
+                            42
+                        })
+                        .await
+                })
+            })
+            .collect();
+
+        for task in tasks {
+            assert_eq!(task.await.unwrap(), 42);
+        }
+        assert_eq!(calls.load(std::sync::atomic::Ordering::SeqCst), 1);
+        assert!(!flights.is_in_flight(&"key"));
+    }
+
+    #[test]
+    fn test_least_recently_used_is_evicted() {
+        let mut cache = LruCache::new(2);
+        cache.insert("a", 1, TTL);
+        cache.insert("b", 2, TTL);
+
+        // Touch "a" so "b" becomes the eviction candidate
+        cache.get(&"a");
+        cache.insert("c", 3, TTL);
+
+        assert_eq!(cache.len(), 2);
+        assert_eq!(cache.get(&"a"), CacheLookup::Hit(1));
+        assert_eq!(cache.get(&"b"), CacheLookup::Miss);
+        assert_eq!(cache.get(&"c"), CacheLookup::Hit(3));
+    }
+}
@@ -0,0 +1,731 @@
+use crate::models::{Resource, ResourceType};
+use chrono::{DateTime, NaiveDate, Utc};
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+use serde_json::Value;
+use std::borrow::Cow;
+use std::cmp::Ordering;
+use std::fmt;
+use std::iter::Peekable;
+use std::str::{CharIndices, FromStr};
+
+use super::error::CoreError;
+
+/// Characters that start a comparison operator in the text syntax
+const OPERATOR_CHARS: &[char] = &[':', '=', '!', '<', '>', '~'];
+
+/// Longest query accepted in the text syntax, in bytes
+const MAX_QUERY_LENGTH: usize = 4096;
+
+/// Deepest nesting of groups and negations accepted in the text syntax
+const MAX_QUERY_DEPTH: usize = 32;
+
+/// Resource field a condition or projection refers to
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Field {
+    /// Resource ID
+    Id,
+    /// Resource name
+    Name,
+    /// Resource type
+    Type,
+    /// Owner user ID
+    Owner,
+    /// Resource description
+    Description,
+    /// Creation timestamp
+    CreatedAt,
+    /// Last update timestamp
+    UpdatedAt,
+    /// Entry of the resource data map
+    Data(String),
+    /// Entry of the resource metadata map
+    Metadata(String),
+}
+
+impl Field {
+    /// Get the value of the field for a resource, if it has one
+    pub fn value<'a>(&self, resource: &'a Resource) -> Option<Cow<'a, str>> {
+        match self {
+            Field::Id => Some(Cow::Borrowed(&resource.id)),
+            Field::Name => Some(Cow::Borrowed(&resource.data.name)),
+            Field::Type => Some(Cow::Owned(resource.data.resource_type.to_string())),
+            Field::Owner => resource.owner_id.as_deref().map(Cow::Borrowed),
+            Field::Description => resource.data.description.as_deref().map(Cow::Borrowed),
+            Field::CreatedAt => Some(Cow::Borrowed(&resource.created_at)),
+            Field::UpdatedAt => Some(Cow::Borrowed(&resource.updated_at)),
+            Field::Data(key) => resource.data.data.get(key).map(|v| match v {
+                Value::String(text) => Cow::Borrowed(text.as_str()),
+                other => Cow::Owned(other.to_string()),
+            }),
+            Field::Metadata(key) => resource.data.metadata.get(key).map(|v| Cow::Borrowed(v.as_str())),
+        }
+    }
+
+    /// Check if the field holds a timestamp
+    pub fn is_timestamp(&self) -> bool {
+        matches!(self, Field::CreatedAt | Field::UpdatedAt)
+    }
+}
+
+impl fmt::Display for Field {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Field::Id => write!(f, "id"),
+            Field::Name => write!(f, "name"),
+            Field::Type => write!(f, "type"),
+            Field::Owner => write!(f, "owner"),
+            Field::Description => write!(f, "description"),
+            Field::CreatedAt => write!(f, "created_at"),
+            Field::UpdatedAt => write!(f, "updated_at"),
+            Field::Data(key) => write!(f, "data.{}", key),
+            Field::Metadata(key) => write!(f, "meta.{}", key),
+        }
+    }
+}
+
+impl FromStr for Field {
+    type Err = CoreError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if let Some(key) = s.strip_prefix("data.") {
+            return non_empty_key(key, s).map(Field::Data);
+        }
+        if let Some(key) = s.strip_prefix("meta.").or_else(|| s.strip_prefix("metadata.")) {
+            return non_empty_key(key, s).map(Field::Metadata);
+        }
+
+        match s {
+            "id" => Ok(Field::Id),
+            "name" => Ok(Field::Name),
+            "type" => Ok(Field::Type),
+            "owner" => Ok(Field::Owner),
+            "description" | "desc" => Ok(Field::Description),
+            "created_at" | "created" => Ok(Field::CreatedAt),
+            "updated_at" | "updated" => Ok(Field::UpdatedAt),
+            _ => Err(CoreError::Validation(format!("Unknown query field: {}", s))),
+        }
+    }
+}
+
+/// Comparison operator of a condition
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Op {
+    /// Equal (`:` or `=`)
+    Eq,
+    /// Not equal (`!=`)
+    Ne,
+    /// Contains a substring (`~`)
+    Contains,
+    /// Less than (`<`)
+    Lt,
+    /// Less than or equal (`<=`)
+    Le,
+    /// Greater than (`>`)
+    Gt,
+    /// Greater than or equal (`>=`)
+    Ge,
+}
+
+impl fmt::Display for Op {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let op = match self {
+            Op::Eq => ":",
+            Op::Ne => "!=",
+            Op::Contains => "~",
+            Op::Lt => "<",
+            Op::Le => "<=",
+            Op::Gt => ">",
+            Op::Ge => ">=",
+        };
+        write!(f, "{}", op)
+    }
+}
+
+/// Structured filter over resources
+///
+/// The compact text syntax combines `field<op>value` terms, e.g.
+/// `type:document AND (owner:alice OR -meta.archived:*) created>=2024-01-01`.
+/// Terms next to each other are joined with AND, `-` or `NOT` negates,
+/// `field:*` checks that a field is present, and a bare word matches a
+/// substring of the name. Values containing spaces or operators are quoted.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Condition {
+    /// Compare a field against a value
+    Compare(Field, Op, String),
+    /// Field is present
+    Exists(Field),
+    /// Every condition holds
+    And(Vec<Condition>),
+    /// At least one condition holds
+    Or(Vec<Condition>),
+    /// Condition does not hold
+    Not(Box<Condition>),
+}
+
+impl Condition {
+    /// Create a comparison, checking that the value fits the field
+    pub fn compare(field: Field, op: Op, value: &str) -> Result<Self, CoreError> {
+        if field.is_timestamp() && parse_timestamp(value).is_none() {
+            return Err(CoreError::Validation(format!("Invalid timestamp for {}: {}", field, value)));
+        }
+        if field == Field::Type && matches!(op, Op::Eq | Op::Ne) {
+            value
+                .parse::<ResourceType>()
+                .map_err(|e| CoreError::Validation(format!("Invalid resource type: {}", e)))?;
+        }
+
+        Ok(Condition::Compare(field, op, value.to_string()))
+    }
+
+    /// Parse a condition from the compact text syntax
+    ///
+    /// Queries longer than 4096 bytes or nesting groups and negations more
+    /// than 32 deep are rejected.
+    pub fn parse(input: &str) -> Result<Self, CoreError> {
+        if input.len() > MAX_QUERY_LENGTH {
+            return Err(CoreError::Validation(format!("Query longer than {} bytes", MAX_QUERY_LENGTH)));
+        }
+
+        let mut parser = Parser {
+            tokens: tokenize(input)?.into_iter().peekable(),
+            depth: 0,
+        };
+
+        let condition = parser.parse_or()?;
+        match parser.tokens.next() {
+            None => Ok(condition),
+            Some(token) => Err(CoreError::Validation(format!("Unexpected {} in query", token))),
+        }
+    }
+
+    /// Combine with another condition that must also hold
+    pub fn and(self, other: Condition) -> Self {
+        match self {
+            Condition::And(mut conditions) => {
+                conditions.push(other);
+                Condition::And(conditions)
+            }
+            condition => Condition::And(vec![condition, other]),
+        }
+    }
+
+    /// Combine with an alternative condition
+    pub fn or(self, other: Condition) -> Self {
+        match self {
+            Condition::Or(mut conditions) => {
+                conditions.push(other);
+                Condition::Or(conditions)
+            }
+            condition => Condition::Or(vec![condition, other]),
+        }
+    }
+
+    /// Evaluate the condition against a resource
+    pub fn matches(&self, resource: &Resource) -> bool {
+        match self {
+            Condition::Compare(field, op, expected) => {
+                let actual = field.value(resource);
+                match (op, actual) {
+                    (Op::Ne, actual) => !actual.is_some_and(|a| equals(field, &a, expected)),
+                    (_, None) => false,
+                    (Op::Eq, Some(actual)) => equals(field, &actual, expected),
+                    (Op::Contains, Some(actual)) => actual.contains(expected.as_str()),
+                    (op, Some(actual)) => {
+                        let ordering = compare_values(field, &actual, expected);
+                        match op {
+                            Op::Lt => ordering == Some(Ordering::Less),
+                            Op::Le => matches!(ordering, Some(Ordering::Less | Ordering::Equal)),
+                            Op::Gt => ordering == Some(Ordering::Greater),
+                            _ => matches!(ordering, Some(Ordering::Greater | Ordering::Equal)),
+                        }
+                    }
+                }
+            }
+            Condition::Exists(field) => field.value(resource).is_some(),
+            Condition::And(conditions) => conditions.iter().all(|c| c.matches(resource)),
+            Condition::Or(conditions) => conditions.iter().any(|c| c.matches(resource)),
+            Condition::Not(condition) => !condition.matches(resource),
+        }
+    }
+
+    // Helper to format an operand, parenthesizing combinators
+    fn fmt_operand(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Condition::And(_) | Condition::Or(_) => write!(f, "({})", self),
+            _ => write!(f, "{}", self),
+        }
+    }
+}
+
+impl std::ops::Not for Condition {
+    type Output = Condition;
+
+    fn not(self) -> Self::Output {
+        Condition::Not(Box::new(self))
+    }
+}
+
+impl fmt::Display for Condition {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Condition::Compare(field, op, value) => write!(f, "{}{}{}", field, op, quote(value)),
+            Condition::Exists(field) => write!(f, "{}:*", field),
+            Condition::And(conditions) | Condition::Or(conditions) => {
+                let separator = match self {
+                    Condition::And(_) => " AND ",
+                    _ => " OR ",
+                };
+                for (i, condition) in conditions.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, "{}", separator)?;
+                    }
+                    condition.fmt_operand(f)?;
+                }
+                Ok(())
+            }
+            Condition::Not(condition) => {
+                write!(f, "NOT ")?;
+                condition.fmt_operand(f)
+            }
+        }
+    }
+}
+
+impl FromStr for Condition {
+    type Err = CoreError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Condition::parse(s)
+    }
+}
+
+impl Serialize for Condition {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for Condition {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer)?.parse().map_err(de::Error::custom)
+    }
+}
+
+/// Fields selected for output, written as a comma-separated list
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Projection(pub Vec<Field>);
+
+impl Projection {
+    /// Select the projected fields of a resource as a JSON object
+    pub fn apply(&self, resource: &Resource) -> serde_json::Value {
+        let fields = self
+            .0
+            .iter()
+            .map(|field| {
+                let value = field
+                    .value(resource)
+                    .map_or(serde_json::Value::Null, |v| serde_json::Value::String(v.into_owned()));
+                (field.to_string(), value)
+            })
+            .collect();
+
+        serde_json::Value::Object(fields)
+    }
+}
+
+impl fmt::Display for Projection {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let fields: Vec<String> = self.0.iter().map(ToString::to_string).collect();
+        write!(f, "{}", fields.join(","))
+    }
+}
+
+impl FromStr for Projection {
+    type Err = CoreError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        s.split(',')
+            .map(str::trim)
+            .filter(|field| !field.is_empty())
+            .map(str::parse)
+            .collect::<Result<Vec<Field>, _>>()
+            .map(Projection)
+    }
+}
+
+impl Serialize for Projection {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for Projection {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer)?.parse().map_err(de::Error::custom)
+    }
+}
+
+/// Token of the compact text syntax
+#[derive(Debug)]
+enum Token {
+    LParen,
+    RParen,
+    And,
+    Or,
+    Not,
+    Term(Condition),
+}
+
+impl fmt::Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Token::LParen => write!(f, "'('"),
+            Token::RParen => write!(f, "')'"),
+            Token::And => write!(f, "AND"),
+            Token::Or => write!(f, "OR"),
+            Token::Not => write!(f, "NOT"),
+            Token::Term(condition) => write!(f, "'{}'", condition),
+        }
+    }
+}
+
+/// Recursive descent parser over tokens
+struct Parser {
+    tokens: Peekable<std::vec::IntoIter<Token>>,
+    depth: usize,
+}
+
+impl Parser {
+    // Helper to parse alternatives separated by OR
+    fn parse_or(&mut self) -> Result<Condition, CoreError> {
+        let mut conditions = vec![self.parse_and()?];
+        while matches!(self.tokens.peek(), Some(Token::Or)) {
+            self.tokens.next();
+            conditions.push(self.parse_and()?);
+        }
+
+        Ok(collapse(conditions, Condition::Or))
+    }
+
+    // Helper to parse conditions joined by AND or by juxtaposition
+    fn parse_and(&mut self) -> Result<Condition, CoreError> {
+        let mut conditions = vec![self.parse_unary()?];
+        loop {
+            match self.tokens.peek() {
+                Some(Token::And) => {
+                    self.tokens.next();
+                }
+                Some(Token::LParen | Token::Not | Token::Term(_)) => {}
+                _ => break,
+            }
+            conditions.push(self.parse_unary()?);
+        }
+
+        Ok(collapse(conditions, Condition::And))
+    }
+
+    // Helper to parse a term, a negation or a parenthesized group
+    fn parse_unary(&mut self) -> Result<Condition, CoreError> {
+        match self.tokens.next() {
+            Some(Token::Not) => Ok(!self.nested(Self::parse_unary)?),
+            Some(Token::LParen) => {
+                let condition = self.nested(Self::parse_or)?;
+                match self.tokens.next() {
+                    Some(Token::RParen) => Ok(condition),
+                    _ => Err(CoreError::Validation("Missing ')' in query".to_string())),
+                }
+            }
+            Some(Token::Term(condition)) => Ok(condition),
+            Some(token) => Err(CoreError::Validation(format!("Unexpected {} in query", token))),
+            None => Err(CoreError::Validation("Unexpected end of query".to_string())),
+        }
+    }
+
+    // Helper to parse one level deeper, refusing to nest past the limit
+    fn nested(&mut self, parse: fn(&mut Self) -> Result<Condition, CoreError>) -> Result<Condition, CoreError> {
+        if self.depth == MAX_QUERY_DEPTH {
+            return Err(CoreError::Validation(format!("Query nested more than {} deep", MAX_QUERY_DEPTH)));
+        }
+
+        self.depth += 1;
+        let condition = parse(self);
+        self.depth -= 1;
+        condition
+    }
+}
+
+// Helper to build a combinator, skipping it for a single condition
+fn collapse(mut conditions: Vec<Condition>, combine: fn(Vec<Condition>) -> Condition) -> Condition {
+    if conditions.len() == 1 {
+        conditions.remove(0)
+    } else {
+        combine(conditions)
+    }
+}
+
+// Helper to split the text syntax into tokens
+fn tokenize(input: &str) -> Result<Vec<Token>, CoreError> {
+    let mut tokens = Vec::new();
+    let mut chars = input.char_indices().peekable();
+
+    while let Some(&(start, c)) = chars.peek() {
+        match c {
+            c if c.is_whitespace() => {
+                chars.next();
+            }
+            '(' => {
+                chars.next();
+                tokens.push(Token::LParen);
+            }
+            ')' => {
+                chars.next();
+                tokens.push(Token::RParen);
+            }
+            '"' => {
+                let value = read_quoted(&mut chars)?;
+                tokens.push(Token::Term(Condition::Compare(Field::Name, Op::Contains, value)));
+            }
+            '-' if input[start + 1..].starts_with(|c: char| !c.is_whitespace()) => {
+                chars.next();
+                tokens.push(Token::Not);
+            }
+            _ => tokens.push(read_term(input, &mut chars)?),
+        }
+    }
+
+    if tokens.is_empty() {
+        return Err(CoreError::Validation("Empty query".to_string()));
+    }
+
+    Ok(tokens)
+}
+
+// Helper to read a keyword, a bare word or a `field<op>value` term
+fn read_term(input: &str, chars: &mut Peekable<CharIndices<'_>>) -> Result<Token, CoreError> {
+    let start = chars.peek().map_or(input.len(), |&(i, _)| i);
+    let word = read_while(input, chars, |c| {
+        !c.is_whitespace() && !matches!(c, '(' | ')' | '"') && !OPERATOR_CHARS.contains(&c)
+    });
+
+    if !chars.peek().is_some_and(|&(_, c)| OPERATOR_CHARS.contains(&c)) {
+        return Ok(match word {
+            "AND" => Token::And,
+            "OR" => Token::Or,
+            "NOT" => Token::Not,
+            _ => Token::Term(Condition::Compare(Field::Name, Op::Contains, word.to_string())),
+        });
+    }
+
+    if word.is_empty() {
+        return Err(CoreError::Validation(format!("Missing field name at: {}", &input[start..])));
+    }
+    let field: Field = word.parse()?;
+
+    let op_start = chars.peek().map_or(input.len(), |&(i, _)| i);
+    let op = read_while(input, chars, |c| OPERATOR_CHARS.contains(&c));
+    let op = match op {
+        ":" | "=" => Op::Eq,
+        "!=" => Op::Ne,
+        "~" => Op::Contains,
+        "<" => Op::Lt,
+        "<=" => Op::Le,
+        ">" => Op::Gt,
+        ">=" => Op::Ge,
+        _ => {
+            return Err(CoreError::Validation(format!("Invalid operator at: {}", &input[op_start..])));
+        }
+    };
+
+    let value = match chars.peek() {
+        Some(&(_, '"')) => read_quoted(chars)?,
+        _ => {
+            let value = read_while(input, chars, |c| !c.is_whitespace() && !matches!(c, '(' | ')'));
+            if value.is_empty() {
+                return Err(CoreError::Validation(format!("Missing value for {}", field)));
+            }
+            if value == "*" && op == Op::Eq {
+                return Ok(Token::Term(Condition::Exists(field)));
+            }
+            value.to_string()
+        }
+    };
+
+    Condition::compare(field, op, &value).map(Token::Term)
+}
+
+// Helper to consume characters while they satisfy a predicate
+fn read_while<'a>(
+    input: &'a str,
+    chars: &mut Peekable<CharIndices<'_>>,
+    predicate: impl Fn(char) -> bool,
+) -> &'a str {
+    let start = chars.peek().map_or(input.len(), |&(i, _)| i);
+    let mut end = start;
+    while let Some(&(i, c)) = chars.peek() {
+        if !predicate(c) {
+            break;
+        }
+        end = i + c.len_utf8();
+        chars.next();
+    }
+
+    &input[start..end]
+}
+
+// Helper to read a double-quoted string with backslash escapes
+fn read_quoted(chars: &mut Peekable<CharIndices<'_>>) -> Result<String, CoreError> {
+    chars.next();
+    let mut value = String::new();
+
+    while let Some((_, c)) = chars.next() {
+        match c {
+            '"' => return Ok(value),
+            '\\' => match chars.next() {
+                Some((_, escaped)) => value.push(escaped),
+                None => break,
+            },
+            c => value.push(c),
+        }
+    }
+
+    Err(CoreError::Validation("Unterminated quote in query".to_string()))
+}
+
+// Helper to quote a value if it would not survive parsing as-is
+fn quote(value: &str) -> Cow<'_, str> {
+    let plain = !value.is_empty()
+        && value != "*"
+        && !value.starts_with(|c: char| OPERATOR_CHARS.contains(&c))
+        && !value.contains(|c: char| c.is_whitespace() || matches!(c, '(' | ')' | '"' | '\\'));
+    if plain {
+        return Cow::Borrowed(value);
+    }
+
+    let escaped = value.replace('\\', "\\\\").replace('"', "\\\"");
+    Cow::Owned(format!("\"{}\"", escaped))
+}
+
+// Helper to check a field value for equality with an expected value
+fn equals(field: &Field, actual: &str, expected: &str) -> bool {
+    match field {
+        Field::Type => actual.eq_ignore_ascii_case(expected),
+        field if field.is_timestamp() => compare_values(field, actual, expected) == Some(Ordering::Equal),
+        _ => actual == expected,
+    }
+}
+
+// Helper to order a field value against an expected value
+//
+// Timestamps compare chronologically and numeric values numerically; anything
+// else compares as text.
+fn compare_values(field: &Field, actual: &str, expected: &str) -> Option<Ordering> {
+    if field.is_timestamp() {
+        return Some(parse_timestamp(actual)?.cmp(&parse_timestamp(expected)?));
+    }
+
+    match (actual.parse::<f64>(), expected.parse::<f64>()) {
+        (Ok(a), Ok(b)) => a.partial_cmp(&b),
+        _ => Some(actual.cmp(expected)),
+    }
+}
+
+// Helper to parse an RFC 3339 timestamp or a date, taken as midnight UTC
+fn parse_timestamp(value: &str) -> Option<DateTime<Utc>> {
+    if let Ok(timestamp) = DateTime::parse_from_rfc3339(value) {
+        return Some(timestamp.with_timezone(&Utc));
+    }
+
+    NaiveDate::parse_from_str(value, "%Y-%m-%d")
+        .ok()
+        .and_then(|date| date.and_hms_opt(0, 0, 0))
+        .map(|midnight| midnight.and_utc())
+}
+
+// Helper to reject empty data or metadata keys
+fn non_empty_key(key: &str, field: &str) -> Result<String, CoreError> {
+    match key.is_empty() {
+        true => Err(CoreError::Validation(format!("Missing key in query field: {}", field))),
+        false => Ok(key.to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::ResourceData;
+
+    fn resource() -> Resource {
+        let data = ResourceData::new("Quarterly notes", ResourceType::Document)
+            .with_data("pages", "12")
+            .with_metadata("status", "draft");
+        let mut resource = Resource::new("doc-1", data).with_owner("alice");
+        resource.created_at = "2024-03-01T10:00:00+00:00".to_string();
+        resource
+    }
+
+    #[test]
+    fn test_parse_and_evaluate() {
+        let cases = [
+            ("type:document owner:alice", true),
+            ("type:DOCUMENT AND NOT owner:bob", true),
+            ("notes", true),
+            ("\"Quarterly notes\"", true),
+            ("name:\"Quarterly notes\"", true),
+            ("data.pages>9 data.pages<100", true),
+            ("data.pages>=13", false),
+            ("meta.status:* -description:*", true),
+            ("created>=2024-03-01 created<2024-03-02", true),
+            ("updated<2024-01-01 OR owner~ali", true),
+            ("(type:project OR type:media) owner:alice", false),
+        ];
+
+        for (input, expected) in cases {
+            let condition = Condition::parse(input).unwrap();
+            assert_eq!(condition.matches(&resource()), expected, "{}", input);
+        }
+    }
+
+    #[test]
+    fn test_display_round_trips() {
+        let condition = Condition::compare(Field::Type, Op::Eq, "document")
+            .unwrap()
+            .and(!Condition::Exists(Field::Owner).or(Condition::compare(Field::Name, Op::Contains, "a \"b\"").unwrap()))
+            .and(Condition::compare(Field::Data("x".to_string()), Op::Ne, "*").unwrap());
+
+        let text = condition.to_string();
+        assert_eq!(text, "type:document AND NOT (owner:* OR name~\"a \\\"b\\\"\") AND data.x!=\"*\"");
+        assert_eq!(Condition::parse(&text).unwrap(), condition);
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        for input in ["", "colour:red", "type:9lives", "created>yesterday", "(owner:alice", "owner!alice", "name:\"open"] {
+            assert!(matches!(Condition::parse(input), Err(CoreError::Validation(_))), "{}", input);
+        }
+        assert!(Condition::parse("type:invoice").is_ok());
+
+        // Deep nesting is refused instead of exhausting the stack
+        for input in ["NOT ".repeat(16000) + "owner:alice", "(".repeat(1000) + "owner:alice" + &")".repeat(1000)] {
+            assert!(matches!(Condition::parse(&input), Err(CoreError::Validation(_))));
+        }
+        let nested = "(".repeat(MAX_QUERY_DEPTH) + "owner:alice" + &")".repeat(MAX_QUERY_DEPTH);
+        assert!(Condition::parse(&nested).is_ok());
+        assert!(Condition::parse(&format!("-{}", nested)).is_err());
+    }
+
+    #[test]
+    fn test_projection() {
+        let projection: Projection = "id, name,meta.status,owner,data.missing".parse().unwrap();
+        assert_eq!(projection.to_string(), "id,name,meta.status,owner,data.missing");
+        assert_eq!(
+            projection.apply(&resource()),
+            serde_json::json!({
+                "id": "doc-1",
+                "name": "Quarterly notes",
+                "meta.status": "draft",
+                "owner": "alice",
+                "data.missing": null,
+            })
+        );
+    }
+}
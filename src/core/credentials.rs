@@ -0,0 +1,419 @@
+use crate::models::persistence::Repository;
+use crate::models::User;
+use crate::utils::validation::{validate_all, validate_length, ValidationError, ValidationResult};
+use argon2::password_hash::{PasswordHash, PasswordHasher, PasswordVerifier, SaltString};
+use argon2::{Algorithm, Argon2, Params, Version};
+use chrono::Utc;
+use std::time::Duration;
+
+use super::error::CoreError;
+
+/// Message returned for every failed login, so callers can't tell which part was wrong
+const INVALID_CREDENTIALS: &str = "Invalid user ID or password";
+
+/// Argon2id cost parameters
+///
+/// Defaults follow the OWASP recommendation of 19 MiB, two passes and one
+/// lane. Stored hashes made with other parameters are upgraded on login.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HashingConfig {
+    /// Memory cost in KiB
+    pub memory_kib: u32,
+    /// Number of passes
+    pub iterations: u32,
+    /// Degree of parallelism
+    pub parallelism: u32,
+}
+
+impl Default for HashingConfig {
+    fn default() -> Self {
+        Self {
+            memory_kib: 19 * 1024,
+            iterations: 2,
+            parallelism: 1,
+        }
+    }
+}
+
+/// Rules a new password must satisfy
+#[derive(Debug, Clone)]
+pub struct PasswordPolicy {
+    /// Minimum length in characters
+    pub min_length: usize,
+    /// Maximum length in characters
+    pub max_length: usize,
+    /// Require at least one lowercase and one uppercase letter
+    pub require_mixed_case: bool,
+    /// Require at least one digit
+    pub require_digit: bool,
+    /// Require at least one character that is neither a letter nor a digit
+    pub require_symbol: bool,
+    /// Reject passwords containing the user's ID or the local part of their email
+    pub reject_user_details: bool,
+}
+
+impl Default for PasswordPolicy {
+    fn default() -> Self {
+        Self {
+            min_length: 12,
+            max_length: 128,
+            require_mixed_case: true,
+            require_digit: true,
+            require_symbol: false,
+            reject_user_details: true,
+        }
+    }
+}
+
+impl PasswordPolicy {
+    /// Check a password against the policy, collecting every violation
+    pub fn validate(&self, password: &str, user: &User) -> ValidationResult {
+        let mut checks = vec![validate_length(password, "password", self.min_length, self.max_length)];
+
+        let has = |predicate: fn(char) -> bool| password.chars().any(predicate);
+        if self.require_mixed_case && !(has(char::is_lowercase) && has(char::is_uppercase)) {
+            checks.push(weak("must contain lowercase and uppercase letters"));
+        }
+        if self.require_digit && !has(|c| c.is_ascii_digit()) {
+            checks.push(weak("must contain a digit"));
+        }
+        if self.require_symbol && !has(|c| !c.is_alphanumeric()) {
+            checks.push(weak("must contain a symbol"));
+        }
+        if self.reject_user_details {
+            let lowered = password.to_lowercase();
+            let local_part = user.email.split('@').next().unwrap_or_default();
+            let details = [user.id.as_str(), local_part];
+            if details.iter().any(|d| d.len() >= 3 && lowered.contains(&d.to_lowercase())) {
+                checks.push(weak("must not contain the user ID or email address"));
+            }
+        }
+
+        validate_all(checks)
+    }
+}
+
+/// Lockout after repeated failed logins
+#[derive(Debug, Clone, Copy)]
+pub struct LockoutPolicy {
+    /// Failed logins in a row that lock the account
+    pub max_failures: u32,
+    /// How long a locked account refuses logins
+    pub duration: Duration,
+}
+
+impl Default for LockoutPolicy {
+    fn default() -> Self {
+        Self {
+            max_failures: 5,
+            duration: Duration::from_secs(15 * 60),
+        }
+    }
+}
+
+/// Password hashing, verification and lockout for users
+///
+/// Credentials are tracked on the `User` itself, so every method that takes
+/// a mutable user expects the caller to save it afterwards; `login` does so
+/// through a repository.
+#[derive(Debug, Clone, Default)]
+pub struct PasswordManager {
+    hashing: HashingConfig,
+    policy: PasswordPolicy,
+    lockout: LockoutPolicy,
+}
+
+impl PasswordManager {
+    /// Create a manager with the default cost, policy and lockout
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the hashing cost
+    pub fn with_hashing(mut self, hashing: HashingConfig) -> Self {
+        self.hashing = hashing;
+        self
+    }
+
+    /// Set the password policy
+    pub fn with_policy(mut self, policy: PasswordPolicy) -> Self {
+        self.policy = policy;
+        self
+    }
+
+    /// Set the lockout policy
+    pub fn with_lockout(mut self, lockout: LockoutPolicy) -> Self {
+        self.lockout = lockout;
+        self
+    }
+
+    /// Validate a new password against the policy and store its hash on the user
+    pub fn set_password(&self, user: &mut User, password: &str) -> Result<(), CoreError> {
+        self.policy.validate(password, user)?;
+        user.password_hash = Some(self.hash(password)?);
+        Ok(())
+    }
+
+    /// Like `set_password`, but hashes on the blocking pool for async callers
+    pub async fn update_password(&self, user: User, password: &str) -> Result<User, CoreError> {
+        let password = password.to_string();
+        self.blocking(move |manager| {
+            let mut user = user;
+            manager.set_password(&mut user, &password).map(|_| user)
+        })
+        .await?
+    }
+
+    /// Hash a password with the configured parameters
+    pub fn hash(&self, password: &str) -> Result<String, CoreError> {
+        let salt = SaltString::encode_b64(&rand::random::<[u8; 16]>())
+            .map_err(|e| CoreError::Processing(format!("Failed to generate salt: {}", e)))?;
+
+        self.argon2()?
+            .hash_password(password.as_bytes(), &salt)
+            .map(|hash| hash.to_string())
+            .map_err(|e| CoreError::Processing(format!("Failed to hash password: {}", e)))
+    }
+
+    /// Check a login attempt, updating the user's lockout state
+    ///
+    /// On success the failure count is reset, the login is recorded, and the
+    /// hash is upgraded if it was made with other parameters. Locked and
+    /// disabled accounts are refused even with the right password.
+    pub fn verify(&self, user: &mut User, password: &str) -> Result<(), CoreError> {
+        let now = Utc::now();
+        if user.is_locked_at(now) {
+            let until = user.locked_until.clone().unwrap_or_default();
+            return Err(CoreError::PermissionDenied(format!("Account {} is locked until {}", user.id, until)));
+        }
+
+        let hash = match user.password_hash.as_deref() {
+            Some(hash) => PasswordHash::new(hash)
+                .map_err(|e| CoreError::Processing(format!("Stored password hash is invalid: {}", e)))?,
+            None => return Err(CoreError::PermissionDenied(INVALID_CREDENTIALS.to_string())),
+        };
+
+        // The stored hash carries its own parameters, so verification works across upgrades
+        if Argon2::default().verify_password(password.as_bytes(), &hash).is_err() {
+            user.failed_logins += 1;
+            if user.failed_logins >= self.lockout.max_failures {
+                let duration = chrono::Duration::from_std(self.lockout.duration).unwrap_or(chrono::Duration::MAX);
+                user.locked_until = Some((now + duration).to_rfc3339());
+                user.failed_logins = 0;
+            }
+            return Err(CoreError::PermissionDenied(INVALID_CREDENTIALS.to_string()));
+        }
+
+        if !user.enabled {
+            return Err(CoreError::PermissionDenied(format!("User {} is {}", user.id, user.status())));
+        }
+
+        let outdated = self.needs_rehash(&hash);
+        user.failed_logins = 0;
+        user.locked_until = None;
+        user.record_login();
+        if outdated {
+            user.password_hash = Some(self.hash(password)?);
+        }
+
+        Ok(())
+    }
+
+    /// Log a user in by ID and password, saving the updated user
+    pub async fn login(
+        &self,
+        users: &(dyn Repository<User, String> + Send + Sync),
+        id: &str,
+        password: &str,
+    ) -> Result<User, CoreError> {
+        let mut user = match users.find_by_id(&id.to_string()).await.map_err(database_error)? {
+            Some(user) => user,
+            None => {
+                // Spend the same effort as a real check so unknown IDs can't be told apart
+                let password = password.to_string();
+                let _ = self.blocking(move |manager| manager.hash(&password)).await;
+                return Err(CoreError::PermissionDenied(INVALID_CREDENTIALS.to_string()));
+            }
+        };
+
+        let password = password.to_string();
+        let (user, result) = self
+            .blocking(move |manager| {
+                let result = manager.verify(&mut user, &password);
+                (user, result)
+            })
+            .await?;
+        let user = users.save(user).await.map_err(database_error)?;
+        result.map(|_| user)
+    }
+
+    // Helper to run Argon2 work on the blocking pool, since it would stall an async worker
+    async fn blocking<T, F>(&self, work: F) -> Result<T, CoreError>
+    where
+        F: FnOnce(&PasswordManager) -> T + Send + 'static,
+        T: Send + 'static,
+    {
+        let manager = self.clone();
+        tokio::task::spawn_blocking(move || work(&manager))
+            .await
+            .map_err(|e| CoreError::Processing(format!("Password task failed: {}", e)))
+    }
+
+    /// Check if a stored hash was made with other than the configured algorithm or parameters
+    pub fn needs_rehash(&self, hash: &PasswordHash<'_>) -> bool {
+        if hash.algorithm != Algorithm::Argon2id.ident() {
+            return true;
+        }
+
+        match Params::try_from(hash) {
+            Ok(params) => {
+                params.m_cost() != self.hashing.memory_kib
+                    || params.t_cost() != self.hashing.iterations
+                    || params.p_cost() != self.hashing.parallelism
+            }
+            Err(_) => true,
+        }
+    }
+
+    // Helper to build a hasher with the configured parameters
+    fn argon2(&self) -> Result<Argon2<'static>, CoreError> {
+        let params = Params::new(
+            self.hashing.memory_kib,
+            self.hashing.iterations,
+            self.hashing.parallelism,
+            None,
+        )
+        .map_err(|e| CoreError::Configuration(format!("Invalid password hashing parameters: {}", e)))?;
+
+        Ok(Argon2::new(Algorithm::Argon2id, Version::V0x13, params))
+    }
+}
+
+// Helper to build a password policy violation
+fn weak(reason: &str) -> ValidationResult {
+    Err(ValidationError::InvalidFieldValue("password".to_string(), reason.to_string()))
+}
+
+// Helper to map repository errors
+fn database_error(error: crate::models::persistence::PersistenceError) -> CoreError {
+    CoreError::Database(error.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::persistence::InMemoryUserRepository;
+
+    // Cheap parameters so tests stay fast
+    const FAST: HashingConfig = HashingConfig {
+        memory_kib: 64,
+        iterations: 1,
+        parallelism: 1,
+    };
+
+    fn manager() -> PasswordManager {
+        PasswordManager::new().with_hashing(FAST)
+    }
+
+    fn alice() -> User {
+        User::new("alice", "alice.smith@example.com", "Alice")
+    }
+
+    #[test]
+    fn test_password_policy() {
+        let policy = PasswordPolicy {
+            require_symbol: true,
+            ..PasswordPolicy::default()
+        };
+
+        assert!(policy.validate("Correct-Horse-42", &alice()).is_ok());
+        match policy.validate("short", &alice()) {
+            Err(ValidationError::MultipleErrors(errors)) => assert_eq!(errors.len(), 4),
+            other => panic!("expected several violations, got {:?}", other),
+        }
+        assert!(policy.validate("Alice.Smith-2024", &alice()).is_err());
+
+        let mut user = alice();
+        let result = manager().set_password(&mut user, "password");
+        assert!(matches!(result, Err(CoreError::Validation(msg)) if msg.contains("digit")));
+        assert!(!user.has_password());
+    }
+
+    #[test]
+    fn test_lockout_after_repeated_failures() {
+        let manager = manager().with_lockout(LockoutPolicy {
+            max_failures: 3,
+            duration: Duration::from_secs(60),
+        });
+        let mut user = alice();
+        manager.set_password(&mut user, "Correct-Horse-42").unwrap();
+        assert!(user.password_hash.as_deref().unwrap().starts_with("$argon2id$"));
+
+        assert!(manager.verify(&mut user, "Correct-Horse-42").is_ok());
+        assert!(user.last_login.is_some());
+
+        for _ in 0..2 {
+            assert!(manager.verify(&mut user, "wrong").is_err());
+        }
+        assert_eq!(user.failed_logins, 2);
+        assert!(manager.verify(&mut user, "wrong").is_err());
+        assert!(user.is_locked_at(Utc::now()));
+
+        // Locked accounts refuse even the right password until the lock expires
+        let result = manager.verify(&mut user, "Correct-Horse-42");
+        assert!(matches!(result, Err(CoreError::PermissionDenied(msg)) if msg.contains("locked")));
+        user.locked_until = Some((Utc::now() - chrono::Duration::seconds(1)).to_rfc3339());
+        assert!(manager.verify(&mut user, "Correct-Horse-42").is_ok());
+        assert_eq!(user.locked_until, None);
+    }
+
+    #[tokio::test]
+    async fn test_login_rehashes_outdated_hashes() {
+        let users = InMemoryUserRepository::new();
+        let mut user = alice();
+        manager().set_password(&mut user, "Correct-Horse-42").unwrap();
+        let old_hash = user.password_hash.clone().unwrap();
+        users.save(user).await.unwrap();
+
+        let stronger = PasswordManager::new().with_hashing(HashingConfig { memory_kib: 128, ..FAST });
+        assert!(stronger.login(&users, "bob", "Correct-Horse-42").await.is_err());
+        assert!(stronger.login(&users, "alice", "wrong").await.is_err());
+        assert_eq!(users.find_by_id(&"alice".to_string()).await.unwrap().unwrap().failed_logins, 1);
+
+        let user = stronger.login(&users, "alice", "Correct-Horse-42").await.unwrap();
+        let new_hash = user.password_hash.unwrap();
+        assert_ne!(new_hash, old_hash);
+        assert!(new_hash.contains("m=128"));
+        assert_eq!(user.failed_logins, 0);
+
+        // The upgraded hash still verifies, and no longer needs upgrading
+        let stored = users.find_by_id(&"alice".to_string()).await.unwrap().unwrap();
+        assert_eq!(stored.password_hash.as_deref(), Some(new_hash.as_str()));
+        assert!(!stronger.needs_rehash(&PasswordHash::new(&new_hash).unwrap()));
+        assert!(stronger.login(&users, "alice", "Correct-Horse-42").await.is_ok());
+    }
+
+    #[tokio::test(flavor = "current_thread")]
+    async fn test_login_leaves_the_runtime_free() {
+        use std::sync::atomic::{AtomicBool, Ordering};
+
+        let users = InMemoryUserRepository::new();
+        let slow = PasswordManager::new().with_hashing(HashingConfig { memory_kib: 4096, iterations: 4, ..FAST });
+        let user = slow.update_password(alice(), "Correct-Horse-42").await.unwrap();
+        users.save(user).await.unwrap();
+
+        // On a single worker, the other task only runs if hashing happens elsewhere
+        let ticked = AtomicBool::new(false);
+        let login = async {
+            let result = slow.login(&users, "alice", "Correct-Horse-42").await;
+            (result, ticked.load(Ordering::SeqCst))
+        };
+        let tick = async {
+            tokio::task::yield_now().await;
+            ticked.store(true, Ordering::SeqCst);
+        };
+        let ((result, ticked_during_login), _) = tokio::join!(login, tick);
+        assert!(result.is_ok());
+        assert!(ticked_during_login);
+    }
+}
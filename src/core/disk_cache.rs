@@ -0,0 +1,352 @@
+use chrono::Utc;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::io;
+use std::marker::PhantomData;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+use super::cache::{CacheBackend, CacheLookup};
+
+/// Extension of cache entry files
+const ENTRY_EXTENSION: &str = "json";
+
+/// Cache entry as stored on disk
+#[derive(Serialize, Deserialize)]
+struct DiskEntry<V> {
+    /// When the entry stops being fresh, in milliseconds since the Unix epoch
+    expires_at: i64,
+    /// Cached value, or `None` if the key is known not to exist
+    value: Option<V>,
+}
+
+/// Cache backend storing each entry as a JSON file in a directory
+///
+/// Entries survive restarts. File names are derived from a hash of the key,
+/// and file modification times record recency for eviction, which kicks in
+/// when either the entry count or the total size exceeds its cap. The count
+/// and size are tracked as entries are written and removed, so the directory
+/// is only scanned when opening the cache and when evicting.
+pub struct DiskCache<K, V> {
+    dir: PathBuf,
+    capacity: usize,
+    max_bytes: u64,
+    stale_window: Duration,
+    count: usize,
+    bytes: u64,
+    _marker: PhantomData<fn(K) -> V>,
+}
+
+impl<K, V> DiskCache<K, V>
+where
+    K: Serialize,
+    V: Serialize + DeserializeOwned,
+{
+    /// Open a cache in `dir` holding at most `capacity` entries, creating the directory if needed
+    pub fn open(dir: impl AsRef<Path>, capacity: usize) -> io::Result<Self> {
+        let dir = dir.as_ref().to_path_buf();
+        fs::create_dir_all(&dir)?;
+
+        let mut cache = Self {
+            dir,
+            capacity,
+            max_bytes: u64::MAX,
+            stale_window: Duration::ZERO,
+            count: 0,
+            bytes: 0,
+            _marker: PhantomData,
+        };
+        cache.recount();
+        Ok(cache)
+    }
+
+    /// Cap the total size of the cache files
+    pub fn with_max_bytes(mut self, max_bytes: u64) -> Self {
+        self.max_bytes = max_bytes;
+        self
+    }
+
+    /// Keep expired values available as `Stale` for the given window
+    pub fn with_stale_window(mut self, stale_window: Duration) -> Self {
+        self.stale_window = stale_window;
+        self
+    }
+
+    /// Get the cache directory
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Get the total size of the cache files in bytes
+    pub fn size_bytes(&self) -> u64 {
+        self.bytes
+    }
+
+    // Helper to map a key to its entry file
+    fn path_for(&self, key: &K) -> Option<PathBuf> {
+        let encoded = serde_json::to_vec(key).ok()?;
+        let name = hex::encode(Sha256::digest(&encoded));
+        Some(self.dir.join(name).with_extension(ENTRY_EXTENSION))
+    }
+
+    // Helper to write an entry and evict older ones if the cache is over its caps
+    fn write(&mut self, key: &K, value: Option<V>, ttl: Duration) {
+        if self.capacity == 0 {
+            return;
+        }
+
+        let path = match self.path_for(key) {
+            Some(path) => path,
+            None => return,
+        };
+        let entry = DiskEntry {
+            expires_at: deadline(ttl),
+            value,
+        };
+
+        // Write to a temporary file first so readers never see a partial entry
+        let previous = fs::metadata(&path).map(|metadata| metadata.len()).ok();
+        let tmp = path.with_extension("tmp");
+        let written = serde_json::to_vec(&entry)
+            .map_err(io::Error::other)
+            .and_then(|bytes| fs::write(&tmp, &bytes).map(|_| bytes.len() as u64))
+            .and_then(|size| fs::rename(&tmp, &path).map(|_| size));
+        let size = match written {
+            Ok(size) => size,
+            Err(e) => {
+                log::warn!("Failed to write cache entry {}: {}", path.display(), e);
+                let _ = fs::remove_file(&tmp);
+                return;
+            }
+        };
+
+        match previous {
+            Some(previous) => self.bytes = self.bytes.saturating_sub(previous),
+            None => self.count += 1,
+        }
+        self.bytes += size;
+        if self.count > self.capacity || self.bytes > self.max_bytes {
+            self.evict(&path);
+        }
+    }
+
+    // Helper to remove an entry file, keeping the totals in step
+    fn delete(&mut self, path: &Path) -> bool {
+        let size = fs::metadata(path).map(|metadata| metadata.len()).unwrap_or(0);
+        if fs::remove_file(path).is_err() {
+            return false;
+        }
+
+        self.count = self.count.saturating_sub(1);
+        self.bytes = self.bytes.saturating_sub(size);
+        true
+    }
+
+    // Helper to remove the least recently used entries until the cache fits its caps
+    //
+    // The totals are taken from the directory itself, which other processes
+    // sharing the cache may have changed.
+    fn evict(&mut self, keep: &Path) {
+        let mut files = self.recount();
+        if self.count <= self.capacity && self.bytes <= self.max_bytes {
+            return;
+        }
+
+        files.sort_by_key(|(_, _, modified)| *modified);
+        for (path, size, _) in files {
+            if self.count <= self.capacity && self.bytes <= self.max_bytes {
+                break;
+            }
+            if path == keep {
+                continue;
+            }
+            if fs::remove_file(&path).is_ok() {
+                self.count -= 1;
+                self.bytes = self.bytes.saturating_sub(size);
+            }
+        }
+    }
+
+    // Helper to reset the totals from the entry files, which are returned
+    fn recount(&mut self) -> Vec<(PathBuf, u64, SystemTime)> {
+        let files = self.files();
+        self.count = files.len();
+        self.bytes = files.iter().map(|(_, size, _)| size).sum();
+        files
+    }
+
+    // Helper to list entry files with their size and modification time
+    fn files(&self) -> Vec<(PathBuf, u64, SystemTime)> {
+        let entries = match fs::read_dir(&self.dir) {
+            Ok(entries) => entries,
+            Err(_) => return Vec::new(),
+        };
+
+        entries
+            .filter_map(Result::ok)
+            .filter(|entry| entry.path().extension().is_some_and(|ext| ext == ENTRY_EXTENSION))
+            .filter_map(|entry| {
+                let metadata = entry.metadata().ok()?;
+                let modified = metadata.modified().unwrap_or(SystemTime::UNIX_EPOCH);
+                Some((entry.path(), metadata.len(), modified))
+            })
+            .collect()
+    }
+}
+
+impl<K, V> CacheBackend<K, V> for DiskCache<K, V>
+where
+    K: Serialize,
+    V: Serialize + DeserializeOwned,
+{
+    fn get(&mut self, key: &K) -> CacheLookup<V> {
+        let path = match self.path_for(key) {
+            Some(path) => path,
+            None => return CacheLookup::Miss,
+        };
+        let bytes = match fs::read(&path) {
+            Ok(bytes) => bytes,
+            Err(_) => return CacheLookup::Miss,
+        };
+        let entry: DiskEntry<V> = match serde_json::from_slice(&bytes) {
+            Ok(entry) => entry,
+            Err(_) => {
+                // Unreadable entries, e.g. from an older format, are dropped
+                self.delete(&path);
+                return CacheLookup::Miss;
+            }
+        };
+
+        let overdue = Utc::now().timestamp_millis() - entry.expires_at;
+        let stale = match u64::try_from(overdue) {
+            Err(_) => false,
+            Ok(overdue) if entry.value.is_some() && u128::from(overdue) < self.stale_window.as_millis() => true,
+            Ok(_) => {
+                self.delete(&path);
+                return CacheLookup::Miss;
+            }
+        };
+
+        // Mark the entry as recently used
+        if let Ok(file) = fs::File::options().write(true).open(&path) {
+            let _ = file.set_modified(SystemTime::now());
+        }
+
+        match entry.value {
+            Some(value) if stale => CacheLookup::Stale(value),
+            Some(value) => CacheLookup::Hit(value),
+            None => CacheLookup::NotFound,
+        }
+    }
+
+    fn insert(&mut self, key: K, value: V, ttl: Duration) {
+        self.write(&key, Some(value), ttl);
+    }
+
+    fn insert_missing(&mut self, key: K, ttl: Duration) {
+        self.write(&key, None, ttl);
+    }
+
+    fn remove(&mut self, key: &K) -> bool {
+        self.path_for(key)
+            .is_some_and(|path| self.delete(&path))
+    }
+
+    fn clear(&mut self) {
+        for (path, _, _) in self.files() {
+            if let Err(e) = fs::remove_file(&path) {
+                log::warn!("Failed to remove cache entry {}: {}", path.display(), e);
+            }
+        }
+        self.recount();
+    }
+
+    fn len(&self) -> usize {
+        self.count
+    }
+}
+
+// Helper to compute the expiry time for a time to live, saturating on overflow
+fn deadline(ttl: Duration) -> i64 {
+    let ttl = i64::try_from(ttl.as_millis()).unwrap_or(i64::MAX);
+    Utc::now().timestamp_millis().saturating_add(ttl)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::id::generate_uuid;
+
+    const TTL: Duration = Duration::from_secs(60);
+
+    fn temp_dir() -> PathBuf {
+        std::env::temp_dir().join(format!("disk-cache-{}", generate_uuid()))
+    }
+
+    #[test]
+    fn test_entries_survive_reopen() {
+        let dir = temp_dir();
+        {
+            let mut cache: DiskCache<String, u32> = DiskCache::open(&dir, 10).unwrap();
+            cache.insert("a".to_string(), 1, TTL);
+            cache.insert_missing("b".to_string(), TTL);
+            cache.insert("c".to_string(), 3, Duration::ZERO);
+        }
+
+        let mut cache: DiskCache<String, u32> = DiskCache::open(&dir, 10).unwrap();
+        assert_eq!(cache.get(&"a".to_string()), CacheLookup::Hit(1));
+        assert_eq!(cache.get(&"b".to_string()), CacheLookup::NotFound);
+        assert_eq!(cache.get(&"c".to_string()), CacheLookup::Miss);
+        assert_eq!(cache.len(), 2);
+
+        cache.clear();
+        assert!(cache.is_empty());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_stale_window() {
+        let dir = temp_dir();
+        let mut cache: DiskCache<String, u32> = DiskCache::open(&dir, 10).unwrap().with_stale_window(TTL);
+        cache.insert("a".to_string(), 1, Duration::ZERO);
+
+        assert_eq!(cache.get(&"a".to_string()), CacheLookup::Stale(1));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_least_recently_used_is_evicted() {
+        let dir = temp_dir();
+        let mut cache: DiskCache<String, u32> = DiskCache::open(&dir, 2).unwrap();
+        cache.insert("a".to_string(), 1, TTL);
+        std::thread::sleep(Duration::from_millis(10));
+        cache.insert("b".to_string(), 2, TTL);
+        std::thread::sleep(Duration::from_millis(10));
+
+        // Touch "a" so "b" becomes the eviction candidate
+        cache.get(&"a".to_string());
+        std::thread::sleep(Duration::from_millis(10));
+        cache.insert("c".to_string(), 3, TTL);
+
+        assert_eq!(cache.len(), 2);
+        assert_eq!(cache.get(&"b".to_string()), CacheLookup::Miss);
+        assert_eq!(cache.get(&"a".to_string()), CacheLookup::Hit(1));
+
+        // Totals are tracked without rescanning, and match the directory
+        for key in ["e", "f", "a"] {
+            cache.insert(key.to_string(), 5, TTL);
+        }
+        let on_disk = cache.files();
+        assert_eq!(cache.len(), on_disk.len());
+        assert_eq!(cache.size_bytes(), on_disk.iter().map(|(_, size, _)| size).sum::<u64>());
+
+        // A size cap evicts down to the newest entry
+        let size = cache.size_bytes();
+        let mut cache = cache.with_max_bytes(size / 2);
+        cache.insert("d".to_string(), 4, TTL);
+        assert_eq!(cache.len(), 1);
+        assert_eq!(cache.get(&"d".to_string()), CacheLookup::Hit(4));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
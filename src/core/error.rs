@@ -1,7 +1,8 @@
+use crate::utils::validation::ValidationError;
 use thiserror::Error;
 
 /// Core error types for business logic
-#[derive(Error, Debug)]
+#[derive(Error, Debug, Clone)]
 pub enum CoreError {
     /// General error with message
     #[error("Core error: {0}")]
@@ -19,6 +20,10 @@ pub enum CoreError {
     #[error("Resource already exists: {0}")]
     AlreadyExists(String),
     
+    /// Resource was changed by someone else since it was read
+    #[error("Conflicting update: {0}")]
+    Conflict(String),
+    
     /// Permission denied
     #[error("Permission denied: {0}")]
     PermissionDenied(String),
@@ -35,6 +40,10 @@ pub enum CoreError {
     #[error("External service error: {0}")]
     ExternalService(String),
     
+    /// External service could not be reached or failed temporarily, so the request may succeed later
+    #[error("External service unavailable: {0}")]
+    Unavailable(String),
+    
     /// Database error
     #[error("Database error: {0}")]
     Database(String),
@@ -44,6 +53,28 @@ pub enum CoreError {
     Api(#[from] crate::api::ApiError),
 }
 
+/// Map an API error with no more specific meaning to an external service error
+///
+/// Timeouts, connection failures, rate limiting and 5xx responses become
+/// `CoreError::Unavailable`, everything else `CoreError::ExternalService`.
+pub(crate) fn external_error(error: crate::api::ApiError) -> CoreError {
+    match error.is_transient() {
+        true => CoreError::Unavailable(error.to_string()),
+        false => CoreError::ExternalService(format!("API error: {}", error)),
+    }
+}
+
+impl From<ValidationError> for CoreError {
+    fn from(error: ValidationError) -> Self {
+        match error {
+            ValidationError::MultipleErrors(errors) => CoreError::Validation(
+                errors.iter().map(ToString::to_string).collect::<Vec<_>>().join("; "),
+            ),
+            e => CoreError::Validation(e.to_string()),
+        }
+    }
+}
+
 /// Common error handling utilities
 pub trait ErrorHandler {
     /// Handle and log an error
@@ -63,7 +94,7 @@ impl ErrorHandler for DefaultErrorHandler {
         
         // Additional handling based on error type
         match error {
-            CoreError::ExternalService(msg) => {
+            CoreError::ExternalService(msg) | CoreError::Unavailable(msg) => {
                 log::warn!("External service issue: {}", msg);
             }
             CoreError::Database(msg) => {
@@ -78,8 +109,9 @@ impl ErrorHandler for DefaultErrorHandler {
             CoreError::Validation(_) => "The provided data is invalid. Please check your input and try again.".to_string(),
             CoreError::NotFound(_) => "The requested resource could not be found.".to_string(),
             CoreError::AlreadyExists(_) => "This resource already exists.".to_string(),
+            CoreError::Conflict(_) => "This resource was changed by someone else. Reload it and try again.".to_string(),
             CoreError::PermissionDenied(_) => "You don't have permission to perform this action.".to_string(),
-            CoreError::ExternalService(_) => "An external service is currently unavailable. Please try again later.".to_string(),
+            CoreError::ExternalService(_) | CoreError::Unavailable(_) => "An external service is currently unavailable. Please try again later.".to_string(),
             _ => "An error occurred. Our team has been notified.".to_string(),
         }
     }
@@ -0,0 +1,90 @@
+use async_trait::async_trait;
+use chrono::Utc;
+use std::path::{Path, PathBuf};
+
+use crate::utils::id::generate_uuid;
+
+use super::error::CoreError;
+
+/// Outgoing email message
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Email {
+    /// Recipient address
+    pub to: String,
+    /// Subject line
+    pub subject: String,
+    /// Plain text body
+    pub body: String,
+}
+
+impl Email {
+    /// Create a plain text message
+    pub fn new(to: &str, subject: &str, body: &str) -> Self {
+        Self {
+            to: to.to_string(),
+            subject: subject.to_string(),
+            body: body.to_string(),
+        }
+    }
+
+    /// Render the message with minimal RFC 5322 headers
+    pub fn to_rfc5322(&self) -> String {
+        format!(
+            "To: {}\r\nSubject: {}\r\nDate: {}\r\nContent-Type: text/plain; charset=utf-8\r\n\r\n{}\r\n",
+            self.to,
+            self.subject,
+            Utc::now().to_rfc2822(),
+            self.body
+        )
+    }
+}
+
+/// Delivers outgoing email
+#[async_trait]
+pub trait MailSender: Send + Sync {
+    /// Send a message
+    async fn send(&self, email: &Email) -> Result<(), CoreError>;
+}
+
+/// Mail sender printing messages to standard output, for local development
+#[derive(Debug, Clone, Copy, Default)]
+pub struct StdoutMailSender;
+
+#[async_trait]
+impl MailSender for StdoutMailSender {
+    async fn send(&self, email: &Email) -> Result<(), CoreError> {
+        println!("{}", email.to_rfc5322());
+        Ok(())
+    }
+}
+
+/// Mail sender writing each message to an `.eml` file in a directory, for local testing
+#[derive(Debug, Clone)]
+pub struct FileMailSender {
+    dir: PathBuf,
+}
+
+impl FileMailSender {
+    /// Write messages to `dir`, which is created on first use
+    pub fn new(dir: impl AsRef<Path>) -> Self {
+        Self { dir: dir.as_ref().to_path_buf() }
+    }
+
+    /// Get the directory messages are written to
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+}
+
+#[async_trait]
+impl MailSender for FileMailSender {
+    async fn send(&self, email: &Email) -> Result<(), CoreError> {
+        let io_error = |e: std::io::Error| CoreError::ExternalService(format!("Failed to write email: {}", e));
+
+        tokio::fs::create_dir_all(&self.dir).await.map_err(io_error)?;
+
+        // Timestamp first so a directory listing is in sending order
+        let name = format!("{}-{}.eml", Utc::now().format("%Y%m%dT%H%M%S%.6f"), generate_uuid());
+        tokio::fs::write(self.dir.join(name), email.to_rfc5322()).await.map_err(io_error)
+    }
+}
@@ -2,17 +2,32 @@
 //!
 //! Contains the main application logic and service implementations.
 
+pub mod account;
+pub mod authorization;
+pub mod cache;
+pub mod condition;
+pub mod credentials;
+pub mod disk_cache;
 pub mod error;
+pub mod jwt;
+pub mod mail;
 pub mod service;
+pub mod session;
+pub mod policy;
 pub mod processor;
+pub mod query;
+pub mod resource_types;
+pub mod sync;
+pub mod user_service;
 
 pub use error::CoreError;
 pub use service::Service;
 
 /// Application state enum
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
 pub enum AppState {
     /// Application is initializing
+    #[default]
     Initializing,
     /// Application is running normally
     Running,
@@ -24,12 +39,6 @@ pub enum AppState {
     Error,
 }
 
-impl Default for AppState {
-    fn default() -> Self {
-        Self::Initializing
-    }
-}
-
 impl std::fmt::Display for AppState {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         let state_str = match self {
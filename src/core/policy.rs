@@ -0,0 +1,680 @@
+use crate::models::{Group, Permission, Resource, ResourceType, User, UserRole};
+use serde::Deserialize;
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::Path;
+
+use super::error::CoreError;
+
+/// Range of resources a grant covers
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Scope {
+    /// Every resource
+    Any,
+    /// Resources owned by the acting user, or by nobody
+    Own,
+}
+
+/// Rule denying a permission regardless of grants
+///
+/// Unset criteria match everything, so a rule with no criteria denies every
+/// permission to everyone. A resource type only matches checks made against
+/// a resource of that type.
+#[derive(Debug, Clone, Default)]
+pub struct DenyRule {
+    /// Denied permission, or every permission if unset
+    pub permission: Option<Permission>,
+    /// Role the rule applies to
+    pub role: Option<UserRole>,
+    /// User the rule applies to
+    pub user_id: Option<String>,
+    /// Group whose members the rule applies to
+    pub group_id: Option<String>,
+    /// Resource type the rule applies to
+    pub resource_type: Option<ResourceType>,
+    /// Explanation included in the denial
+    pub reason: Option<String>,
+}
+
+impl DenyRule {
+    /// Create a rule denying a permission
+    pub fn permission(permission: Permission) -> Self {
+        Self {
+            permission: Some(permission),
+            ..Self::default()
+        }
+    }
+
+    /// Create a rule denying every permission
+    pub fn everything() -> Self {
+        Self::default()
+    }
+
+    /// Restrict the rule to a role
+    pub fn for_role(mut self, role: UserRole) -> Self {
+        self.role = Some(role);
+        self
+    }
+
+    /// Restrict the rule to a user
+    pub fn for_user(mut self, user_id: &str) -> Self {
+        self.user_id = Some(user_id.to_string());
+        self
+    }
+
+    /// Restrict the rule to members of a group
+    pub fn for_group(mut self, group_id: &str) -> Self {
+        self.group_id = Some(group_id.to_string());
+        self
+    }
+
+    /// Restrict the rule to resources of a type
+    pub fn on_type(mut self, resource_type: ResourceType) -> Self {
+        self.resource_type = Some(resource_type);
+        self
+    }
+
+    /// Set the explanation included in the denial
+    pub fn with_reason(mut self, reason: &str) -> Self {
+        self.reason = Some(reason.to_string());
+        self
+    }
+
+    // Helper to check whether the rule applies to a check
+    fn matches(&self, user: &User, groups: &[&Group], permission: &Permission, resource: Option<&Resource>) -> bool {
+        self.permission.as_ref().is_none_or(|p| p == permission)
+            && self.role.as_ref().is_none_or(|r| *r == user.role)
+            && self.user_id.as_ref().is_none_or(|id| *id == user.id)
+            && self.group_id.as_ref().is_none_or(|id| groups.iter().any(|g| g.id == *id))
+            && self
+                .resource_type
+                .as_ref()
+                .is_none_or(|t| resource.is_some_and(|r| r.data.resource_type == *t))
+    }
+}
+
+/// Outcome of a policy evaluation
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Decision {
+    /// The action is permitted
+    Allow,
+    /// The action is refused, with the reason
+    Deny(String),
+}
+
+impl Decision {
+    /// Check if the action is permitted
+    pub fn is_allowed(&self) -> bool {
+        matches!(self, Decision::Allow)
+    }
+
+    /// Convert a denial into `CoreError::PermissionDenied`
+    pub fn into_result(self) -> Result<(), CoreError> {
+        match self {
+            Decision::Allow => Ok(()),
+            Decision::Deny(reason) => Err(CoreError::PermissionDenied(reason)),
+        }
+    }
+}
+
+/// Access control policy
+///
+/// Roles map to permissions with a scope, superuser roles hold every
+/// permission on every resource, and deny rules override both. Permissions
+/// granted directly to a user or inherited from their groups apply with
+/// `Scope::Own`, and a resource's ACL entries grant permissions on that
+/// resource alone. Disabled users are denied everything.
+#[derive(Debug, Clone)]
+pub struct Policy {
+    roles: HashMap<UserRole, HashMap<Permission, Scope>>,
+    superusers: HashSet<UserRole>,
+    deny_rules: Vec<DenyRule>,
+}
+
+impl Policy {
+    /// Create a policy granting nothing
+    pub fn empty() -> Self {
+        Self {
+            roles: HashMap::new(),
+            superusers: HashSet::new(),
+            deny_rules: Vec::new(),
+        }
+    }
+
+    /// Grant a permission to a role over the given scope
+    pub fn grant(mut self, role: UserRole, permission: Permission, scope: Scope) -> Self {
+        self.roles.entry(role).or_default().insert(permission, scope);
+        self
+    }
+
+    /// Take a permission away from a role
+    pub fn revoke(mut self, role: &UserRole, permission: &Permission) -> Self {
+        if let Some(grants) = self.roles.get_mut(role) {
+            grants.remove(permission);
+        }
+        self
+    }
+
+    /// Give a role every permission on every resource
+    pub fn with_superuser(mut self, role: UserRole) -> Self {
+        self.superusers.insert(role);
+        self
+    }
+
+    /// Add a deny rule
+    pub fn with_deny_rule(mut self, rule: DenyRule) -> Self {
+        self.deny_rules.push(rule);
+        self
+    }
+
+    /// Get the permissions a role is granted, with their scope
+    pub fn role_permissions(&self, role: &UserRole) -> HashMap<Permission, Scope> {
+        self.roles.get(role).cloned().unwrap_or_default()
+    }
+
+    /// Decide whether a user may use a permission, optionally on a specific resource
+    ///
+    /// Without a resource, any grant of the permission is sufficient. With a
+    /// resource, the grant's scope must also cover it.
+    pub fn evaluate(&self, user: &User, permission: &Permission, resource: Option<&Resource>) -> Decision {
+        self.evaluate_with_groups(user, &[], permission, resource)
+    }
+
+    /// Decide like `evaluate`, for a user belonging to some of the given groups
+    ///
+    /// Members inherit their groups' permissions, and their own scope extends
+    /// to resources owned by their groups. Resources shared with a group are
+    /// in scope for its members when reading, and ACL entries naming one of
+    /// the groups apply to its members. Groups the user is not a member of are
+    /// ignored.
+    pub fn evaluate_with_groups(
+        &self,
+        user: &User,
+        groups: &[Group],
+        permission: &Permission,
+        resource: Option<&Resource>,
+    ) -> Decision {
+        if !user.enabled {
+            return Decision::Deny(format!("User {} is {}", user.id, user.status()));
+        }
+
+        let groups: Vec<&Group> = groups.iter().filter(|g| g.has_member(&user.id)).collect();
+
+        if let Some(rule) = self.deny_rules.iter().find(|rule| rule.matches(user, &groups, permission, resource)) {
+            let reason = rule.reason.clone().unwrap_or_else(|| "denied by policy".to_string());
+            return Decision::Deny(format!("Permission {} for user {}: {}", permission, user.id, reason));
+        }
+
+        let group_ids: Vec<&str> = groups.iter().map(|g| g.id.as_str()).collect();
+        if resource.is_some_and(|r| r.acl_permits(&user.id, &group_ids, permission)) {
+            return Decision::Allow;
+        }
+
+        let scope = match self.scope(user, &groups, permission) {
+            Some(scope) => scope,
+            None => return Decision::Deny(format!("User {} is missing permission {}", user.id, permission)),
+        };
+
+        match (scope, resource) {
+            (Scope::Own, Some(resource)) if !in_own_scope(user, &groups, permission, resource) => {
+                Decision::Deny(format!("User {} does not own resource {}", user.id, resource.id))
+            }
+            _ => Decision::Allow,
+        }
+    }
+
+    /// Get the permissions a user may use on at least some resources
+    ///
+    /// Covers the built-in permissions and every permission the policy, the
+    /// user or their groups mention, minus those a deny rule takes away.
+    pub fn effective_permissions(&self, user: &User, groups: &[Group]) -> HashSet<Permission> {
+        let mut candidates = UserRole::Admin.default_permissions();
+        candidates.extend(self.roles.values().flat_map(|grants| grants.keys().cloned()));
+        candidates.extend(user.permissions.iter().cloned());
+        candidates.extend(groups.iter().flat_map(|g| g.permissions.iter().cloned()));
+
+        candidates
+            .into_iter()
+            .filter(|permission| self.evaluate_with_groups(user, groups, permission, None).is_allowed())
+            .collect()
+    }
+
+    /// Load a policy file, in TOML or JSON depending on its extension
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, CoreError> {
+        let path = path.as_ref();
+        let contents = fs::read_to_string(path).map_err(|e| {
+            CoreError::Configuration(format!("Failed to read policy file {}: {}", path.display(), e))
+        })?;
+
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some("toml") => Self::from_toml_str(&contents),
+            Some("json") => Self::from_json_str(&contents),
+            _ => Err(CoreError::Configuration(format!(
+                "Unsupported policy file format: {}",
+                path.display()
+            ))),
+        }
+    }
+
+    /// Build a policy from a TOML policy file, on top of the built-in roles
+    pub fn from_toml_str(contents: &str) -> Result<Self, CoreError> {
+        let file: PolicyFile = toml::from_str(contents)
+            .map_err(|e| CoreError::Configuration(format!("Invalid policy file: {}", e)))?;
+        file.into_policy()
+    }
+
+    /// Build a policy from a JSON policy file, on top of the built-in roles
+    pub fn from_json_str(contents: &str) -> Result<Self, CoreError> {
+        let file: PolicyFile = serde_json::from_str(contents)
+            .map_err(|e| CoreError::Configuration(format!("Invalid policy file: {}", e)))?;
+        file.into_policy()
+    }
+
+    /// Evaluate a check, failing with `CoreError::PermissionDenied` on denial
+    pub fn check(&self, user: &User, permission: &Permission, resource: Option<&Resource>) -> Result<(), CoreError> {
+        self.evaluate(user, permission, resource).into_result()
+    }
+
+    /// Evaluate a check for a group member, failing with `CoreError::PermissionDenied` on denial
+    pub fn check_with_groups(
+        &self,
+        user: &User,
+        groups: &[Group],
+        permission: &Permission,
+        resource: Option<&Resource>,
+    ) -> Result<(), CoreError> {
+        self.evaluate_with_groups(user, groups, permission, resource).into_result()
+    }
+
+    // Helper to grant a permission, keeping the wider scope if it is already granted
+    fn widen(&mut self, role: &UserRole, permission: Permission, scope: Scope) {
+        let current = self.roles.entry(role.clone()).or_default().entry(permission).or_insert(scope);
+        if scope == Scope::Any {
+            *current = Scope::Any;
+        }
+    }
+
+    // Helper to find the scope a user holds a permission with, preferring the role grant
+    fn scope(&self, user: &User, groups: &[&Group], permission: &Permission) -> Option<Scope> {
+        if self.superusers.contains(&user.role) {
+            return Some(Scope::Any);
+        }
+
+        let role_scope = self.roles.get(&user.role).and_then(|grants| grants.get(permission)).copied();
+        let explicit = user.permissions.contains(permission)
+            || groups.iter().any(|g| g.permissions.contains(permission));
+
+        role_scope.or(explicit.then_some(Scope::Own))
+    }
+}
+
+// Helper to check whether a resource falls within a user's own scope
+fn in_own_scope(user: &User, groups: &[&Group], permission: &Permission, resource: &Resource) -> bool {
+    !resource.has_owner()
+        || resource.is_owned_by(&user.id)
+        || groups.iter().any(|g| {
+            resource.is_owned_by_group(&g.id)
+                || (*permission == Permission::ReadResource && resource.is_shared_with(&g.id))
+        })
+}
+
+impl Default for Policy {
+    /// Built-in policy matching `UserRole::default_permissions`
+    ///
+    /// Admins are superusers and managers act on any resource. Other roles may
+    /// only act on resources they own or that have no owner.
+    fn default() -> Self {
+        let mut policy = Self::empty().with_superuser(UserRole::Admin);
+
+        for role in [UserRole::Manager, UserRole::User, UserRole::ReadOnly, UserRole::Guest] {
+            for permission in role.default_permissions() {
+                let scope = match role {
+                    UserRole::Manager => Scope::Any,
+                    _ => Scope::Own,
+                };
+                policy = policy.grant(role.clone(), permission, scope);
+            }
+        }
+
+        policy
+    }
+}
+
+/// Policy file defining custom permissions and roles
+///
+/// ```toml
+/// custom_permissions = ["audit_logs"]
+///
+/// [roles.auditor]
+/// inherits = ["readonly"]
+/// permissions = ["view_reports", "custom:audit_logs"]
+/// scope = "any"
+/// ```
+///
+/// Roles named after a built-in role extend it. A role holds the grants of
+/// every role it inherits from, with the wider scope winning on overlap.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct PolicyFile {
+    /// Names usable as `custom:<name>` permissions
+    custom_permissions: Vec<String>,
+    /// Role definitions by role name
+    roles: HashMap<String, RoleDefinition>,
+}
+
+/// Role definition in a policy file
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct RoleDefinition {
+    /// Roles whose grants this role inherits
+    inherits: Vec<String>,
+    /// Permissions granted by this role
+    permissions: Vec<String>,
+    /// Scope of the permissions, `own` if unset
+    scope: Option<Scope>,
+    /// Whether the role holds every permission on every resource
+    superuser: bool,
+}
+
+/// Role definition with its names parsed and checked
+struct ParsedRole {
+    parents: Vec<UserRole>,
+    grants: Vec<(Permission, Scope)>,
+    superuser: bool,
+}
+
+impl PolicyFile {
+    // Helper to validate the definitions and resolve them against the built-in policy
+    fn into_policy(self) -> Result<Policy, CoreError> {
+        let declared: HashSet<String> = self.custom_permissions.into_iter().collect();
+
+        let mut definitions = HashMap::new();
+        for (name, definition) in self.roles {
+            let role: UserRole = name.parse().map_err(CoreError::Configuration)?;
+            let scope = definition.scope.unwrap_or(Scope::Own);
+
+            let mut grants = Vec::new();
+            for name in &definition.permissions {
+                let permission: Permission = name.parse().map_err(CoreError::Configuration)?;
+                if let Permission::Custom(custom) = &permission {
+                    if !declared.contains(custom) {
+                        return Err(CoreError::Configuration(format!(
+                            "Role {} uses undeclared custom permission {}",
+                            role, custom
+                        )));
+                    }
+                }
+                grants.push((permission, scope));
+            }
+
+            let parents = definition
+                .inherits
+                .iter()
+                .map(|parent| parent.parse().map_err(CoreError::Configuration))
+                .collect::<Result<Vec<UserRole>, _>>()?;
+
+            definitions.insert(role, ParsedRole {
+                parents,
+                grants,
+                superuser: definition.superuser,
+            });
+        }
+
+        let mut policy = Policy::default();
+        let mut resolved = HashSet::new();
+        for role in definitions.keys() {
+            resolve_role(role, &definitions, &mut policy, &mut Vec::new(), &mut resolved)?;
+        }
+
+        Ok(policy)
+    }
+}
+
+// Helper to apply a role definition after the roles it inherits from, rejecting cycles
+fn resolve_role(
+    role: &UserRole,
+    definitions: &HashMap<UserRole, ParsedRole>,
+    policy: &mut Policy,
+    path: &mut Vec<UserRole>,
+    resolved: &mut HashSet<UserRole>,
+) -> Result<(), CoreError> {
+    if resolved.contains(role) {
+        return Ok(());
+    }
+    if path.contains(role) {
+        let cycle: Vec<String> = path.iter().chain([role]).map(ToString::to_string).collect();
+        return Err(CoreError::Configuration(format!(
+            "Role hierarchy has a cycle: {}",
+            cycle.join(" -> ")
+        )));
+    }
+
+    let definition = match definitions.get(role) {
+        Some(definition) => definition,
+        // Built-in roles without a definition keep their defaults
+        None if !matches!(role, UserRole::Custom(_)) => return Ok(()),
+        None => {
+            let child = path.last().map(ToString::to_string).unwrap_or_default();
+            return Err(CoreError::Configuration(format!(
+                "Role {} inherits from unknown role {}",
+                child, role
+            )));
+        }
+    };
+
+    path.push(role.clone());
+    for parent in &definition.parents {
+        resolve_role(parent, definitions, policy, path, resolved)?;
+
+        if policy.superusers.contains(parent) {
+            policy.superusers.insert(role.clone());
+        }
+        for (permission, scope) in policy.role_permissions(parent) {
+            policy.widen(role, permission, scope);
+        }
+    }
+    path.pop();
+
+    if definition.superuser {
+        policy.superusers.insert(role.clone());
+    }
+    for (permission, scope) in &definition.grants {
+        policy.widen(role, permission.clone(), *scope);
+    }
+    resolved.insert(role.clone());
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{AclAction, AclEntry, Principal, ResourceData};
+
+    fn user(id: &str, role: UserRole) -> User {
+        User::new(id, &format!("{}@example.com", id), id).with_role(role)
+    }
+
+    fn document(id: &str, owner: &str) -> Resource {
+        Resource::new(id, ResourceData::new(id, ResourceType::Document)).with_owner(owner)
+    }
+
+    #[test]
+    fn test_default_policy_scopes() {
+        let policy = Policy::default();
+        let alice = user("alice", UserRole::User);
+        let manager = user("mgr", UserRole::Manager);
+        let theirs = document("doc-1", "bob");
+
+        assert!(policy.evaluate(&alice, &Permission::UpdateResource, None).is_allowed());
+        assert!(policy.evaluate(&alice, &Permission::UpdateResource, Some(&document("doc-2", "alice"))).is_allowed());
+        assert_eq!(
+            policy.evaluate(&alice, &Permission::UpdateResource, Some(&theirs)),
+            Decision::Deny("User alice does not own resource doc-1".to_string())
+        );
+        assert!(policy.evaluate(&manager, &Permission::DeleteResource, Some(&theirs)).is_allowed());
+        assert!(policy.evaluate(&manager, &Permission::ViewReports, None).is_allowed());
+        assert!(!policy.evaluate(&manager, &Permission::ManageUsers, None).is_allowed());
+        assert!(policy.evaluate(&user("root", UserRole::Admin), &Permission::Custom("x".to_string()), None).is_allowed());
+    }
+
+    #[test]
+    fn test_configured_roles_and_deny_rules() {
+        let policy = Policy::default()
+            .grant(UserRole::ReadOnly, Permission::ExportData, Scope::Any)
+            .revoke(&UserRole::User, &Permission::DeleteResource)
+            .with_deny_rule(
+                DenyRule::permission(Permission::UpdateResource)
+                    .on_type(ResourceType::Document)
+                    .with_reason("documents are frozen"),
+            )
+            .with_deny_rule(DenyRule::everything().for_user("mallory"));
+
+        let alice = user("alice", UserRole::User);
+        let mine = document("doc-1", "alice");
+
+        assert!(policy.check(&user("ro", UserRole::ReadOnly), &Permission::ExportData, None).is_ok());
+        assert!(matches!(
+            policy.check(&alice, &Permission::DeleteResource, Some(&mine)),
+            Err(CoreError::PermissionDenied(msg)) if msg.contains("missing permission delete_resource")
+        ));
+        assert!(matches!(
+            policy.check(&alice, &Permission::UpdateResource, Some(&mine)),
+            Err(CoreError::PermissionDenied(msg)) if msg.contains("documents are frozen")
+        ));
+        assert!(policy.check(&alice, &Permission::UpdateResource, None).is_ok());
+        assert!(!policy.evaluate(&user("mallory", UserRole::Admin), &Permission::ReadResource, None).is_allowed());
+    }
+
+    #[test]
+    fn test_custom_roles_from_file() {
+        let policy = Policy::from_toml_str(
+            r#"
+            custom_permissions = ["audit_logs"]
+
+            [roles.auditor]
+            inherits = ["readonly"]
+            permissions = ["read_resource", "view_reports", "custom:audit_logs"]
+            scope = "any"
+
+            [roles.lead-auditor]
+            inherits = ["auditor", "user"]
+
+            [roles.guest]
+            permissions = ["view_reports"]
+            "#,
+        )
+        .unwrap();
+
+        let auditor = user("aud", UserRole::Custom("auditor".to_string()));
+        let lead = user("lead", UserRole::Custom("lead-auditor".to_string()));
+        let theirs = document("doc-1", "bob");
+
+        assert!(policy.evaluate(&auditor, &Permission::ReadResource, Some(&theirs)).is_allowed());
+        assert!(policy.evaluate(&auditor, &Permission::Custom("audit_logs".to_string()), None).is_allowed());
+        assert!(!policy.evaluate(&auditor, &Permission::UpdateResource, None).is_allowed());
+        assert!(policy.evaluate(&lead, &Permission::UpdateResource, Some(&document("doc-2", "lead"))).is_allowed());
+        assert!(!policy.evaluate(&lead, &Permission::UpdateResource, Some(&theirs)).is_allowed());
+        assert!(policy.evaluate(&lead, &Permission::ViewReports, Some(&theirs)).is_allowed());
+        assert!(policy.evaluate(&user("g", UserRole::Guest), &Permission::ViewReports, None).is_allowed());
+
+        let json = Policy::from_json_str(r#"{"roles": {"billing": {"inherits": ["admin"]}}}"#).unwrap();
+        assert!(json.evaluate(&user("b", UserRole::Custom("billing".to_string())), &Permission::ManageUsers, None).is_allowed());
+    }
+
+    #[test]
+    fn test_invalid_policy_files() {
+        let error = |contents: &str| match Policy::from_toml_str(contents) {
+            Err(CoreError::Configuration(msg)) => msg,
+            other => panic!("expected a configuration error, got {:?}", other.map(|_| ())),
+        };
+
+        // Either role may be reached first, but the cycle is reported in full
+        let cycle = error("[roles.a]\ninherits = [\"b\"]\n[roles.b]\ninherits = [\"a\"]");
+        assert!(cycle == "Role hierarchy has a cycle: a -> b -> a" || cycle == "Role hierarchy has a cycle: b -> a -> b");
+        assert!(error("[roles.a]\ninherits = [\"a\"]").contains("cycle"));
+        assert!(error("[roles.a]\ninherits = [\"missing\"]").contains("unknown role missing"));
+        assert!(error("[roles.a]\npermissions = [\"custom:billing\"]").contains("undeclared custom permission billing"));
+        assert!(error("[roles.a]\npermissions = [\"fly\"]").contains("Unknown permission"));
+        assert!(error("[roles.\"Bad Name\"]").contains("Invalid role name"));
+    }
+
+    #[test]
+    fn test_effective_permissions() {
+        let policy = Policy::default()
+            .with_deny_rule(DenyRule::permission(Permission::ExportData).for_role(UserRole::Manager));
+        let manager = user("mia", UserRole::Manager).with_permission(Permission::Custom("audit".to_string()));
+
+        let permissions = policy.effective_permissions(&manager, &[]);
+        assert!(permissions.contains(&Permission::DeleteResource));
+        assert!(permissions.contains(&Permission::Custom("audit".to_string())));
+        assert!(!permissions.contains(&Permission::ExportData));
+        assert!(!permissions.contains(&Permission::ManageUsers));
+
+        let team = Group::team("ops", "Ops").with_member("rita").with_permission(Permission::ViewReports);
+        let reader = user("rita", UserRole::ReadOnly);
+        assert!(policy.effective_permissions(&reader, &[team]).contains(&Permission::ViewReports));
+    }
+
+    #[test]
+    fn test_group_membership() {
+        let policy = Policy::default().with_deny_rule(DenyRule::permission(Permission::DeleteResource).for_group("interns"));
+        let alice = user("alice", UserRole::User);
+        let guest = user("guest", UserRole::Guest);
+        let groups = vec![
+            Group::team("design", "Design").with_member("alice").with_member("guest").with_permission(Permission::ExportData),
+            Group::new("interns", "Interns").with_member("guest"),
+            Group::new("finance", "Finance").with_permission(Permission::ViewReports),
+        ];
+
+        let team_doc = Resource::new("doc-1", ResourceData::new("doc-1", ResourceType::Document)).with_owner_group("design");
+        let shared = document("doc-2", "bob").shared_with("design");
+        let finance_doc = team_doc.clone().with_owner_group("finance");
+
+        // Group ownership extends the own scope, and sharing allows reading
+        assert!(policy.evaluate_with_groups(&alice, &groups, &Permission::UpdateResource, Some(&team_doc)).is_allowed());
+        assert!(!policy.evaluate(&alice, &Permission::UpdateResource, Some(&team_doc)).is_allowed());
+        assert!(policy.evaluate_with_groups(&alice, &groups, &Permission::ReadResource, Some(&shared)).is_allowed());
+        assert!(!policy.evaluate_with_groups(&alice, &groups, &Permission::UpdateResource, Some(&shared)).is_allowed());
+        assert!(!policy.evaluate_with_groups(&alice, &groups, &Permission::ReadResource, Some(&finance_doc)).is_allowed());
+
+        // Members inherit group permissions, but only from groups they belong to
+        assert!(policy.evaluate_with_groups(&guest, &groups, &Permission::ExportData, Some(&team_doc)).is_allowed());
+        assert!(!policy.evaluate_with_groups(&alice, &groups, &Permission::ViewReports, None).is_allowed());
+        assert!(policy.evaluate_with_groups(&alice, &groups, &Permission::DeleteResource, Some(&team_doc)).is_allowed());
+        assert!(matches!(
+            policy.check_with_groups(&guest, &groups, &Permission::DeleteResource, None),
+            Err(CoreError::PermissionDenied(msg)) if msg.contains("denied by policy")
+        ));
+    }
+
+    #[test]
+    fn test_acl_entries() {
+        let policy = Policy::default().with_deny_rule(DenyRule::permission(Permission::DeleteResource).for_user("ro"));
+        let reader = user("ro", UserRole::ReadOnly);
+        let groups = vec![Group::new("ops", "Ops").with_member("ro")];
+        let mut doc = document("doc-1", "bob");
+
+        assert!(!policy.evaluate(&reader, &Permission::ReadResource, Some(&doc)).is_allowed());
+
+        doc.grant(AclEntry::new(Principal::User("ro".to_string()), [Permission::ReadResource, Permission::UpdateResource]), "bob");
+        doc.grant(AclEntry::new(Principal::Group("ops".to_string()), [Permission::DeleteResource]), "bob");
+        assert!(policy.evaluate(&reader, &Permission::UpdateResource, Some(&doc)).is_allowed());
+        assert!(!policy.evaluate(&reader, &Permission::UpdateResource, None).is_allowed());
+
+        // Deny rules still win over ACL entries
+        assert!(!policy.evaluate_with_groups(&reader, &groups, &Permission::DeleteResource, Some(&doc)).is_allowed());
+
+        let expired = AclEntry::new(Principal::User("ro".to_string()), [Permission::ReadResource])
+            .with_expiry(chrono::Utc::now() - chrono::Duration::seconds(1));
+        doc.grant(expired, "bob");
+        assert!(!policy.evaluate(&reader, &Permission::ReadResource, Some(&doc)).is_allowed());
+
+        assert!(doc.revoke(&Principal::Group("ops".to_string()), "bob"));
+        assert!(!doc.revoke(&Principal::Group("ops".to_string()), "bob"));
+        let actions: Vec<AclAction> = doc.acl_log.iter().map(|e| e.action).collect();
+        assert_eq!(actions, vec![AclAction::Grant, AclAction::Grant, AclAction::Grant, AclAction::Revoke]);
+        assert_eq!(doc.acl.len(), 1);
+    }
+}
@@ -1,5 +1,6 @@
-use crate::models::{Resource, ResourceType};
+use crate::models::{Resource, ResourceType, Schemas};
 use super::error::CoreError;
+use serde_json::Value;
 use std::collections::HashMap;
 use std::sync::Arc;
 use tokio::sync::Mutex;
@@ -13,9 +14,12 @@ pub trait ResourceProcessor: Send + Sync {
     fn can_handle(&self, resource_type: &ResourceType) -> bool;
 }
 
+/// Processors grouped by the resource type they are registered for
+type ProcessorMap = HashMap<ResourceType, Vec<Box<dyn ResourceProcessor>>>;
+
 /// Registry for resource processors
 pub struct ProcessorRegistry {
-    processors: Arc<Mutex<HashMap<ResourceType, Vec<Box<dyn ResourceProcessor>>>>>,
+    processors: Arc<Mutex<ProcessorMap>>,
 }
 
 impl ProcessorRegistry {
@@ -63,19 +67,35 @@ impl Default for ProcessorRegistry {
     }
 }
 
+/// Create a built-in processor by name, for processors configured at runtime
+///
+/// Known names are `document`, `user` and `audit`.
+pub fn builtin_processor(name: &str) -> Option<Box<dyn ResourceProcessor>> {
+    match name {
+        "document" => Some(Box::new(DocumentProcessor)),
+        "user" => Some(Box::new(UserProcessor)),
+        "audit" => Some(Box::new(AuditLogProcessor)),
+        _ => None,
+    }
+}
+
 /// Document processor for handling document resources
 pub struct DocumentProcessor;
 
 impl ResourceProcessor for DocumentProcessor {
     fn process(&self, resource: &mut Resource) -> Result<(), CoreError> {
-        if let Some(content) = resource.data.data.get_mut("content") {
-            // Example processing: ensure content is not empty
-            if content.is_empty() {
-                return Err(CoreError::Validation("Document content cannot be empty".to_string()));
+        match resource.data.data.get_mut("content") {
+            Some(Value::String(content)) => {
+                // Example processing: ensure content is not empty
+                if content.is_empty() {
+                    return Err(CoreError::Validation("Document content cannot be empty".to_string()));
+                }
+                
+                // Example processing: trim content
+                *content = content.trim().to_string();
             }
-            
-            // Example processing: trim content
-            *content = content.trim().to_string();
+            Some(_) => return Err(CoreError::Validation("Document content must be text".to_string())),
+            None => {}
         }
         
         Ok(())
@@ -93,7 +113,7 @@ impl ResourceProcessor for UserProcessor {
     fn process(&self, resource: &mut Resource) -> Result<(), CoreError> {
         if let Some(email) = resource.data.data.get("email") {
             // Example validation: basic email format check
-            if !email.contains('@') || !email.contains('.') {
+            if !email.as_str().is_some_and(|email| email.contains('@') && email.contains('.')) {
                 return Err(CoreError::Validation("Invalid email format".to_string()));
             }
         }
@@ -101,7 +121,7 @@ impl ResourceProcessor for UserProcessor {
         // Example: ensure users have a created_at timestamp
         if !resource.data.data.contains_key("created_at") {
             let now = chrono::Utc::now().to_rfc3339();
-            resource.data.data.insert("created_at".to_string(), now);
+            resource.data.data.insert("created_at".to_string(), Value::String(now));
         }
         
         Ok(())
@@ -119,7 +139,7 @@ impl ResourceProcessor for AuditLogProcessor {
     fn process(&self, resource: &mut Resource) -> Result<(), CoreError> {
         // Example: add a last_modified timestamp to all resources
         let now = chrono::Utc::now().to_rfc3339();
-        resource.data.data.insert("last_modified".to_string(), now);
+        resource.data.data.insert("last_modified".to_string(), Value::String(now));
         
         // In a real app, this would log the modification to an audit log
         log::info!(
@@ -136,4 +156,37 @@ impl ResourceProcessor for AuditLogProcessor {
         // This processor handles all resource types
         true
     }
-}
\ No newline at end of file
+}
+
+/// Schema processor converting string-encoded data and enforcing the schema of each resource type
+pub struct SchemaProcessor {
+    schemas: Arc<Schemas>,
+}
+
+impl SchemaProcessor {
+    /// Create a processor enforcing the given schemas
+    pub fn new(schemas: Arc<Schemas>) -> Self {
+        Self { schemas }
+    }
+}
+
+impl Default for SchemaProcessor {
+    fn default() -> Self {
+        Self::new(Arc::new(Schemas::default()))
+    }
+}
+
+impl ResourceProcessor for SchemaProcessor {
+    fn process(&self, resource: &mut Resource) -> Result<(), CoreError> {
+        let migrated = self.schemas.migrate(&mut resource.data);
+        if !migrated.is_empty() {
+            log::info!("Migrated string-encoded fields of {}: {}", resource.id, migrated.join(", "));
+        }
+        
+        Ok(self.schemas.validate(&resource.data)?)
+    }
+    
+    fn can_handle(&self, _resource_type: &ResourceType) -> bool {
+        true
+    }
+}
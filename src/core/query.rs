@@ -0,0 +1,261 @@
+use crate::models::Resource;
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+use std::collections::HashMap;
+
+use super::condition::{Condition, Projection};
+
+/// Parameters of a list request
+///
+/// `filter` matches a substring of the resource name, `condition` is a
+/// structured filter sent as the `q` parameter, `page` is 1-based and uses
+/// `limit` as the page size, and `sort` names a field, prefixed with `-` for
+/// descending order. `fields` selects the fields shown for each result and
+/// does not change which resources are returned.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct ListQuery {
+    /// Substring the resource name must contain
+    pub filter: Option<String>,
+    /// Structured filter in the compact text syntax
+    #[serde(rename = "q", default, skip_serializing_if = "Option::is_none")]
+    pub condition: Option<Condition>,
+    /// Fields to project each result onto
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fields: Option<Projection>,
+    /// Maximum number of resources to return
+    pub limit: Option<usize>,
+    /// Page number, starting at 1
+    pub page: Option<usize>,
+    /// Sort field, e.g. `name` or `-updated_at`
+    pub sort: Option<String>,
+}
+
+/// Fields resources can be sorted by locally
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum SortField {
+    Id,
+    Name,
+    Type,
+    Owner,
+    CreatedAt,
+    UpdatedAt,
+}
+
+impl ListQuery {
+    /// Create a query matching every resource
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the name filter
+    pub fn with_filter(mut self, filter: &str) -> Self {
+        self.filter = Some(filter.to_string());
+        self
+    }
+
+    /// Set the structured filter
+    pub fn with_condition(mut self, condition: Condition) -> Self {
+        self.condition = Some(condition);
+        self
+    }
+    
+    /// Set the projected fields
+    pub fn with_fields(mut self, fields: Projection) -> Self {
+        self.fields = Some(fields);
+        self
+    }
+
+    /// Set the maximum number of results
+    pub fn with_limit(mut self, limit: usize) -> Self {
+        self.limit = Some(limit);
+        self
+    }
+
+    /// Set the page number
+    pub fn with_page(mut self, page: usize) -> Self {
+        self.page = Some(page);
+        self
+    }
+
+    /// Set the sort field
+    pub fn with_sort(mut self, sort: &str) -> Self {
+        self.sort = Some(sort.to_string());
+        self
+    }
+
+    /// The same query without limit and page, i.e. every matching resource
+    pub fn unbounded(&self) -> Self {
+        Self {
+            limit: None,
+            page: None,
+            ..self.clone()
+        }
+    }
+
+    /// Check if the query returns every resource matching its filter
+    pub fn is_unbounded(&self) -> bool {
+        self.limit.is_none() && self.page.is_none()
+    }
+
+    /// Check if the sort order can be reproduced locally
+    pub fn has_local_sort(&self) -> bool {
+        self.sort.is_none() || self.sort_field().is_some()
+    }
+
+    /// Check if a resource matches the name filter and the structured filter
+    pub fn matches(&self, resource: &Resource) -> bool {
+        self.filter.as_deref().is_none_or(|f| resource.data.name.contains(f))
+            && self.condition.as_ref().is_none_or(|c| c.matches(resource))
+    }
+
+    /// Project a resource onto the selected fields, or the whole resource if none are selected
+    pub fn project(&self, resource: &Resource) -> serde_json::Value {
+        match &self.fields {
+            Some(fields) => fields.apply(resource),
+            None => serde_json::to_value(resource).unwrap_or(serde_json::Value::Null),
+        }
+    }
+
+    /// Evaluate the query against a set of resources
+    ///
+    /// Sort fields that cannot be evaluated locally leave the input order untouched.
+    pub fn apply(&self, resources: Vec<Resource>) -> Vec<Resource> {
+        let mut matched: Vec<Resource> = resources.into_iter().filter(|r| self.matches(r)).collect();
+
+        if let Some((field, descending)) = self.sort_field() {
+            matched.sort_by(|a, b| {
+                let ordering = compare(a, b, field).then_with(|| a.id.cmp(&b.id));
+                if descending { ordering.reverse() } else { ordering }
+            });
+        }
+
+        let offset = match (self.page, self.limit) {
+            (Some(page), Some(limit)) => page.saturating_sub(1) * limit,
+            _ => 0,
+        };
+
+        matched
+            .into_iter()
+            .skip(offset)
+            .take(self.limit.unwrap_or(usize::MAX))
+            .collect()
+    }
+
+    /// Convert the query to API query parameters
+    pub fn to_query_params(&self) -> HashMap<String, String> {
+        let mut params = HashMap::new();
+
+        if let Some(filter) = &self.filter {
+            params.insert("filter".to_string(), filter.clone());
+        }
+        if let Some(condition) = &self.condition {
+            params.insert("q".to_string(), condition.to_string());
+        }
+        if let Some(fields) = &self.fields {
+            params.insert("fields".to_string(), fields.to_string());
+        }
+        if let Some(limit) = self.limit {
+            params.insert("limit".to_string(), limit.to_string());
+        }
+        if let Some(page) = self.page {
+            params.insert("page".to_string(), page.to_string());
+        }
+        if let Some(sort) = &self.sort {
+            params.insert("sort".to_string(), sort.clone());
+        }
+
+        params
+    }
+
+    // Helper to parse the sort parameter
+    fn sort_field(&self) -> Option<(SortField, bool)> {
+        let sort = self.sort.as_deref()?;
+        let (name, descending) = match sort.strip_prefix('-') {
+            Some(name) => (name, true),
+            None => (sort, false),
+        };
+
+        let field = match name {
+            "id" => SortField::Id,
+            "name" => SortField::Name,
+            "type" => SortField::Type,
+            "owner" => SortField::Owner,
+            "created_at" => SortField::CreatedAt,
+            "updated_at" => SortField::UpdatedAt,
+            _ => return None,
+        };
+
+        Some((field, descending))
+    }
+}
+
+// Helper to compare two resources by a sort field
+fn compare(a: &Resource, b: &Resource, field: SortField) -> Ordering {
+    match field {
+        SortField::Id => a.id.cmp(&b.id),
+        SortField::Name => a.data.name.cmp(&b.data.name),
+        SortField::Type => a.data.resource_type.to_string().cmp(&b.data.resource_type.to_string()),
+        SortField::Owner => a.owner_id.cmp(&b.owner_id),
+        SortField::CreatedAt => a.created_at.cmp(&b.created_at),
+        SortField::UpdatedAt => a.updated_at.cmp(&b.updated_at),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{ResourceData, ResourceType};
+
+    fn resources() -> Vec<Resource> {
+        ["beta", "alpha", "gamma", "alphabet"]
+            .iter()
+            .enumerate()
+            .map(|(i, name)| Resource::new(&format!("r{}", i), ResourceData::new(name, ResourceType::Project)))
+            .collect()
+    }
+
+    fn names(resources: &[Resource]) -> Vec<&str> {
+        resources.iter().map(|r| r.data.name.as_str()).collect()
+    }
+
+    #[test]
+    fn test_apply_filter_sort_and_page() {
+        let query = ListQuery::new().with_sort("name");
+        assert_eq!(names(&query.apply(resources())), vec!["alpha", "alphabet", "beta", "gamma"]);
+
+        let query = ListQuery::new().with_filter("alpha").with_sort("-name");
+        assert_eq!(names(&query.apply(resources())), vec!["alphabet", "alpha"]);
+
+        let query = ListQuery::new().with_sort("name").with_limit(2).with_page(2);
+        assert_eq!(names(&query.apply(resources())), vec!["beta", "gamma"]);
+    }
+
+    #[test]
+    fn test_unknown_sort_keeps_order() {
+        let query = ListQuery::new().with_sort("popularity");
+        assert!(!query.has_local_sort());
+        assert_eq!(names(&query.apply(resources())), vec!["beta", "alpha", "gamma", "alphabet"]);
+    }
+
+    #[test]
+    fn test_to_query_params() {
+        let params = ListQuery::new().with_filter("a b").with_limit(5).to_query_params();
+        assert_eq!(params.get("filter"), Some(&"a b".to_string()));
+        assert_eq!(params.get("limit"), Some(&"5".to_string()));
+        assert_eq!(params.get("page"), None);
+    }
+
+    #[test]
+    fn test_condition_round_trips_through_params() {
+        let query = ListQuery::new()
+            .with_condition(Condition::parse("name~alpha -id:r3").unwrap())
+            .with_fields("id,name".parse().unwrap())
+            .with_sort("name");
+        assert_eq!(names(&query.apply(resources())), vec!["alpha"]);
+
+        // The server side decodes the same query from the URL
+        let encoded = serde_urlencoded::to_string(query.to_query_params()).unwrap();
+        let decoded: ListQuery = serde_urlencoded::from_str(&encoded).unwrap();
+        assert_eq!(decoded, query);
+    }
+}
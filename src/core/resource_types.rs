@@ -0,0 +1,328 @@
+use crate::models::{Resource, ResourceType, Schema};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+use std::sync::{Arc, RwLock};
+
+use super::error::CoreError;
+use super::processor::{builtin_processor, ResourceProcessor};
+
+/// Definition of a user-defined resource type
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct CustomType {
+    /// Type name, which resources of the type serialize as
+    pub name: String,
+    /// What resources of the type hold
+    #[serde(default)]
+    pub description: Option<String>,
+    /// Schema of the data fields
+    #[serde(default)]
+    pub schema: Schema,
+    /// Names of built-in processors run on resources of the type, e.g. `document` or `audit`
+    #[serde(default)]
+    pub processors: Vec<String>,
+    /// Prefix of generated resource IDs, the type name if unset
+    #[serde(default)]
+    pub id_prefix: Option<String>,
+}
+
+impl CustomType {
+    /// Create a type accepting any data
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            description: None,
+            schema: Schema::new(),
+            processors: Vec::new(),
+            id_prefix: None,
+        }
+    }
+
+    /// Set the description
+    pub fn with_description(mut self, description: &str) -> Self {
+        self.description = Some(description.to_string());
+        self
+    }
+
+    /// Set the schema of the data fields
+    pub fn with_schema(mut self, schema: Schema) -> Self {
+        self.schema = schema;
+        self
+    }
+
+    /// Run a built-in processor on resources of the type
+    pub fn with_processor(mut self, name: &str) -> Self {
+        self.processors.push(name.to_string());
+        self
+    }
+
+    /// Set the prefix of generated resource IDs
+    pub fn with_id_prefix(mut self, id_prefix: &str) -> Self {
+        self.id_prefix = Some(id_prefix.to_string());
+        self
+    }
+
+    /// Get the resource type this definition describes
+    pub fn resource_type(&self) -> ResourceType {
+        ResourceType::Custom(self.name.clone())
+    }
+
+    /// Get the prefix of generated resource IDs
+    pub fn id_prefix(&self) -> &str {
+        self.id_prefix.as_deref().unwrap_or(&self.name)
+    }
+
+    /// Check that the definition is usable
+    pub fn validate(&self) -> Result<(), CoreError> {
+        let resource_type: ResourceType = self.name.parse().map_err(CoreError::Validation)?;
+        if !resource_type.is_custom() {
+            return Err(CoreError::Validation(format!("{} is a built-in resource type", self.name)));
+        }
+        if resource_type.to_string() != self.name {
+            return Err(CoreError::Validation(format!("Resource type names must be lowercase: {}", self.name)));
+        }
+
+        if let Some(prefix) = &self.id_prefix {
+            if prefix.is_empty() || !prefix.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit()) {
+                return Err(CoreError::Validation(format!("Invalid ID prefix for {}: {}", self.name, prefix)));
+            }
+        }
+        if let Some(unknown) = self.processors.iter().find(|name| builtin_processor(name).is_none()) {
+            return Err(CoreError::Validation(format!("Unknown processor for {}: {}", self.name, unknown)));
+        }
+
+        Ok(self.schema.verify()?)
+    }
+}
+
+/// Registry of user-defined resource types
+///
+/// Types can be registered and removed while the application runs. Custom
+/// types that aren't registered are rejected wherever a registry is
+/// consulted; removing a type leaves its resources in place, but they can't
+/// be saved again until it is registered anew.
+#[derive(Debug, Default)]
+pub struct TypeRegistry {
+    types: RwLock<HashMap<String, CustomType>>,
+}
+
+impl TypeRegistry {
+    /// Create an empty registry
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Load a registry from a file of type definitions, in TOML or JSON depending on its extension
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, CoreError> {
+        let path = path.as_ref();
+        let contents = fs::read_to_string(path).map_err(|e| {
+            CoreError::Configuration(format!("Failed to read types file {}: {}", path.display(), e))
+        })?;
+
+        let file: TypesFile = match path.extension().and_then(|ext| ext.to_str()) {
+            Some("toml") => toml::from_str(&contents).map_err(|e| e.to_string()),
+            Some("json") => serde_json::from_str(&contents).map_err(|e| e.to_string()),
+            _ => {
+                return Err(CoreError::Configuration(format!(
+                    "Unsupported types file format: {}",
+                    path.display()
+                )))
+            }
+        }
+        .map_err(|e| CoreError::Configuration(format!("Invalid types file: {}", e)))?;
+
+        let registry = Self::new();
+        for definition in file.types {
+            registry
+                .register(definition)
+                .map_err(|e| CoreError::Configuration(e.to_string()))?;
+        }
+        Ok(registry)
+    }
+
+    /// Register a type, replacing any earlier definition with the same name
+    pub fn register(&self, definition: CustomType) -> Result<ResourceType, CoreError> {
+        definition.validate()?;
+
+        let resource_type = definition.resource_type();
+        self.types.write().unwrap().insert(definition.name.clone(), definition);
+        Ok(resource_type)
+    }
+
+    /// Remove a type, returning whether it was registered
+    pub fn unregister(&self, name: &str) -> bool {
+        self.types.write().unwrap().remove(name).is_some()
+    }
+
+    /// Get the definition of a type
+    pub fn get(&self, name: &str) -> Option<CustomType> {
+        self.types.read().unwrap().get(name).cloned()
+    }
+
+    /// List the registered types by name
+    pub fn list(&self) -> Vec<CustomType> {
+        let mut types: Vec<CustomType> = self.types.read().unwrap().values().cloned().collect();
+        types.sort_by(|a, b| a.name.cmp(&b.name));
+        types
+    }
+
+    /// Parse a type name, accepting built-in and registered types
+    pub fn parse(&self, name: &str) -> Result<ResourceType, CoreError> {
+        let resource_type: ResourceType = name.parse().map_err(CoreError::Validation)?;
+        self.check(&resource_type)?;
+        Ok(resource_type)
+    }
+
+    /// Check that a type is built in or registered
+    pub fn check(&self, resource_type: &ResourceType) -> Result<(), CoreError> {
+        match resource_type {
+            ResourceType::Custom(name) if !self.types.read().unwrap().contains_key(name) => {
+                Err(CoreError::Validation(format!("Unknown resource type: {}", name)))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Get the schema of a custom type
+    pub fn schema(&self, resource_type: &ResourceType) -> Option<Schema> {
+        match resource_type {
+            ResourceType::Custom(name) => self.get(name).map(|definition| definition.schema),
+            _ => None,
+        }
+    }
+
+    /// Get the prefix of generated IDs for resources of a type
+    pub fn id_prefix(&self, resource_type: &ResourceType) -> String {
+        match resource_type {
+            ResourceType::Custom(name) => self
+                .get(name)
+                .map_or_else(|| name.clone(), |definition| definition.id_prefix().to_string()),
+            other => other.to_string(),
+        }
+    }
+}
+
+/// File of custom type definitions
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct TypesFile {
+    types: Vec<CustomType>,
+}
+
+/// Processor applying the schema and processors of registered custom types
+///
+/// Built-in types pass through untouched. Definitions are looked up on every
+/// call, so types registered later take effect immediately.
+pub struct CustomTypeProcessor {
+    types: Arc<TypeRegistry>,
+}
+
+impl CustomTypeProcessor {
+    /// Create a processor for the types of a registry
+    pub fn new(types: Arc<TypeRegistry>) -> Self {
+        Self { types }
+    }
+}
+
+impl ResourceProcessor for CustomTypeProcessor {
+    fn process(&self, resource: &mut Resource) -> Result<(), CoreError> {
+        let ResourceType::Custom(name) = &resource.data.resource_type else {
+            return Ok(());
+        };
+        let definition = self
+            .types
+            .get(name)
+            .ok_or_else(|| CoreError::Validation(format!("Unknown resource type: {}", name)))?;
+
+        definition.schema.migrate(&mut resource.data.data);
+        definition.schema.validate(&resource.data.data)?;
+        for processor in definition.processors.iter().filter_map(|name| builtin_processor(name)) {
+            processor.process(resource)?;
+        }
+
+        Ok(())
+    }
+
+    fn can_handle(&self, resource_type: &ResourceType) -> bool {
+        resource_type.is_custom()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{FieldSchema, FieldType, ResourceData};
+
+    fn invoice() -> CustomType {
+        CustomType::new("invoice")
+            .with_id_prefix("inv")
+            .with_processor("audit")
+            .with_schema(Schema::new().with_field("amount", FieldSchema::of(FieldType::Number).required()))
+    }
+
+    #[test]
+    fn test_register_and_parse_types() {
+        let types = TypeRegistry::new();
+        assert!(types.parse("invoice").is_err());
+        assert_eq!(types.register(invoice()).unwrap(), ResourceType::Custom("invoice".to_string()));
+        assert_eq!(types.parse("invoice").unwrap(), ResourceType::Custom("invoice".to_string()));
+        assert_eq!(types.parse("Document").unwrap(), ResourceType::Document);
+        assert_eq!(types.id_prefix(&ResourceType::Custom("invoice".to_string())), "inv");
+
+        // Built-in names, bad names and unknown processors are refused
+        assert!(types.register(CustomType::new("document")).is_err());
+        assert!(types.register(CustomType::new("Receipt")).is_err());
+        assert!(types.register(CustomType::new("9lives")).is_err());
+        assert!(types.register(CustomType::new("receipt").with_processor("ocr")).is_err());
+
+        // Custom types serialize as their name
+        let data = ResourceData::new("March", types.parse("invoice").unwrap());
+        let json = serde_json::to_value(&data).unwrap();
+        assert_eq!(json["resource_type"], "invoice");
+        assert_eq!(serde_json::from_value::<ResourceData>(json).unwrap(), data);
+
+        assert!(types.unregister("invoice"));
+        assert!(types.parse("invoice").is_err());
+    }
+
+    #[test]
+    fn test_load_types_file() {
+        let path = std::env::temp_dir().join(format!("types-{}.toml", crate::utils::id::generate_uuid()));
+        fs::write(
+            &path,
+            r#"
+            [[types]]
+            name = "invoice"
+            id_prefix = "inv"
+            processors = ["audit"]
+
+            [types.schema.fields.amount]
+            type = "number"
+            required = true
+            minimum = 0
+            "#,
+        )
+        .unwrap();
+
+        let types = TypeRegistry::load(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+        assert_eq!(types.list(), vec![invoice().with_schema(
+            Schema::new().with_field("amount", FieldSchema::of(FieldType::Number).required().with_range(Some(0.0), None)),
+        )]);
+
+        // Resources of the type get its schema and processors
+        let processor = CustomTypeProcessor::new(Arc::new(types));
+        let data = ResourceData::new("March", ResourceType::Custom("invoice".to_string())).with_data("amount", "12.5");
+        let mut resource = Resource::new("inv-1", data);
+        processor.process(&mut resource).unwrap();
+        assert_eq!(resource.data.data["amount"], 12.5);
+        assert!(resource.data.data.contains_key("last_modified"));
+
+        resource.data.data.insert("amount".to_string(), (-1).into());
+        assert!(processor.process(&mut resource).is_err());
+        resource.data.resource_type = ResourceType::Custom("receipt".to_string());
+        assert!(processor.process(&mut resource).is_err());
+    }
+}
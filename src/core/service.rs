@@ -1,11 +1,21 @@
-use crate::api::{ApiClient, ApiError};
-use crate::models::{Resource, ResourceData, ResourceType};
-use crate::Config;
+use crate::api::{ApiClient, ApiError, ApiRequest};
+use crate::models::revision::{self, FieldChange, Revision};
+use crate::models::{AclEntry, Principal, Resource, ResourceData, Schemas};
+use crate::{CacheConfig, Config};
 use async_trait::async_trait;
 use std::sync::Arc;
-use tokio::sync::RwLock;
+use tokio::sync::Mutex;
 
-use super::error::CoreError;
+use super::cache::{open_backend, CacheBackend, CacheLookup, SingleFlight};
+use super::error::{external_error, CoreError};
+use super::query::ListQuery;
+use super::resource_types::TypeRegistry;
+
+/// Cache namespace holding resources
+const RESOURCES_CACHE_NAMESPACE: &str = "resources";
+
+/// Cache namespace holding resource listings
+const LISTINGS_CACHE_NAMESPACE: &str = "listings";
 
 /// Generic service trait for resource operations
 #[async_trait]
@@ -24,68 +34,180 @@ pub trait Service<T> {
     
     /// List all resources with optional filtering
     async fn list(&self, limit: Option<usize>, filter: Option<&str>) -> Result<Vec<T>, CoreError>;
+    
+    /// Get a resource by ID from the source, bypassing any cache
+    async fn get_uncached(&self, id: &str) -> Result<T, CoreError> {
+        self.get(id).await
+    }
+    
+    /// List all resources from the source, bypassing any cache
+    async fn list_uncached(&self) -> Result<Vec<T>, CoreError> {
+        self.list(None, None).await
+    }
 }
 
 /// Primary implementation of the Service trait for Resource types
+///
+/// Concurrent reads of the same resource or listing share a single request,
+/// and expired entries are served while they are refreshed in the background.
+#[derive(Clone)]
 pub struct ResourceService {
     client: Arc<ApiClient>,
-    cache: Arc<RwLock<ResourceCache>>,
+    cache: Arc<Mutex<ResourceCache>>,
+    resource_flights: Arc<SingleFlight<String, Result<Resource, CoreError>>>,
+    listing_flights: Arc<SingleFlight<ListQuery, Result<Vec<Resource>, CoreError>>>,
+    schemas: Arc<Schemas>,
+    types: Arc<TypeRegistry>,
 }
 
-/// Simple in-memory cache for resources
+/// Cache for resources keyed by ID, and for listings keyed by query
 struct ResourceCache {
-    resources: Vec<Resource>,
-    last_updated: chrono::DateTime<chrono::Utc>,
+    entries: Box<dyn CacheBackend<String, Resource>>,
+    listings: Box<dyn CacheBackend<ListQuery, Vec<Resource>>>,
+    config: CacheConfig,
+    /// Bumped on every invalidation so fetches started earlier don't store outdated data
+    generation: u64,
 }
 
 impl ResourceCache {
-    /// Create a new empty cache
-    fn new() -> Self {
+    /// Create a cache with the given policy
+    fn new(config: CacheConfig) -> Self {
         Self {
-            resources: Vec::new(),
-            last_updated: chrono::Utc::now(),
+            entries: open_backend(&config, RESOURCES_CACHE_NAMESPACE),
+            listings: open_backend(&config, LISTINGS_CACHE_NAMESPACE),
+            config,
+            generation: 0,
         }
     }
     
-    /// Check if the cache is stale (older than 5 minutes)
-    fn is_stale(&self) -> bool {
-        let now = chrono::Utc::now();
-        let age = now - self.last_updated;
-        age.num_minutes() > 5
+    /// Look up a resource by ID
+    fn get(&mut self, id: &str) -> CacheLookup<Resource> {
+        self.entries.get(&id.to_string())
+    }
+    
+    /// Store a resource fetched from or written to the API
+    fn put(&mut self, resource: Resource) {
+        self.entries.insert(resource.id.clone(), resource, self.config.ttl);
+    }
+    
+    /// Remember that a resource does not exist
+    fn put_missing(&mut self, id: &str) {
+        self.entries.insert_missing(id.to_string(), self.config.negative_ttl);
+    }
+    
+    /// Drop a single resource and any listing that may contain it
+    fn invalidate(&mut self, id: &str) {
+        self.entries.remove(&id.to_string());
+        self.listings.clear();
+        self.generation += 1;
     }
     
-    /// Update the cache with new data
-    fn update(&mut self, resources: Vec<Resource>) {
-        self.resources = resources;
-        self.last_updated = chrono::Utc::now();
+    /// Answer a list query from the cache
+    ///
+    /// Besides exact matches, a query is answered from a cached listing that
+    /// provably holds every resource it could return: the unbounded listing
+    /// with the same filters and sort, the unbounded listing without the name
+    /// filter or without any filter and with the same sort, or the complete
+    /// listing if the sort can be reproduced locally.
+    /// A stale exact match is only returned when no fresh answer exists.
+    fn listing(&mut self, query: &ListQuery) -> CacheLookup<Vec<Resource>> {
+        let exact = match self.listings.get(query) {
+            CacheLookup::Hit(resources) => return CacheLookup::Hit(resources),
+            CacheLookup::Stale(resources) => Some(resources),
+            _ => None,
+        };
+        
+        let mut candidates = vec![
+            query.unbounded(),
+            ListQuery { filter: None, ..query.unbounded() },
+            ListQuery { filter: None, condition: None, ..query.unbounded() },
+        ];
+        if query.sort.is_some() && query.has_local_sort() {
+            candidates.push(ListQuery::new());
+        }
+        
+        let derived = candidates
+            .iter()
+            .filter(|candidate| *candidate != query)
+            .find_map(|candidate| match self.listings.get(candidate) {
+                CacheLookup::Hit(resources) => Some(query.apply(resources)),
+                _ => None,
+            });
+        
+        match (derived, exact) {
+            (Some(resources), _) => CacheLookup::Hit(resources),
+            (None, Some(resources)) => CacheLookup::Stale(resources),
+            (None, None) => CacheLookup::Miss,
+        }
     }
     
-    /// Get a resource by ID from the cache
-    fn get(&self, id: &str) -> Option<Resource> {
-        self.resources.iter().find(|r| r.id == id).cloned()
+    /// Store a listing and the resources it contains
+    fn put_listing(&mut self, query: &ListQuery, resources: Vec<Resource>) {
+        for resource in &resources {
+            self.put(resource.clone());
+        }
+        
+        // A first page shorter than its limit holds every matching resource
+        let exhausted = query.page.unwrap_or(1) == 1
+            && query.limit.is_some_and(|limit| resources.len() < limit);
+        if exhausted {
+            self.listings.insert(query.unbounded(), resources.clone(), self.config.ttl);
+        }
+        
+        self.listings.insert(query.clone(), resources, self.config.ttl);
+    }
+    
+    /// Remove everything
+    fn clear(&mut self) {
+        self.entries.clear();
+        self.listings.clear();
+        self.generation += 1;
     }
 }
 
 impl ResourceService {
     /// Create a new ResourceService with the given configuration
     pub fn new(config: Config) -> Result<Self, CoreError> {
+        let cache = ResourceCache::new(config.scoped_cache());
         let client = ApiClient::new(config)
             .map_err(|e| CoreError::ExternalService(format!("Failed to create API client: {}", e)))?;
             
         Ok(Self {
             client: Arc::new(client),
-            cache: Arc::new(RwLock::new(ResourceCache::new())),
+            cache: Arc::new(Mutex::new(cache)),
+            resource_flights: Arc::new(SingleFlight::new()),
+            listing_flights: Arc::new(SingleFlight::new()),
+            schemas: Arc::new(Schemas::default()),
+            types: Arc::new(TypeRegistry::new()),
         })
     }
     
     /// Create a new ResourceService with an existing API client
     pub fn with_client(client: Arc<ApiClient>) -> Self {
+        let cache = ResourceCache::new(client.config().scoped_cache());
+        
         Self {
             client,
-            cache: Arc::new(RwLock::new(ResourceCache::new())),
+            cache: Arc::new(Mutex::new(cache)),
+            resource_flights: Arc::new(SingleFlight::new()),
+            listing_flights: Arc::new(SingleFlight::new()),
+            schemas: Arc::new(Schemas::default()),
+            types: Arc::new(TypeRegistry::new()),
         }
     }
     
+    /// Check resource data against the given schemas instead of the built-in ones
+    pub fn with_schemas(mut self, schemas: Schemas) -> Self {
+        self.schemas = Arc::new(schemas);
+        self
+    }
+    
+    /// Check resources of the custom types of a registry against their schemas before sending
+    pub fn with_types(mut self, types: Arc<TypeRegistry>) -> Self {
+        self.types = types;
+        self
+    }
+    
     /// Get the API client
     pub fn client(&self) -> Arc<ApiClient> {
         self.client.clone()
@@ -93,11 +215,180 @@ impl ResourceService {
     
     /// Invalidate the cache, forcing a refresh on next fetch
     pub async fn invalidate_cache(&self) {
-        let mut cache = self.cache.write().await;
-        cache.last_updated = chrono::DateTime::<chrono::Utc>::from_utc(
-            chrono::NaiveDateTime::from_timestamp(0, 0), 
-            chrono::Utc
-        );
+        self.cache.lock().await.clear();
+    }
+    
+    /// List resources matching a query, answering from the cache when possible
+    ///
+    /// Resources are always fetched whole so they can be cached; apply the
+    /// query's projection with `ListQuery::project` when presenting them.
+    pub async fn list_with(&self, query: &ListQuery) -> Result<Vec<Resource>, CoreError> {
+        let query = &ListQuery { fields: None, ..query.clone() };
+        
+        // Check the cache first
+        let cached = self.cache.lock().await.listing(query);
+        match cached {
+            CacheLookup::Hit(resources) => return Ok(resources),
+            CacheLookup::Stale(resources) => {
+                let service = self.clone();
+                let query = query.clone();
+                tokio::spawn(async move { service.fetch_listing(&query).await });
+                return Ok(resources);
+            }
+            CacheLookup::NotFound | CacheLookup::Miss => {}
+        }
+        
+        // Cache miss, fetch from the API
+        self.fetch_listing(query).await
+    }
+    
+    /// Grant a user or group permissions on a resource
+    ///
+    /// The grant is recorded in the resource's ACL audit trail.
+    pub async fn share(&self, id: &str, entry: AclEntry) -> Result<Resource, CoreError> {
+        entry.validate().map_err(CoreError::Validation)?;
+        
+        let request = ApiRequest::put(&format!("resources/{}/acl", id)).with_body(&entry);
+        let result = self.client.execute::<Resource, &AclEntry>(request)
+            .await
+            .map(|response| response.into_body())
+            .map_err(|e| map_nested_error(e, "Resource or access entry", id, "share this resource"))?;
+        
+        let mut cache = self.cache.lock().await;
+        cache.invalidate(id);
+        cache.put(result.clone());
+        
+        Ok(result)
+    }
+    
+    /// Remove a user's or group's ACL entry from a resource
+    pub async fn unshare(&self, id: &str, principal: &Principal) -> Result<Resource, CoreError> {
+        let request = ApiRequest::<()>::delete(&format!("resources/{}/acl/{}", id, principal));
+        let result = self.client.execute::<Resource, ()>(request)
+            .await
+            .map(|response| response.into_body())
+            .map_err(|e| map_nested_error(e, "Resource or access entry", id, "unshare this resource"))?;
+        
+        let mut cache = self.cache.lock().await;
+        cache.invalidate(id);
+        cache.put(result.clone());
+        
+        Ok(result)
+    }
+    
+    /// List the recorded revisions of a resource, oldest first
+    pub async fn revisions(&self, id: &str) -> Result<Vec<Revision>, CoreError> {
+        self.client.get::<Vec<Revision>>(&format!("resources/{}/revisions", id))
+            .await
+            .map_err(|e| map_nested_error(e, "Resource", id, "read this resource's history"))
+    }
+    
+    /// Get a past revision of a resource
+    pub async fn revision(&self, id: &str, number: u64) -> Result<Revision, CoreError> {
+        self.client.get::<Revision>(&format!("resources/{}/revisions/{}", id, number))
+            .await
+            .map_err(|e| map_nested_error(e, "Resource or revision", id, "read this resource's history"))
+    }
+    
+    /// List the resources whose parent is the given resource
+    pub async fn children(&self, id: &str) -> Result<Vec<Resource>, CoreError> {
+        self.client.get::<Vec<Resource>>(&format!("resources/{}/children", id))
+            .await
+            .map_err(|e| map_nested_error(e, "Resource", id, "read this resource"))
+    }
+    
+    /// List the resources linking to the given resource
+    pub async fn backlinks(&self, id: &str) -> Result<Vec<Resource>, CoreError> {
+        self.client.get::<Vec<Resource>>(&format!("resources/{}/backlinks", id))
+            .await
+            .map_err(|e| map_nested_error(e, "Resource", id, "read this resource"))
+    }
+    
+    /// Compare two revisions of a resource
+    pub async fn diff(&self, id: &str, from: u64, to: u64) -> Result<Vec<FieldChange>, CoreError> {
+        let before = self.revision(id, from).await?;
+        let after = self.revision(id, to).await?;
+        
+        Ok(revision::diff(&before.data, &after.data))
+    }
+    
+    /// Restore a past revision's data, recorded as a new revision
+    pub async fn restore(&self, id: &str, number: u64) -> Result<Resource, CoreError> {
+        let request = ApiRequest::<()>::post(&format!("resources/{}/revisions/{}/restore", id, number));
+        let result = self.client.execute::<Resource, ()>(request)
+            .await
+            .map(|response| response.into_body())
+            .map_err(|e| map_nested_error(e, "Resource or revision", id, "restore this resource"))?;
+        
+        let mut cache = self.cache.lock().await;
+        cache.invalidate(id);
+        cache.put(result.clone());
+        
+        Ok(result)
+    }
+    
+    // Helper to fetch a resource, joining a fetch of the same ID already in flight
+    async fn fetch_resource(&self, id: &str) -> Result<Resource, CoreError> {
+        let client = self.client.clone();
+        let cache = self.cache.clone();
+        let key = id.to_string();
+        
+        self.resource_flights.run(id.to_string(), move || async move {
+            let generation = cache.lock().await.generation;
+            let result = client.get::<Resource>(&format!("resources/{}", key)).await;
+            
+            // Only record the outcome if nothing was written since the fetch began
+            let mut cache = cache.lock().await;
+            let current = cache.generation == generation;
+            match result {
+                Ok(resource) => {
+                    if current {
+                        cache.put(resource.clone());
+                    }
+                    Ok(resource)
+                }
+                Err(ApiError::ResourceNotFound) => {
+                    if current {
+                        cache.put_missing(&key);
+                    }
+                    Err(CoreError::NotFound(format!("Resource not found: {}", key)))
+                }
+                Err(ApiError::Unauthorized | ApiError::Forbidden) => {
+                    Err(CoreError::PermissionDenied("Not authorized to access this resource".to_string()))
+                }
+                Err(e) => Err(external_error(e)),
+            }
+        }).await
+    }
+    
+    // Helper to fetch a listing, joining a fetch of the same query already in flight
+    async fn fetch_listing(&self, query: &ListQuery) -> Result<Vec<Resource>, CoreError> {
+        let client = self.client.clone();
+        let cache = self.cache.clone();
+        let key = query.clone();
+        
+        self.listing_flights.run(query.clone(), move || async move {
+            let generation = cache.lock().await.generation;
+            let request = ApiRequest::<()>::get("resources").with_query_params(key.to_query_params());
+            let result = client.execute::<Vec<Resource>, ()>(request)
+                .await
+                .map(|response| response.into_body())
+                .map_err(|e| match e {
+                    ApiError::Unauthorized | ApiError::Forbidden => {
+                        CoreError::PermissionDenied("Not authorized to list resources".to_string())
+                    }
+                    ApiError::ServerError(400 | 422, msg) => CoreError::Validation(msg),
+                    e => external_error(e),
+                })?;
+            
+            // Only record the listing if nothing was written since the fetch began
+            let mut cache = cache.lock().await;
+            if cache.generation == generation {
+                cache.put_listing(&key, result.clone());
+            }
+            
+            Ok(result)
+        }).await
     }
     
     /// Validate resource data before sending to the API
@@ -110,31 +401,30 @@ impl ResourceService {
             return Err(CoreError::Validation("Resource name too long (max 100 characters)".to_string()));
         }
         
-        // Validate specific resource types
-        match data.resource_type {
-            ResourceType::Document => {
-                // Documents should have content
-                if data.data.get("content").is_none() {
-                    return Err(CoreError::Validation("Document must have content".to_string()));
-                }
-            }
-            ResourceType::User => {
-                // Users should have an email
-                if data.data.get("email").is_none() {
-                    return Err(CoreError::Validation("User must have an email".to_string()));
-                }
-            }
-            _ => {}
+        // Validate the data fields against the schema of the resource type; the
+        // server decides on custom types this client doesn't know about
+        match self.types.schema(&data.resource_type) {
+            Some(schema) => schema.validate(&data.data)?,
+            None => self.schemas.validate(data)?,
         }
         
         Ok(())
     }
+    
+    // Helper to convert string-encoded values using the schema of the resource type
+    fn migrate(&self, data: &mut ResourceData) {
+        match self.types.schema(&data.resource_type) {
+            Some(schema) => schema.migrate(&mut data.data),
+            None => self.schemas.migrate(data),
+        };
+    }
 }
 
 #[async_trait]
 impl Service<Resource> for ResourceService {
-    async fn create(&self, resource: Resource) -> Result<Resource, CoreError> {
-        // Validate the resource data
+    async fn create(&self, mut resource: Resource) -> Result<Resource, CoreError> {
+        // Read string-encoded values, then validate the resource data
+        self.migrate(&mut resource.data);
         self.validate(&resource.data)?;
         
         // Send the request to the API
@@ -142,41 +432,45 @@ impl Service<Resource> for ResourceService {
             .await
             .map_err(|e| match e {
                 ApiError::ResponseParseError(msg) => CoreError::Processing(msg),
-                ApiError::Unauthorized => CoreError::PermissionDenied("Not authorized to create resources".to_string()),
-                _ => CoreError::ExternalService(format!("API error: {}", e)),
+                ApiError::Conflict(_) => CoreError::AlreadyExists(resource.id.clone()),
+                ApiError::Unauthorized | ApiError::Forbidden => {
+                    CoreError::PermissionDenied("Not authorized to create resources".to_string())
+                }
+                ApiError::ServerError(400 | 422, msg) => CoreError::Validation(msg),
+                e => external_error(e),
             })?;
         
-        // Invalidate the cache since we've modified data
-        self.invalidate_cache().await;
+        // Write through to the cache
+        let mut cache = self.cache.lock().await;
+        cache.invalidate(&result.id);
+        cache.put(result.clone());
         
         Ok(result)
     }
     
     async fn get(&self, id: &str) -> Result<Resource, CoreError> {
         // Check the cache first
-        let cache = self.cache.read().await;
-        
-        if !cache.is_stale() {
-            if let Some(resource) = cache.get(id) {
+        let cached = self.cache.lock().await.get(id);
+        match cached {
+            CacheLookup::Hit(resource) => return Ok(resource),
+            CacheLookup::Stale(resource) => {
+                // Serve the expired copy and refresh it in the background
+                let service = self.clone();
+                let id = id.to_string();
+                tokio::spawn(async move { service.fetch_resource(&id).await });
                 return Ok(resource);
             }
+            CacheLookup::NotFound => return Err(CoreError::NotFound(format!("Resource not found: {}", id))),
+            CacheLookup::Miss => {}
         }
-        drop(cache); // Release the read lock
         
-        // Cache miss or stale, fetch from API
-        let result = self.client.get::<Resource>(&format!("resources/{}", id))
-            .await
-            .map_err(|e| match e {
-                ApiError::ResourceNotFound => CoreError::NotFound(format!("Resource not found: {}", id)),
-                ApiError::Unauthorized => CoreError::PermissionDenied("Not authorized to access this resource".to_string()),
-                _ => CoreError::ExternalService(format!("API error: {}", e)),
-            })?;
-        
-        Ok(result)
+        // Cache miss, fetch from API
+        self.fetch_resource(id).await
     }
     
-    async fn update(&self, id: &str, resource: Resource) -> Result<Resource, CoreError> {
-        // Validate the resource data
+    async fn update(&self, id: &str, mut resource: Resource) -> Result<Resource, CoreError> {
+        // Read string-encoded values, then validate the resource data
+        self.migrate(&mut resource.data);
         self.validate(&resource.data)?;
         
         // Ensure the ID in the path matches the ID in the resource
@@ -184,89 +478,289 @@ impl Service<Resource> for ResourceService {
             return Err(CoreError::Validation("Resource ID mismatch".to_string()));
         }
         
-        // Send the request to the API
-        let result = self.client.post::<Resource, Resource>(&format!("resources/{}", id), &resource)
-            .await
-            .map_err(|e| match e {
+        // Send the request to the API, only applying it to the version it was based on
+        let request = ApiRequest::post(&format!("resources/{}", id))
+            .with_header("If-Match", &resource.etag())
+            .with_body(&resource);
+        let result = match self.client.execute::<Resource, &Resource>(request).await {
+            Ok(response) => response.into_body(),
+            Err(ApiError::Conflict(_)) => {
+                // The cached copy is stale too, so the next read fetches the current version
+                self.cache.lock().await.invalidate(id);
+                return Err(CoreError::Conflict(format!("Resource {} changed since version {}", id, resource.version)));
+            }
+            Err(e) => return Err(match e {
                 ApiError::ResourceNotFound => CoreError::NotFound(format!("Resource not found: {}", id)),
-                ApiError::Unauthorized => CoreError::PermissionDenied("Not authorized to update this resource".to_string()),
-                _ => CoreError::ExternalService(format!("API error: {}", e)),
-            })?;
+                ApiError::Unauthorized | ApiError::Forbidden => {
+                    CoreError::PermissionDenied("Not authorized to update this resource".to_string())
+                }
+                ApiError::ServerError(400 | 422, msg) => CoreError::Validation(msg),
+                e => external_error(e),
+            }),
+        };
         
-        // Invalidate the cache since we've modified data
-        self.invalidate_cache().await;
+        // Write through to the cache
+        let mut cache = self.cache.lock().await;
+        cache.invalidate(id);
+        cache.put(result.clone());
         
         Ok(result)
     }
     
     async fn delete(&self, id: &str) -> Result<bool, CoreError> {
         // Send the request to the API
-        let result: bool = self.client.get(&format!("resources/{}/delete", id))
+        let request = ApiRequest::<()>::delete(&format!("resources/{}", id));
+        let deleted = self.client.execute::<Vec<String>, ()>(request)
             .await
+            .map(|response| response.into_body())
             .map_err(|e| match e {
                 ApiError::ResourceNotFound => CoreError::NotFound(format!("Resource not found: {}", id)),
-                ApiError::Unauthorized => CoreError::PermissionDenied("Not authorized to delete this resource".to_string()),
-                _ => CoreError::ExternalService(format!("API error: {}", e)),
+                ApiError::Unauthorized | ApiError::Forbidden => {
+                    CoreError::PermissionDenied("Not authorized to delete this resource".to_string())
+                }
+                ApiError::Conflict(msg) => CoreError::Conflict(msg),
+                ApiError::ServerError(400 | 422, msg) => CoreError::Validation(msg),
+                e => external_error(e),
             })?;
         
-        // Invalidate the cache since we've modified data
-        self.invalidate_cache().await;
+        // Drop the resource and any deleted along with it from the cache
+        let mut cache = self.cache.lock().await;
+        cache.invalidate(id);
+        for id in &deleted {
+            cache.invalidate(id);
+        }
         
-        Ok(result)
+        Ok(true)
     }
     
     async fn list(&self, limit: Option<usize>, filter: Option<&str>) -> Result<Vec<Resource>, CoreError> {
-        // Check the cache first
-        let cache = self.cache.read().await;
+        let query = ListQuery {
+            filter: filter.map(|f| f.to_string()),
+            limit,
+            ..ListQuery::default()
+        };
         
-        if !cache.is_stale() {
-            let resources = cache.resources.clone();
-            drop(cache); // Release the read lock
-            
-            let filtered = match filter {
-                Some(f) => resources.into_iter()
-                    .filter(|r| r.data.name.contains(f))
-                    .collect::<Vec<_>>(),
-                None => resources,
-            };
-            
-            let limited = match limit {
-                Some(l) => filtered.into_iter().take(l).collect(),
-                None => filtered,
-            };
-            
-            return Ok(limited);
-        }
-        drop(cache); // Release the read lock
-        
-        // Cache is stale, fetch from API
-        let mut endpoint = String::from("resources");
-        
-        // Add query parameters if needed
-        let mut query_params = Vec::new();
-        if let Some(l) = limit {
-            query_params.push(format!("limit={}", l));
+        self.list_with(&query).await
+    }
+    
+    async fn get_uncached(&self, id: &str) -> Result<Resource, CoreError> {
+        // Fresh results still refresh the cache for later reads
+        self.fetch_resource(id).await
+    }
+    
+    async fn list_uncached(&self) -> Result<Vec<Resource>, CoreError> {
+        self.fetch_listing(&ListQuery::default()).await
+    }
+}
+
+// Helper to map API errors from the endpoints nested under a resource
+fn map_nested_error(error: ApiError, missing: &str, id: &str, action: &str) -> CoreError {
+    match error {
+        ApiError::ResourceNotFound => CoreError::NotFound(format!("{} not found: {}", missing, id)),
+        ApiError::Conflict(_) => CoreError::Conflict(format!("Resource {} changed while trying to {}", id, action)),
+        ApiError::Unauthorized | ApiError::Forbidden => {
+            CoreError::PermissionDenied(format!("Not authorized to {}", action))
         }
-        if let Some(f) = filter {
-            query_params.push(format!("filter={}", f));
+        ApiError::ServerError(400, msg) => CoreError::Validation(msg),
+        e => external_error(e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::ResourceType;
+
+    fn test_service(api_url: String) -> ResourceService {
+        ResourceService::new(Config {
+            api_url,
+            max_retries: 0,
+            ..Config::default()
+        })
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_get_populates_cache() {
+        let mut server = mockito::Server::new_async().await;
+        let body = serde_json::to_string(&Resource::new("doc-1", ResourceData::new("Notes", ResourceType::Project))).unwrap();
+        let found = server.mock("GET", "/resources/doc-1").with_status(200).with_body(body).expect(1).create_async().await;
+        let missing = server.mock("GET", "/resources/doc-2").with_status(404).expect(1).create_async().await;
+
+        let service = test_service(server.url());
+        for _ in 0..2 {
+            assert_eq!(service.get("doc-1").await.unwrap().data.name, "Notes");
+            assert!(matches!(service.get("doc-2").await, Err(CoreError::NotFound(_))));
         }
-        
-        if !query_params.is_empty() {
-            endpoint = format!("{}?{}", endpoint, query_params.join("&"));
+
+        found.assert_async().await;
+        missing.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn test_stale_update_is_a_conflict() {
+        let mut server = mockito::Server::new_async().await;
+        let mut resource = Resource::new("doc-1", ResourceData::new("Notes", ResourceType::Project));
+        resource.version = 3;
+        let read = server.mock("GET", "/resources/doc-1").with_body(serde_json::to_string(&resource).unwrap()).expect(2).create_async().await;
+        let stale = server.mock("POST", "/resources/doc-1").match_header("if-match", "\"3\"").with_status(412).create_async().await;
+
+        let service = test_service(server.url());
+        let mut edited = service.get("doc-1").await.unwrap();
+        edited.data.name = "Edited".to_string();
+        assert!(matches!(service.update("doc-1", edited.clone()).await, Err(CoreError::Conflict(_))));
+        stale.assert_async().await;
+
+        // A 409 maps the same way, and the stale cached copy is refetched afterwards
+        server.mock("POST", "/resources/doc-1").with_status(409).create_async().await;
+        assert!(matches!(service.update("doc-1", edited).await, Err(CoreError::Conflict(_))));
+        service.get("doc-1").await.unwrap();
+        read.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn test_errors_tell_outages_from_rejections() {
+        let mut server = mockito::Server::new_async().await;
+        server.mock("GET", "/resources/doc-1").with_status(503).create_async().await;
+        server.mock("GET", "/resources/doc-2").with_status(403).create_async().await;
+        server.mock("POST", "/resources").with_status(422).with_body("Invalid data").create_async().await;
+
+        let service = test_service(server.url());
+        assert!(matches!(service.get("doc-1").await, Err(CoreError::Unavailable(_))));
+        assert!(matches!(service.get("doc-2").await, Err(CoreError::PermissionDenied(_))));
+        let created = service.create(Resource::new("prj-1", ResourceData::new("Plan", ResourceType::Project))).await;
+        assert!(matches!(created, Err(CoreError::Validation(msg)) if msg == "Invalid data"));
+    }
+
+    #[tokio::test]
+    async fn test_list_cache_respects_query() {
+        let mut server = mockito::Server::new_async().await;
+        let resources: Vec<Resource> = ["alpha", "beta", "alphabet"]
+            .iter()
+            .map(|name| Resource::new(name, ResourceData::new(name, ResourceType::Project)))
+            .collect();
+        let filtered = server
+            .mock("GET", "/resources")
+            .match_query(mockito::Matcher::UrlEncoded("filter".into(), "beta".into()))
+            .with_status(200)
+            .with_body(serde_json::to_string(&resources[1..2]).unwrap())
+            .expect(1)
+            .create_async()
+            .await;
+        let everything = server
+            .mock("GET", "/resources")
+            .match_query(mockito::Matcher::Missing)
+            .with_status(200)
+            .with_body(serde_json::to_string(&resources).unwrap())
+            .expect(1)
+            .create_async()
+            .await;
+
+        let service = test_service(server.url());
+
+        // A filtered listing must not answer an unfiltered query
+        assert_eq!(service.list(None, Some("beta")).await.unwrap().len(), 1);
+        assert_eq!(service.list(None, None).await.unwrap().len(), 3);
+
+        // Narrower queries are derived from the complete listing
+        let narrowed = service.list_with(&ListQuery::new().with_filter("alpha").with_sort("-name")).await.unwrap();
+        assert_eq!(narrowed.iter().map(|r| r.id.as_str()).collect::<Vec<_>>(), vec!["alphabet", "alpha"]);
+        assert_eq!(service.list(Some(2), None).await.unwrap().len(), 2);
+
+        filtered.assert_async().await;
+        everything.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_gets_share_one_request() {
+        let mut server = mockito::Server::new_async().await;
+        let body = serde_json::to_string(&Resource::new("doc-1", ResourceData::new("Notes", ResourceType::Project))).unwrap();
+        let m = server.mock("GET", "/resources/doc-1").with_status(200).with_body(body).expect(1).create_async().await;
+
+        let service = test_service(server.url());
+        let results = futures::future::join_all((0..5).map(|_| service.get("doc-1"))).await;
+
+        assert!(results.iter().all(|r| r.as_ref().unwrap().data.name == "Notes"));
+        m.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn test_stale_entry_is_served_and_refreshed() {
+        let mut server = mockito::Server::new_async().await;
+        let body = serde_json::to_string(&Resource::new("doc-1", ResourceData::new("Notes", ResourceType::Project))).unwrap();
+        let m = server.mock("GET", "/resources/doc-1").with_status(200).with_body(body).expect(2).create_async().await;
+
+        let mut config = Config {
+            api_url: server.url(),
+            max_retries: 0,
+            ..Config::default()
+        };
+        config.cache.ttl = std::time::Duration::ZERO;
+        let service = ResourceService::new(config).unwrap();
+
+        // The first read fetches, the second is answered stale and triggers a refresh
+        service.get("doc-1").await.unwrap();
+        assert_eq!(service.get("doc-1").await.unwrap().data.name, "Notes");
+
+        for _ in 0..50 {
+            if m.matched_async().await {
+                break;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(10)).await;
         }
-        
-        // Fetch from the API
-        let result = self.client.get::<Vec<Resource>>(&endpoint)
-            .await
-            .map_err(|e| match e {
-                ApiError::Unauthorized => CoreError::PermissionDenied("Not authorized to list resources".to_string()),
-                _ => CoreError::ExternalService(format!("API error: {}", e)),
-            })?;
-        
-        // Update the cache with the new data
-        let mut cache = self.cache.write().await;
-        cache.update(result.clone());
-        
-        Ok(result)
+        m.assert_async().await;
     }
-}
\ No newline at end of file
+
+    #[tokio::test]
+    async fn test_disk_cache_survives_restart() {
+        let mut server = mockito::Server::new_async().await;
+        let body = serde_json::to_string(&Resource::new("doc-1", ResourceData::new("Notes", ResourceType::Project))).unwrap();
+        let m = server.mock("GET", "/resources/doc-1").with_status(200).with_body(body).expect(1).create_async().await;
+
+        let dir = std::env::temp_dir().join(format!("service-cache-{}", crate::utils::id::generate_uuid()));
+        let mut config = Config {
+            api_url: server.url(),
+            max_retries: 0,
+            ..Config::default()
+        };
+        config.cache.dir = Some(dir.clone());
+
+        // A second service over the same directory is answered from disk
+        ResourceService::new(config.clone()).unwrap().get("doc-1").await.unwrap();
+        let service = ResourceService::new(config.clone()).unwrap();
+        assert_eq!(service.get("doc-1").await.unwrap().data.name, "Notes");
+        m.assert_async().await;
+
+        service.invalidate_cache().await;
+        let scoped = config.scoped_cache().dir.unwrap();
+        assert_eq!(std::fs::read_dir(scoped.join(RESOURCES_CACHE_NAMESPACE)).unwrap().count(), 0);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_disk_cache_is_scoped_to_backend_and_key() {
+        let mut server = mockito::Server::new_async().await;
+        let body = serde_json::to_string(&Resource::new("doc-1", ResourceData::new("Notes", ResourceType::Project))).unwrap();
+        let m = server.mock("GET", "/resources/doc-1").with_status(200).with_body(body).expect(2).create_async().await;
+
+        let dir = std::env::temp_dir().join(format!("service-cache-{}", crate::utils::id::generate_uuid()));
+        let mut config = Config {
+            api_url: server.url(),
+            api_key: Some("alice-key".to_string()),
+            max_retries: 0,
+            ..Config::default()
+        };
+        config.cache.dir = Some(dir.clone());
+        ResourceService::new(config.clone()).unwrap().get("doc-1").await.unwrap();
+
+        // Another user of the same backend doesn't see the first one's entries
+        let other = Config { api_key: Some("bob-key".to_string()), ..config.clone() };
+        ResourceService::new(other.clone()).unwrap().get("doc-1").await.unwrap();
+        m.assert_async().await;
+
+        assert_ne!(config.scoped_cache().dir, other.scoped_cache().dir);
+        let elsewhere = Config { api_url: "http://127.0.0.1:1".to_string(), ..config.clone() };
+        assert_ne!(config.scoped_cache().dir, elsewhere.scoped_cache().dir);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
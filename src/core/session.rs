@@ -0,0 +1,334 @@
+use crate::models::persistence::{GroupRepository, PersistenceError, Repository, RepositoryFactory, TokenRepository};
+use crate::models::token::hash_secret;
+use crate::models::{Permission, Token, TokenKind, User};
+use crate::utils::id::{generate_secret, generate_uuid};
+use chrono::Utc;
+use serde::Serialize;
+use std::collections::HashSet;
+use std::sync::Arc;
+use std::time::Duration;
+
+use super::error::CoreError;
+use super::policy::Policy;
+
+/// Message returned for every token that can't be used, so callers can't tell why
+const INVALID_TOKEN: &str = "Invalid or expired token";
+
+/// Newly issued token, carrying its secret
+///
+/// The secret is only available here; the stored `Token` keeps its hash.
+#[derive(Debug, Clone, Serialize)]
+pub struct IssuedToken {
+    /// Secret to hand to the client
+    pub secret: String,
+    /// Stored token record
+    pub token: Token,
+}
+
+/// User resolved from a token, with what the token lets them do
+#[derive(Debug, Clone)]
+pub struct Authenticated {
+    /// User the token acts for
+    pub user: User,
+    /// Token that was presented
+    pub token: Token,
+    /// Permissions the user holds, limited to the token's scopes
+    pub permissions: HashSet<Permission>,
+}
+
+impl Authenticated {
+    /// Check if the token may be used for a permission
+    pub fn allows(&self, permission: &Permission) -> bool {
+        self.permissions.contains(permission)
+    }
+}
+
+/// Issues, resolves and revokes session and personal API tokens
+///
+/// Secrets are random and only their hashes are stored, so tokens can't be
+/// recovered from the repository. Personal tokens are limited to scopes the
+/// user holds when they are issued, and a token never grants more than its
+/// user currently holds.
+pub struct SessionManager {
+    tokens: Arc<dyn TokenRepository + Send + Sync>,
+    users: Arc<dyn Repository<User, String> + Send + Sync>,
+    groups: Arc<dyn GroupRepository + Send + Sync>,
+    policy: Arc<Policy>,
+    session_ttl: Duration,
+}
+
+impl SessionManager {
+    /// Create a manager over the factory's repositories, with 12 hour sessions
+    pub fn new(factory: &RepositoryFactory) -> Self {
+        Self {
+            tokens: factory.token_repository(),
+            users: factory.user_repository(),
+            groups: factory.group_repository(),
+            policy: Arc::new(Policy::default()),
+            session_ttl: Duration::from_secs(12 * 60 * 60),
+        }
+    }
+
+    /// Work out permissions with the given policy instead of the built-in one
+    pub fn with_policy(mut self, policy: Arc<Policy>) -> Self {
+        self.policy = policy;
+        self
+    }
+
+    /// Set how long session tokens last
+    pub fn with_session_ttl(mut self, ttl: Duration) -> Self {
+        self.session_ttl = ttl;
+        self
+    }
+
+    /// Issue a session token for a user who has just logged in
+    pub async fn issue_session(&self, user: &User) -> Result<IssuedToken, CoreError> {
+        self.issue(user, TokenKind::Session, None, Some(self.session_ttl), None).await
+    }
+
+    /// Issue a named personal token limited to the given scopes
+    ///
+    /// Every scope must be a permission the user currently holds. Without a
+    /// time to live, the token lasts until revoked.
+    pub async fn issue_personal_token(
+        &self,
+        user: &User,
+        name: &str,
+        scopes: HashSet<Permission>,
+        ttl: Option<Duration>,
+    ) -> Result<IssuedToken, CoreError> {
+        if name.trim().is_empty() {
+            return Err(CoreError::Validation("Token name cannot be empty".to_string()));
+        }
+        if scopes.is_empty() {
+            return Err(CoreError::Validation("Token must have at least one scope".to_string()));
+        }
+
+        let held = self.permissions(user).await?;
+        let mut missing: Vec<String> = scopes.difference(&held).map(|p| p.to_string()).collect();
+        if !missing.is_empty() {
+            missing.sort();
+            return Err(CoreError::PermissionDenied(format!(
+                "User {} cannot grant scopes they do not hold: {}",
+                user.id,
+                missing.join(", ")
+            )));
+        }
+
+        self.issue(user, TokenKind::Personal, Some(name), ttl, Some(scopes)).await
+    }
+
+    /// Resolve a secret to its user and effective permissions, recording the use
+    pub async fn resolve(&self, secret: &str) -> Result<Authenticated, CoreError> {
+        let invalid = || CoreError::PermissionDenied(INVALID_TOKEN.to_string());
+
+        let mut token = self
+            .tokens
+            .find_by_secret_hash(&hash_secret(secret))
+            .await
+            .map_err(database_error)?
+            .ok_or_else(invalid)?;
+        if !token.kind.authenticates() || !token.is_active_at(Utc::now()) {
+            return Err(invalid());
+        }
+
+        let user = self
+            .users
+            .find_by_id(&token.user_id)
+            .await
+            .map_err(database_error)?
+            .ok_or_else(invalid)?;
+        // Tokens issued before the account existed belong to an earlier user with the same ID
+        if predates(&token.created_at, &user.created_at) {
+            return Err(invalid());
+        }
+        if !user.enabled {
+            return Err(CoreError::PermissionDenied(format!("User {} is {}", user.id, user.status())));
+        }
+
+        let mut permissions = self.permissions(&user).await?;
+        if let Some(scopes) = &token.scopes {
+            permissions.retain(|p| scopes.contains(p));
+        }
+
+        token.last_used_at = Some(Utc::now().to_rfc3339());
+        let token = self.tokens.save(token).await.map_err(database_error)?;
+
+        Ok(Authenticated { user, token, permissions })
+    }
+
+    /// List the tokens issued to a user, newest first
+    pub async fn tokens_for(&self, user_id: &str) -> Result<Vec<Token>, CoreError> {
+        let mut tokens = self.tokens.find_by_user(user_id).await.map_err(database_error)?;
+        tokens.sort_by(|a, b| b.created_at.cmp(&a.created_at));
+        Ok(tokens)
+    }
+
+    /// Get a token by ID
+    pub async fn token(&self, id: &str) -> Result<Token, CoreError> {
+        self.tokens
+            .find_by_id(&id.to_string())
+            .await
+            .map_err(database_error)?
+            .ok_or_else(|| CoreError::NotFound(format!("Token {}", id)))
+    }
+
+    /// Revoke a token by ID, keeping its record
+    pub async fn revoke(&self, id: &str) -> Result<Token, CoreError> {
+        let mut token = self.token(id).await?;
+        token.revoke();
+        self.tokens.save(token).await.map_err(database_error)
+    }
+
+    /// Revoke every active token of a user, returning how many were revoked
+    pub async fn revoke_all(&self, user_id: &str) -> Result<usize, CoreError> {
+        let now = Utc::now();
+        let mut revoked = 0;
+        for mut token in self.tokens.find_by_user(user_id).await.map_err(database_error)? {
+            if token.is_active_at(now) {
+                token.revoke();
+                self.tokens.save(token).await.map_err(database_error)?;
+                revoked += 1;
+            }
+        }
+        Ok(revoked)
+    }
+
+    // Helper to create and store a token with a fresh secret
+    async fn issue(
+        &self,
+        user: &User,
+        kind: TokenKind,
+        name: Option<&str>,
+        ttl: Option<Duration>,
+        scopes: Option<HashSet<Permission>>,
+    ) -> Result<IssuedToken, CoreError> {
+        if !user.enabled {
+            return Err(CoreError::PermissionDenied(format!("User {} is {}", user.id, user.status())));
+        }
+
+        let secret = generate_secret(kind.secret_prefix());
+        let mut token = Token::new(&generate_uuid(), &user.id, kind, &secret);
+        if let Some(name) = name {
+            token = token.with_name(name);
+        }
+        if let Some(scopes) = scopes {
+            token = token.with_scopes(scopes);
+        }
+        if let Some(ttl) = ttl {
+            let expires_at = chrono::Duration::from_std(ttl)
+                .ok()
+                .and_then(|ttl| Utc::now().checked_add_signed(ttl))
+                .ok_or_else(|| CoreError::Validation(format!("Token lifetime too long: {:?}", ttl)))?;
+            token = token.with_expiry(expires_at);
+        }
+
+        let token = self.tokens.save(token).await.map_err(database_error)?;
+        Ok(IssuedToken { secret, token })
+    }
+
+    // Helper to work out the permissions a user holds through the policy and their groups
+    async fn permissions(&self, user: &User) -> Result<HashSet<Permission>, CoreError> {
+        let groups = self.groups.find_by_member(&user.id).await.map_err(database_error)?;
+        Ok(self.policy.effective_permissions(user, &groups))
+    }
+}
+
+// Helper to check if one timestamp is earlier than another, treating unreadable ones as earliest
+fn predates(timestamp: &str, other: &str) -> bool {
+    let parse = |at: &str| chrono::DateTime::parse_from_rfc3339(at).ok();
+    parse(timestamp) < parse(other)
+}
+
+// Helper to report a repository failure
+fn database_error(error: PersistenceError) -> CoreError {
+    CoreError::Database(error.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::Group;
+
+    async fn setup() -> (SessionManager, RepositoryFactory) {
+        let factory = RepositoryFactory::new_in_memory();
+        factory.user_repository().save(User::new("alice", "alice@example.com", "Alice")).await.unwrap();
+        (SessionManager::new(&factory), factory)
+    }
+
+    async fn alice(factory: &RepositoryFactory) -> User {
+        factory.user_repository().find_by_id(&"alice".to_string()).await.unwrap().unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_session_tokens() {
+        let (sessions, factory) = setup().await;
+        let user = alice(&factory).await;
+
+        let issued = sessions.issue_session(&user).await.unwrap();
+        assert!(issued.secret.starts_with("ses_"));
+        assert_ne!(issued.token.secret_hash, issued.secret);
+        assert!(serde_json::to_value(&issued.token).unwrap().get("secret_hash").is_none());
+
+        let resolved = sessions.resolve(&issued.secret).await.unwrap();
+        assert_eq!(resolved.user.id, "alice");
+        assert!(resolved.allows(&Permission::DeleteResource));
+        assert!(!resolved.allows(&Permission::ManageUsers));
+        assert!(resolved.token.last_used_at.is_some());
+        assert!(sessions.resolve("ses_unknown").await.is_err());
+
+        // Revoked and expired sessions are refused
+        sessions.revoke(&issued.token.id).await.unwrap();
+        assert!(sessions.resolve(&issued.secret).await.is_err());
+        let short = sessions.with_session_ttl(Duration::ZERO);
+        let expired = short.issue_session(&user).await.unwrap();
+        assert!(short.resolve(&expired.secret).await.is_err());
+
+        // Sessions of an account deleted and created again under the same ID are refused
+        let sessions = short.with_session_ttl(Duration::from_secs(60));
+        let earlier = sessions.issue_session(&user).await.unwrap();
+        let mut recreated = User::new("alice", "alice@example.org", "Another Alice");
+        recreated.created_at = (Utc::now() + chrono::Duration::seconds(1)).to_rfc3339();
+        factory.user_repository().save(recreated).await.unwrap();
+        assert!(sessions.resolve(&earlier.secret).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_personal_token_scopes() {
+        let (sessions, factory) = setup().await;
+        let user = alice(&factory).await;
+
+        let scopes: HashSet<Permission> = [Permission::ReadResource].into_iter().collect();
+        let issued = sessions.issue_personal_token(&user, "ci", scopes.clone(), None).await.unwrap();
+        assert!(issued.secret.starts_with("pat_"));
+        assert!(issued.token.expires_at.is_none());
+        let forever = Some(Duration::from_secs(1_000_000_000 * 24 * 60 * 60));
+        let result = sessions.issue_personal_token(&user, "forever", scopes, forever).await;
+        assert!(matches!(result, Err(CoreError::Validation(_))));
+
+        let resolved = sessions.resolve(&issued.secret).await.unwrap();
+        assert_eq!(resolved.permissions, [Permission::ReadResource].into_iter().collect());
+
+        let beyond: HashSet<Permission> = [Permission::ReadResource, Permission::ManageUsers].into_iter().collect();
+        let result = sessions.issue_personal_token(&user, "admin", beyond, None).await;
+        assert!(matches!(result, Err(CoreError::PermissionDenied(msg)) if msg.contains("manage_users")));
+        assert!(sessions.issue_personal_token(&user, "none", HashSet::new(), None).await.is_err());
+
+        // Group permissions can be scoped too, and tokens stop working with their user
+        let reports: HashSet<Permission> = [Permission::ViewReports].into_iter().collect();
+        assert!(sessions.issue_personal_token(&user, "reports", reports.clone(), None).await.is_err());
+        let team = Group::team("bi", "BI").with_member("alice").with_permission(Permission::ViewReports);
+        factory.group_repository().save(team).await.unwrap();
+        let report_token = sessions.issue_personal_token(&user, "reports", reports, None).await.unwrap();
+        assert!(sessions.resolve(&report_token.secret).await.unwrap().allows(&Permission::ViewReports));
+
+        let mut disabled = user.clone();
+        disabled.enabled = false;
+        factory.user_repository().save(disabled).await.unwrap();
+        assert!(sessions.resolve(&issued.secret).await.is_err());
+
+        assert_eq!(sessions.tokens_for("alice").await.unwrap().len(), 2);
+        assert_eq!(sessions.revoke_all("alice").await.unwrap(), 2);
+        assert_eq!(sessions.revoke_all("alice").await.unwrap(), 0);
+    }
+}
@@ -0,0 +1,738 @@
+use crate::models::persistence::{PersistenceError, Repository};
+use crate::models::Resource;
+use std::collections::{HashSet, VecDeque};
+use std::sync::Arc;
+use tokio::sync::Mutex;
+
+use super::error::CoreError;
+use super::service::Service;
+
+/// Local change waiting to be replayed against the API
+#[derive(Debug, Clone)]
+pub enum PendingChange {
+    /// Resource created locally
+    Create(Resource),
+    /// Resource updated locally, with the `updated_at` it was based on
+    Update { resource: Resource, base_updated_at: String },
+    /// Resource deleted locally, with the `updated_at` it was based on
+    Delete { id: String, base_updated_at: String },
+}
+
+impl PendingChange {
+    /// Get the ID of the resource this change applies to
+    pub fn id(&self) -> &str {
+        match self {
+            PendingChange::Create(resource) => &resource.id,
+            PendingChange::Update { resource, .. } => &resource.id,
+            PendingChange::Delete { id, .. } => id,
+        }
+    }
+}
+
+/// Conflict between a queued local change and the remote state
+#[derive(Debug, Clone)]
+pub struct SyncConflict {
+    /// Resource ID
+    pub id: String,
+    /// Local version, `None` if deleted locally
+    pub local: Option<Resource>,
+    /// Remote version, `None` if deleted remotely
+    pub remote: Option<Resource>,
+    /// Change that could not be applied
+    pub change: PendingChange,
+}
+
+/// Outcome chosen for a conflict
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Resolution {
+    /// Discard the local change and adopt the remote state
+    KeepRemote,
+    /// Overwrite the remote state with the local change
+    KeepLocal,
+    /// Leave the conflict for the caller to resolve later
+    Defer,
+}
+
+/// Trait for conflict resolution strategies
+pub trait ConflictResolver: Send + Sync {
+    /// Decide how to resolve a conflict
+    fn resolve(&self, conflict: &SyncConflict) -> Resolution;
+}
+
+/// Built-in conflict resolution strategies
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConflictStrategy {
+    /// Remote state always wins
+    ServerWins,
+    /// Local changes always win
+    ClientWins,
+    /// Conflicts are kept for manual resolution
+    Manual,
+}
+
+impl ConflictResolver for ConflictStrategy {
+    fn resolve(&self, _conflict: &SyncConflict) -> Resolution {
+        match self {
+            ConflictStrategy::ServerWins => Resolution::KeepRemote,
+            ConflictStrategy::ClientWins => Resolution::KeepLocal,
+            ConflictStrategy::Manual => Resolution::Defer,
+        }
+    }
+}
+
+/// Summary of a synchronization run
+#[derive(Debug, Default)]
+pub struct SyncReport {
+    /// Number of local changes applied to the API
+    pub pushed: usize,
+    /// Number of remote resources written to the local repository
+    pub pulled: usize,
+    /// Conflicts detected during this run
+    pub conflicts: Vec<SyncConflict>,
+    /// Changes rejected by the API, with the error; they are kept until retried or discarded
+    pub failed: Vec<(PendingChange, String)>,
+    /// Whether the run stopped because the API was unreachable
+    pub offline: bool,
+}
+
+/// Synchronizes a remote service with a local repository for offline use
+///
+/// Reads and writes go to the local repository; writes are queued and
+/// replayed against the remote service on `sync`. A queued change conflicts
+/// when the remote `updated_at` moved past the version it was based on; the
+/// remote state is always read past any cache the service keeps.
+pub struct SyncEngine {
+    remote: Arc<dyn Service<Resource> + Send + Sync>,
+    local: Arc<dyn Repository<Resource, String> + Send + Sync>,
+    resolver: Box<dyn ConflictResolver>,
+    queue: Mutex<VecDeque<PendingChange>>,
+    unresolved: Mutex<Vec<SyncConflict>>,
+    failed: Mutex<Vec<PendingChange>>,
+}
+
+impl SyncEngine {
+    /// Create a new sync engine resolving conflicts with the given resolver
+    pub fn new(
+        remote: Arc<dyn Service<Resource> + Send + Sync>,
+        local: Arc<dyn Repository<Resource, String> + Send + Sync>,
+        resolver: Box<dyn ConflictResolver>,
+    ) -> Self {
+        Self {
+            remote,
+            local,
+            resolver,
+            queue: Mutex::new(VecDeque::new()),
+            unresolved: Mutex::new(Vec::new()),
+            failed: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// Get a resource from the local repository
+    pub async fn get(&self, id: &str) -> Result<Option<Resource>, CoreError> {
+        self.local.find_by_id(&id.to_string()).await.map_err(persistence_error)
+    }
+
+    /// List all resources in the local repository
+    pub async fn list(&self) -> Result<Vec<Resource>, CoreError> {
+        self.local.find_all().await.map_err(persistence_error)
+    }
+
+    /// Create a resource locally and queue it for upload
+    pub async fn create(&self, resource: Resource) -> Result<Resource, CoreError> {
+        if self.get(&resource.id).await?.is_some() {
+            return Err(CoreError::AlreadyExists(resource.id));
+        }
+
+        let saved = self.local.save(resource).await.map_err(persistence_error)?;
+        self.enqueue(PendingChange::Create(saved.clone())).await;
+        Ok(saved)
+    }
+
+    /// Update a resource locally and queue the change
+    pub async fn update(&self, mut resource: Resource) -> Result<Resource, CoreError> {
+        let existing = self
+            .get(&resource.id)
+            .await?
+            .ok_or_else(|| CoreError::NotFound(resource.id.clone()))?;
+
+        // Local edits always apply; conflicts with the remote are found on sync
+        resource.version = existing.version;
+        resource.touch();
+        let saved = self.local.save(resource).await.map_err(persistence_error)?;
+        self.enqueue(PendingChange::Update {
+            resource: saved.clone(),
+            base_updated_at: existing.updated_at,
+        })
+        .await;
+        Ok(saved)
+    }
+
+    /// Delete a resource locally and queue the deletion
+    pub async fn delete(&self, id: &str) -> Result<bool, CoreError> {
+        let existing = match self.get(id).await? {
+            Some(resource) => resource,
+            None => return Ok(false),
+        };
+
+        self.local.delete(&id.to_string()).await.map_err(persistence_error)?;
+        self.enqueue(PendingChange::Delete {
+            id: id.to_string(),
+            base_updated_at: existing.updated_at,
+        })
+        .await;
+        Ok(true)
+    }
+
+    /// Get the changes waiting to be replayed
+    pub async fn pending(&self) -> Vec<PendingChange> {
+        self.queue.lock().await.iter().cloned().collect()
+    }
+
+    /// Get the conflicts deferred for manual resolution
+    pub async fn unresolved_conflicts(&self) -> Vec<SyncConflict> {
+        self.unresolved.lock().await.clone()
+    }
+
+    /// Get the changes the API rejected, whose local state is kept until they are retried or discarded
+    pub async fn failed_changes(&self) -> Vec<PendingChange> {
+        self.failed.lock().await.clone()
+    }
+
+    /// Queue the rejected changes again, to be replayed on the next sync
+    pub async fn retry_failed(&self) {
+        let failed = std::mem::take(&mut *self.failed.lock().await);
+        self.queue.lock().await.extend(failed);
+    }
+
+    /// Drop a rejected change, letting the next sync overwrite its local state
+    pub async fn discard_failed(&self, id: &str) -> Result<(), CoreError> {
+        let mut failed = self.failed.lock().await;
+        let index = failed
+            .iter()
+            .position(|c| c.id() == id)
+            .ok_or_else(|| CoreError::NotFound(format!("No failed change for resource {}", id)))?;
+        failed.remove(index);
+        Ok(())
+    }
+
+    /// Resolve a deferred conflict
+    pub async fn resolve_conflict(&self, id: &str, resolution: Resolution) -> Result<(), CoreError> {
+        if resolution == Resolution::Defer {
+            return Ok(());
+        }
+
+        let conflict = {
+            let mut unresolved = self.unresolved.lock().await;
+            let index = unresolved
+                .iter()
+                .position(|c| c.id == id)
+                .ok_or_else(|| CoreError::NotFound(format!("No conflict for resource {}", id)))?;
+            unresolved.remove(index)
+        };
+
+        if let Err(e) = self.apply_resolution(&conflict, resolution).await {
+            self.unresolved.lock().await.push(conflict);
+            return Err(e);
+        }
+
+        Ok(())
+    }
+
+    /// Replay queued changes, then mirror the remote state locally
+    pub async fn sync(&self) -> Result<SyncReport, CoreError> {
+        let mut report = SyncReport::default();
+
+        self.push(&mut report).await?;
+        if !report.offline {
+            self.pull(&mut report).await?;
+        }
+
+        log::info!(
+            "Sync finished: pushed={}, pulled={}, conflicts={}, failed={}, offline={}",
+            report.pushed,
+            report.pulled,
+            report.conflicts.len(),
+            report.failed.len(),
+            report.offline
+        );
+
+        Ok(report)
+    }
+
+    // Add a change to the queue, folding it into an earlier queued or rejected change for the same resource
+    async fn enqueue(&self, change: PendingChange) {
+        let mut queue = self.queue.lock().await;
+        let mut failed = self.failed.lock().await;
+
+        let previous = match queue.iter().position(|c| c.id() == change.id()) {
+            Some(index) => queue.remove(index),
+            None => failed.iter().position(|c| c.id() == change.id()).map(|index| failed.remove(index)),
+        };
+
+        let merged = match (previous, change) {
+            (None, change) => Some(change),
+            (Some(PendingChange::Create(_)), PendingChange::Update { resource, .. }) => {
+                Some(PendingChange::Create(resource))
+            }
+            (Some(PendingChange::Create(_)), PendingChange::Delete { .. }) => None,
+            (
+                Some(PendingChange::Update { base_updated_at, .. }) | Some(PendingChange::Delete { base_updated_at, .. }),
+                PendingChange::Update { resource, .. },
+            ) => Some(PendingChange::Update { resource, base_updated_at }),
+            (
+                Some(PendingChange::Update { base_updated_at, .. }) | Some(PendingChange::Delete { base_updated_at, .. }),
+                PendingChange::Delete { id, .. },
+            ) => Some(PendingChange::Delete { id, base_updated_at }),
+            (Some(_), change) => Some(change),
+        };
+
+        if let Some(change) = merged {
+            queue.push_back(change);
+        }
+    }
+
+    // Replay queued changes in order until the queue is empty or the API is unreachable
+    async fn push(&self, report: &mut SyncReport) -> Result<(), CoreError> {
+        loop {
+            let change = match self.queue.lock().await.pop_front() {
+                Some(change) => change,
+                None => return Ok(()),
+            };
+
+            match self.replay(&change).await {
+                Ok(None) => report.pushed += 1,
+                Ok(Some(conflict)) => {
+                    let resolution = self.resolver.resolve(&conflict);
+                    log::warn!("Sync conflict on {}: resolved as {:?}", conflict.id, resolution);
+
+                    // Keep the conflict around if it cannot be settled now
+                    if let Err(e) = self.apply_resolution(&conflict, resolution).await {
+                        log::warn!("Could not apply resolution for {}: {}", conflict.id, e);
+                        self.unresolved.lock().await.push(conflict.clone());
+                    } else if resolution == Resolution::Defer {
+                        self.unresolved.lock().await.push(conflict.clone());
+                    }
+                    report.conflicts.push(conflict);
+                }
+                Err(CoreError::Unavailable(msg)) => {
+                    log::warn!("API unreachable, keeping {} queued: {}", change.id(), msg);
+                    self.queue.lock().await.push_front(change);
+                    report.offline = true;
+                    return Ok(());
+                }
+                Err(e) => {
+                    log::warn!("API rejected the change to {}: {}", change.id(), e);
+                    self.failed.lock().await.push(change.clone());
+                    report.failed.push((change, e.to_string()));
+                }
+            }
+        }
+    }
+
+    // Apply one change remotely, returning a conflict if the remote state moved on
+    async fn replay(&self, change: &PendingChange) -> Result<Option<SyncConflict>, CoreError> {
+        let remote = match change {
+            PendingChange::Create(_) => None,
+            PendingChange::Update { resource, .. } => self.fetch_remote(&resource.id).await?,
+            PendingChange::Delete { id, .. } => self.fetch_remote(id).await?,
+        };
+
+        let conflicted = match (change, &remote) {
+            (PendingChange::Create(_), _) => false,
+            (PendingChange::Update { .. }, None) => true,
+            (PendingChange::Delete { .. }, None) => return Ok(None),
+            (
+                PendingChange::Update { base_updated_at, .. } | PendingChange::Delete { base_updated_at, .. },
+                Some(remote),
+            ) => is_newer(&remote.updated_at, base_updated_at),
+        };
+
+        if conflicted {
+            return Ok(Some(SyncConflict {
+                id: change.id().to_string(),
+                local: self.get(change.id()).await?,
+                remote,
+                change: change.clone(),
+            }));
+        }
+
+        match change {
+            PendingChange::Create(resource) => match self.remote.create(resource.clone()).await {
+                Ok(created) => self.store(created).await?,
+                Err(CoreError::AlreadyExists(_)) => {
+                    return Ok(Some(SyncConflict {
+                        id: resource.id.clone(),
+                        local: Some(resource.clone()),
+                        remote: self.fetch_remote(&resource.id).await?,
+                        change: change.clone(),
+                    }));
+                }
+                Err(e) => return Err(e),
+            },
+            PendingChange::Update { resource, .. } => {
+                // Checked against `updated_at` above, so write over the remote version
+                let mut resource = resource.clone();
+                resource.version = remote.map_or(0, |remote| remote.version);
+                match self.remote.update(change.id(), resource).await {
+                    Ok(updated) => self.store(updated).await?,
+                    Err(CoreError::Conflict(_)) => {
+                        // The remote changed again since it was fetched
+                        return Ok(Some(SyncConflict {
+                            id: change.id().to_string(),
+                            local: self.get(change.id()).await?,
+                            remote: self.fetch_remote(change.id()).await?,
+                            change: change.clone(),
+                        }));
+                    }
+                    Err(e) => return Err(e),
+                }
+            }
+            PendingChange::Delete { id, .. } => {
+                self.remote.delete(id).await?;
+            }
+        }
+
+        Ok(None)
+    }
+
+    // Carry out the chosen resolution for a conflict
+    async fn apply_resolution(&self, conflict: &SyncConflict, resolution: Resolution) -> Result<(), CoreError> {
+        match resolution {
+            Resolution::Defer => Ok(()),
+            Resolution::KeepRemote => match &conflict.remote {
+                Some(remote) => self.store(remote.clone()).await,
+                None => self
+                    .local
+                    .delete(&conflict.id)
+                    .await
+                    .map(|_| ())
+                    .map_err(persistence_error),
+            },
+            Resolution::KeepLocal => match (&conflict.local, &conflict.remote) {
+                (Some(local), Some(remote)) => {
+                    let mut local = local.clone();
+                    local.version = remote.version;
+                    let updated = self.remote.update(&conflict.id, local).await?;
+                    self.store(updated).await
+                }
+                (Some(local), None) => {
+                    let created = self.remote.create(local.clone()).await?;
+                    self.store(created).await
+                }
+                (None, Some(_)) => self.remote.delete(&conflict.id).await.map(|_| ()),
+                (None, None) => Ok(()),
+            },
+        }
+    }
+
+    // Mirror the remote resources into the local repository
+    async fn pull(&self, report: &mut SyncReport) -> Result<(), CoreError> {
+        let remote = match self.remote.list_uncached().await {
+            Ok(resources) => resources,
+            Err(CoreError::Unavailable(msg)) => {
+                log::warn!("API unreachable, skipping pull: {}", msg);
+                report.offline = true;
+                return Ok(());
+            }
+            Err(e) => return Err(e),
+        };
+
+        // Keep local state for resources with pending work
+        let mut protected: HashSet<String> = self.queue.lock().await.iter().map(|c| c.id().to_string()).collect();
+        protected.extend(self.unresolved.lock().await.iter().map(|c| c.id.clone()));
+        protected.extend(self.failed.lock().await.iter().map(|c| c.id().to_string()));
+
+        let remote_ids: HashSet<String> = remote.iter().map(|r| r.id.clone()).collect();
+        for resource in remote {
+            if !protected.contains(&resource.id) {
+                self.store(resource).await?;
+                report.pulled += 1;
+            }
+        }
+
+        for resource in self.list().await? {
+            if !remote_ids.contains(&resource.id) && !protected.contains(&resource.id) {
+                self.local.delete(&resource.id).await.map_err(persistence_error)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    // Helper to fetch the current remote resource, mapping 404 to `None`
+    async fn fetch_remote(&self, id: &str) -> Result<Option<Resource>, CoreError> {
+        match self.remote.get_uncached(id).await {
+            Ok(resource) => Ok(Some(resource)),
+            Err(CoreError::NotFound(_)) => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    // Helper to write a resource to the local repository, which counts its own versions
+    async fn store(&self, mut resource: Resource) -> Result<(), CoreError> {
+        resource.version = self.get(&resource.id).await?.map_or(0, |local| local.version);
+        self.local.save(resource).await.map(|_| ()).map_err(persistence_error)
+    }
+}
+
+// Helper to map repository errors
+fn persistence_error(error: PersistenceError) -> CoreError {
+    CoreError::Database(error.to_string())
+}
+
+// Compare two RFC 3339 timestamps, treating unparseable values as changed
+fn is_newer(remote: &str, base: &str) -> bool {
+    match (
+        chrono::DateTime::parse_from_rfc3339(remote),
+        chrono::DateTime::parse_from_rfc3339(base),
+    ) {
+        (Ok(remote), Ok(base)) => remote > base,
+        _ => remote != base,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::service::ResourceService;
+    use crate::models::persistence::InMemoryResourceRepository;
+    use crate::models::{ResourceData, ResourceType};
+    use async_trait::async_trait;
+    use std::collections::HashMap;
+    use std::sync::atomic::{AtomicBool, Ordering};
+
+    /// Remote service stand-in that can be switched offline, or to refuse updates
+    #[derive(Default)]
+    struct FakeRemote {
+        resources: Mutex<HashMap<String, Resource>>,
+        offline: AtomicBool,
+        stale: AtomicBool,
+        invalid: AtomicBool,
+        denied: AtomicBool,
+    }
+
+    impl FakeRemote {
+        fn check(&self) -> Result<(), CoreError> {
+            match self.offline.load(Ordering::SeqCst) {
+                true => Err(CoreError::Unavailable("offline".to_string())),
+                false => Ok(()),
+            }
+        }
+    }
+
+    #[async_trait]
+    impl Service<Resource> for FakeRemote {
+        async fn create(&self, mut data: Resource) -> Result<Resource, CoreError> {
+            self.check()?;
+            data.touch();
+            self.resources.lock().await.insert(data.id.clone(), data.clone());
+            Ok(data)
+        }
+
+        async fn get(&self, id: &str) -> Result<Resource, CoreError> {
+            self.check()?;
+            self.resources.lock().await.get(id).cloned().ok_or_else(|| CoreError::NotFound(id.to_string()))
+        }
+
+        async fn update(&self, id: &str, mut data: Resource) -> Result<Resource, CoreError> {
+            self.check()?;
+            if self.stale.load(Ordering::SeqCst) {
+                return Err(CoreError::Conflict(id.to_string()));
+            }
+            if self.invalid.load(Ordering::SeqCst) {
+                return Err(CoreError::Validation(id.to_string()));
+            }
+            if self.denied.load(Ordering::SeqCst) {
+                return Err(CoreError::PermissionDenied(id.to_string()));
+            }
+            data.touch();
+            self.resources.lock().await.insert(id.to_string(), data.clone());
+            Ok(data)
+        }
+
+        async fn delete(&self, id: &str) -> Result<bool, CoreError> {
+            self.check()?;
+            Ok(self.resources.lock().await.remove(id).is_some())
+        }
+
+        async fn list(&self, _limit: Option<usize>, _filter: Option<&str>) -> Result<Vec<Resource>, CoreError> {
+            self.check()?;
+            Ok(self.resources.lock().await.values().cloned().collect())
+        }
+    }
+
+    fn project(id: &str, name: &str) -> Resource {
+        Resource::new(id, ResourceData::new(name, ResourceType::Project))
+    }
+
+    fn engine(remote: Arc<FakeRemote>, strategy: ConflictStrategy) -> SyncEngine {
+        SyncEngine::new(remote, Arc::new(InMemoryResourceRepository::new()), Box::new(strategy))
+    }
+
+    #[tokio::test]
+    async fn test_offline_changes_are_replayed() {
+        let remote = Arc::new(FakeRemote::default());
+        let engine = engine(remote.clone(), ConflictStrategy::ServerWins);
+
+        remote.offline.store(true, Ordering::SeqCst);
+        engine.create(project("prj-1", "Draft")).await.unwrap();
+        engine.update(project("prj-1", "Final")).await.unwrap();
+
+        let report = engine.sync().await.unwrap();
+        assert!(report.offline);
+        assert_eq!(engine.pending().await.len(), 1);
+
+        remote.offline.store(false, Ordering::SeqCst);
+        let report = engine.sync().await.unwrap();
+        assert!(!report.offline);
+        assert_eq!(report.pushed, 1);
+        assert!(engine.pending().await.is_empty());
+        assert_eq!(remote.get("prj-1").await.unwrap().data.name, "Final");
+    }
+
+    #[tokio::test]
+    async fn test_server_wins_conflict() {
+        let remote = Arc::new(FakeRemote::default());
+        remote.create(project("prj-1", "Original")).await.unwrap();
+        let engine = engine(remote.clone(), ConflictStrategy::ServerWins);
+        engine.sync().await.unwrap();
+
+        let local = engine.get("prj-1").await.unwrap().unwrap();
+        tokio::time::sleep(std::time::Duration::from_millis(5)).await;
+        remote.update("prj-1", project("prj-1", "Remote edit")).await.unwrap();
+        engine.update(Resource { data: ResourceData::new("Local edit", ResourceType::Project), ..local }).await.unwrap();
+
+        let report = engine.sync().await.unwrap();
+        assert_eq!(report.conflicts.len(), 1);
+        assert_eq!(engine.get("prj-1").await.unwrap().unwrap().data.name, "Remote edit");
+    }
+
+    #[tokio::test]
+    async fn test_conflicts_ignore_cached_remote_state() {
+        let mut server = mockito::Server::new_async().await;
+        let remote = |name: &str, updated_at: &str| Resource {
+            updated_at: updated_at.to_string(),
+            ..project("prj-1", name)
+        };
+        let original = remote("Original", "2024-01-01T00:00:00+00:00");
+        let listing = server
+            .mock("GET", "/resources")
+            .with_status(200)
+            .with_body(serde_json::to_string(&[&original]).unwrap())
+            .create_async()
+            .await;
+
+        // The first pull fills the service's cache with the original version
+        let config = crate::Config { api_url: server.url(), max_retries: 0, ..crate::Config::default() };
+        let service = Arc::new(ResourceService::new(config).unwrap());
+        let engine = SyncEngine::new(service.clone(), Arc::new(InMemoryResourceRepository::new()), Box::new(ConflictStrategy::ServerWins));
+        engine.sync().await.unwrap();
+        assert_eq!(service.get("prj-1").await.unwrap().data.name, "Original");
+
+        let edited = remote("Remote edit", "2024-02-01T00:00:00+00:00");
+        listing.remove_async().await;
+        let body = serde_json::to_string(&edited).unwrap();
+        server.mock("GET", "/resources/prj-1").with_status(200).with_body(body).create_async().await;
+        server
+            .mock("GET", "/resources")
+            .with_status(200)
+            .with_body(serde_json::to_string(&[&edited]).unwrap())
+            .create_async()
+            .await;
+
+        let local = engine.get("prj-1").await.unwrap().unwrap();
+        engine.update(Resource { data: ResourceData::new("Local edit", ResourceType::Project), ..local }).await.unwrap();
+        let report = engine.sync().await.unwrap();
+        assert_eq!(report.conflicts.len(), 1);
+        assert_eq!(engine.get("prj-1").await.unwrap().unwrap().data.name, "Remote edit");
+    }
+
+    #[tokio::test]
+    async fn test_version_conflict_goes_to_resolver() {
+        let remote = Arc::new(FakeRemote::default());
+        remote.create(project("prj-1", "Original")).await.unwrap();
+        let engine = engine(remote.clone(), ConflictStrategy::Manual);
+        engine.sync().await.unwrap();
+
+        remote.stale.store(true, Ordering::SeqCst);
+        engine.update(project("prj-1", "Local edit")).await.unwrap();
+        let report = engine.sync().await.unwrap();
+        assert_eq!(report.conflicts.len(), 1);
+        assert!(report.failed.is_empty());
+        assert_eq!(engine.unresolved_conflicts().await.len(), 1);
+        assert_eq!(engine.get("prj-1").await.unwrap().unwrap().data.name, "Local edit");
+    }
+
+    #[tokio::test]
+    async fn test_rejected_changes_are_kept() {
+        let remote = Arc::new(FakeRemote::default());
+        remote.create(project("prj-1", "Original")).await.unwrap();
+        let engine = engine(remote.clone(), ConflictStrategy::ServerWins);
+        engine.sync().await.unwrap();
+
+        // The pull after a rejection leaves the local edit alone
+        remote.invalid.store(true, Ordering::SeqCst);
+        engine.update(project("prj-1", "Local edit")).await.unwrap();
+        let report = engine.sync().await.unwrap();
+        assert_eq!(report.failed.len(), 1);
+        assert_eq!(engine.failed_changes().await.len(), 1);
+        assert_eq!(engine.get("prj-1").await.unwrap().unwrap().data.name, "Local edit");
+
+        remote.invalid.store(false, Ordering::SeqCst);
+        engine.retry_failed().await;
+        let report = engine.sync().await.unwrap();
+        assert_eq!(report.pushed, 1);
+        assert!(engine.failed_changes().await.is_empty());
+        assert_eq!(remote.get("prj-1").await.unwrap().data.name, "Local edit");
+
+        // Discarded changes give way to the remote state
+        remote.invalid.store(true, Ordering::SeqCst);
+        engine.update(project("prj-1", "Another edit")).await.unwrap();
+        engine.sync().await.unwrap();
+        engine.discard_failed("prj-1").await.unwrap();
+        engine.sync().await.unwrap();
+        assert_eq!(engine.get("prj-1").await.unwrap().unwrap().data.name, "Local edit");
+    }
+
+    #[tokio::test]
+    async fn test_denied_change_does_not_block_the_queue() {
+        let remote = Arc::new(FakeRemote::default());
+        remote.create(project("prj-1", "Original")).await.unwrap();
+        let engine = engine(remote.clone(), ConflictStrategy::ServerWins);
+        engine.sync().await.unwrap();
+
+        // Only an unreachable API stops the run; a refused change is set aside
+        remote.denied.store(true, Ordering::SeqCst);
+        engine.update(project("prj-1", "Local edit")).await.unwrap();
+        engine.create(project("prj-2", "New")).await.unwrap();
+        let report = engine.sync().await.unwrap();
+        assert!(!report.offline);
+        assert_eq!(report.pushed, 1);
+        assert_eq!(report.failed.len(), 1);
+        assert_eq!(engine.failed_changes().await[0].id(), "prj-1");
+        assert!(remote.get("prj-2").await.is_ok());
+
+        let report = engine.sync().await.unwrap();
+        assert_eq!((report.pushed, report.failed.len()), (0, 0));
+    }
+
+    #[tokio::test]
+    async fn test_manual_conflict_is_deferred() {
+        let remote = Arc::new(FakeRemote::default());
+        remote.create(project("prj-1", "Original")).await.unwrap();
+        let engine = engine(remote.clone(), ConflictStrategy::Manual);
+        engine.sync().await.unwrap();
+
+        tokio::time::sleep(std::time::Duration::from_millis(5)).await;
+        remote.update("prj-1", project("prj-1", "Remote edit")).await.unwrap();
+        engine.delete("prj-1").await.unwrap();
+
+        engine.sync().await.unwrap();
+        assert_eq!(engine.unresolved_conflicts().await.len(), 1);
+        assert!(engine.get("prj-1").await.unwrap().is_none());
+
+        engine.resolve_conflict("prj-1", Resolution::KeepLocal).await.unwrap();
+        assert!(engine.unresolved_conflicts().await.is_empty());
+        assert!(remote.get("prj-1").await.is_err());
+    }
+}
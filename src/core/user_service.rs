@@ -0,0 +1,313 @@
+use crate::api::{ApiClient, ApiError, ApiRequest};
+use crate::models::User;
+use crate::utils::validation::{validate_all, validate_email, validate_not_empty, validate_username};
+use crate::{CacheConfig, Config};
+use async_trait::async_trait;
+use std::sync::Arc;
+use tokio::sync::Mutex;
+
+use super::cache::{open_backend, CacheBackend, CacheLookup, SingleFlight};
+use super::error::{external_error, CoreError};
+use super::query::ListQuery;
+use super::service::Service;
+
+/// Cache namespace holding users
+const USERS_CACHE_NAMESPACE: &str = "users";
+
+/// Cache namespace holding user listings
+const USER_LISTINGS_CACHE_NAMESPACE: &str = "user-listings";
+
+/// Implementation of the Service trait for users over the `users` API endpoints
+///
+/// Uses the same cache policy as `ResourceService`, and can share its API
+/// client so both go through the same retry and authentication settings.
+#[derive(Clone)]
+pub struct UserService {
+    client: Arc<ApiClient>,
+    cache: Arc<Mutex<UserCache>>,
+    user_flights: Arc<SingleFlight<String, Result<User, CoreError>>>,
+}
+
+/// Cache for users keyed by ID, and for exact user listings keyed by query
+struct UserCache {
+    entries: Box<dyn CacheBackend<String, User>>,
+    listings: Box<dyn CacheBackend<ListQuery, Vec<User>>>,
+    config: CacheConfig,
+    /// Bumped on every invalidation so fetches started earlier don't store outdated data
+    generation: u64,
+}
+
+impl UserCache {
+    /// Create a cache with the given policy
+    fn new(config: CacheConfig) -> Self {
+        Self {
+            entries: open_backend(&config, USERS_CACHE_NAMESPACE),
+            listings: open_backend(&config, USER_LISTINGS_CACHE_NAMESPACE),
+            config,
+            generation: 0,
+        }
+    }
+
+    /// Store a user fetched from or written to the API
+    fn put(&mut self, user: User) {
+        self.entries.insert(user.id.clone(), user, self.config.ttl);
+    }
+
+    /// Drop a single user and any listing that may contain it
+    fn invalidate(&mut self, id: &str) {
+        self.entries.remove(&id.to_string());
+        self.listings.clear();
+        self.generation += 1;
+    }
+
+    /// Remove everything
+    fn clear(&mut self) {
+        self.entries.clear();
+        self.listings.clear();
+        self.generation += 1;
+    }
+}
+
+impl UserService {
+    /// Create a new UserService with the given configuration
+    pub fn new(config: Config) -> Result<Self, CoreError> {
+        let client = ApiClient::new(config)
+            .map_err(|e| CoreError::ExternalService(format!("Failed to create API client: {}", e)))?;
+
+        Ok(Self::with_client(Arc::new(client)))
+    }
+
+    /// Create a new UserService with an existing API client
+    pub fn with_client(client: Arc<ApiClient>) -> Self {
+        let cache = UserCache::new(client.config().scoped_cache());
+
+        Self {
+            client,
+            cache: Arc::new(Mutex::new(cache)),
+            user_flights: Arc::new(SingleFlight::new()),
+        }
+    }
+
+    /// Get the API client
+    pub fn client(&self) -> Arc<ApiClient> {
+        self.client.clone()
+    }
+
+    /// Invalidate the cache, forcing a refresh on next fetch
+    pub async fn invalidate_cache(&self) {
+        self.cache.lock().await.clear();
+    }
+
+    /// Validate a user before sending it to the API
+    fn validate(&self, user: &User) -> Result<(), CoreError> {
+        validate_all(vec![
+            validate_username(&user.id, "id"),
+            validate_email(&user.email, "email"),
+            validate_not_empty(&user.name, "name"),
+        ])
+        .map_err(CoreError::from)
+    }
+
+    // Helper to fetch a user, joining a fetch of the same ID already in flight
+    async fn fetch_user(&self, id: &str) -> Result<User, CoreError> {
+        let client = self.client.clone();
+        let cache = self.cache.clone();
+        let key = id.to_string();
+
+        self.user_flights.run(id.to_string(), move || async move {
+            let generation = cache.lock().await.generation;
+            let result = client.get::<User>(&format!("users/{}", key)).await;
+
+            // Only record the outcome if nothing was written since the fetch began
+            let mut cache = cache.lock().await;
+            let current = cache.generation == generation;
+            match result {
+                Ok(user) => {
+                    if current {
+                        cache.put(user.clone());
+                    }
+                    Ok(user)
+                }
+                Err(ApiError::ResourceNotFound) => {
+                    if current {
+                        let ttl = cache.config.negative_ttl;
+                        cache.entries.insert_missing(key.clone(), ttl);
+                    }
+                    Err(CoreError::NotFound(format!("User not found: {}", key)))
+                }
+                Err(e) => Err(map_error(e, "access this user")),
+            }
+        }).await
+    }
+}
+
+#[async_trait]
+impl Service<User> for UserService {
+    async fn create(&self, user: User) -> Result<User, CoreError> {
+        self.validate(&user)?;
+
+        let result = self.client.post::<User, User>("users", &user)
+            .await
+            .map_err(|e| match e {
+                ApiError::Conflict(_) => CoreError::AlreadyExists(format!("User already exists: {}", user.id)),
+                e => map_error(e, "create users"),
+            })?;
+
+        // Write through to the cache
+        let mut cache = self.cache.lock().await;
+        cache.invalidate(&result.id);
+        cache.put(result.clone());
+
+        Ok(result)
+    }
+
+    async fn get(&self, id: &str) -> Result<User, CoreError> {
+        // Check the cache first
+        let cached = self.cache.lock().await.entries.get(&id.to_string());
+        match cached {
+            CacheLookup::Hit(user) => return Ok(user),
+            CacheLookup::Stale(user) => {
+                // Serve the expired copy and refresh it in the background
+                let service = self.clone();
+                let id = id.to_string();
+                tokio::spawn(async move { service.fetch_user(&id).await });
+                return Ok(user);
+            }
+            CacheLookup::NotFound => return Err(CoreError::NotFound(format!("User not found: {}", id))),
+            CacheLookup::Miss => {}
+        }
+
+        self.fetch_user(id).await
+    }
+
+    async fn update(&self, id: &str, user: User) -> Result<User, CoreError> {
+        self.validate(&user)?;
+
+        if user.id != id {
+            return Err(CoreError::Validation("User ID mismatch".to_string()));
+        }
+
+        let request = ApiRequest::put(&format!("users/{}", id)).with_body(&user);
+        let result = self.client.execute::<User, &User>(request)
+            .await
+            .map(|response| response.into_body())
+            .map_err(|e| match e {
+                ApiError::ResourceNotFound => CoreError::NotFound(format!("User not found: {}", id)),
+                e => map_error(e, "update this user"),
+            })?;
+
+        // Write through to the cache
+        let mut cache = self.cache.lock().await;
+        cache.invalidate(id);
+        cache.put(result.clone());
+
+        Ok(result)
+    }
+
+    async fn delete(&self, id: &str) -> Result<bool, CoreError> {
+        let result = self.client.execute::<bool, ()>(ApiRequest::delete(&format!("users/{}", id)))
+            .await
+            .map(|response| response.into_body())
+            .map_err(|e| match e {
+                ApiError::ResourceNotFound => CoreError::NotFound(format!("User not found: {}", id)),
+                e => map_error(e, "delete this user"),
+            })?;
+
+        self.cache.lock().await.invalidate(id);
+
+        Ok(result)
+    }
+
+    async fn list(&self, limit: Option<usize>, filter: Option<&str>) -> Result<Vec<User>, CoreError> {
+        let query = ListQuery {
+            filter: filter.map(|f| f.to_string()),
+            limit,
+            ..ListQuery::default()
+        };
+
+        if let CacheLookup::Hit(users) = self.cache.lock().await.listings.get(&query) {
+            return Ok(users);
+        }
+
+        let generation = self.cache.lock().await.generation;
+        let request = ApiRequest::<()>::get("users").with_query_params(query.to_query_params());
+        let result = self.client.execute::<Vec<User>, ()>(request)
+            .await
+            .map(|response| response.into_body())
+            .map_err(|e| map_error(e, "list users"))?;
+
+        let mut cache = self.cache.lock().await;
+        if cache.generation == generation {
+            for user in &result {
+                cache.put(user.clone());
+            }
+            let ttl = cache.config.ttl;
+            cache.listings.insert(query, result.clone(), ttl);
+        }
+
+        Ok(result)
+    }
+}
+
+// Helper to map API errors not specific to an operation
+fn map_error(error: ApiError, action: &str) -> CoreError {
+    match error {
+        ApiError::Unauthorized | ApiError::Forbidden => {
+            CoreError::PermissionDenied(format!("Not authorized to {}", action))
+        }
+        ApiError::ResponseParseError(msg) => CoreError::Processing(msg),
+        e => external_error(e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_service(api_url: String) -> UserService {
+        UserService::new(Config {
+            api_url,
+            max_retries: 0,
+            ..Config::default()
+        })
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_create_validates_before_sending() {
+        let mut server = mockito::Server::new_async().await;
+        let m = server.mock("POST", "/users").expect(0).create_async().await;
+
+        let service = test_service(server.url());
+        let result = service.create(User::new("a", "not-an-email", "Alice")).await;
+
+        match result {
+            Err(CoreError::Validation(msg)) => {
+                assert!(msg.contains("id"));
+                assert!(msg.contains("email"));
+            }
+            other => panic!("expected a validation error, got {:?}", other),
+        }
+        m.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn test_get_populates_cache() {
+        let mut server = mockito::Server::new_async().await;
+        let body = serde_json::to_string(&User::new("alice", "alice@example.com", "Alice")).unwrap();
+        let found = server.mock("GET", "/users/alice").with_status(200).with_body(body).expect(1).create_async().await;
+        let missing = server.mock("GET", "/users/bob").with_status(404).expect(1).create_async().await;
+        let forbidden = server.mock("GET", "/users/carol").with_status(403).create_async().await;
+
+        let service = test_service(server.url());
+        for _ in 0..2 {
+            assert_eq!(service.get("alice").await.unwrap().name, "Alice");
+            assert!(matches!(service.get("bob").await, Err(CoreError::NotFound(_))));
+        }
+        assert!(matches!(service.get("carol").await, Err(CoreError::PermissionDenied(_))));
+
+        found.assert_async().await;
+        missing.assert_async().await;
+        forbidden.assert_async().await;
+    }
+}
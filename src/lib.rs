@@ -6,6 +6,7 @@
 pub mod api;
 pub mod core;
 pub mod models;
+pub mod server;
 pub mod utils;
 
 /// Current library version
@@ -20,11 +21,13 @@ pub fn initialize() {
 }
 
 /// Application-wide configuration
+#[derive(Clone)]
 pub struct Config {
     pub api_url: String,
     pub api_key: Option<String>,
     pub timeout: std::time::Duration,
     pub max_retries: u32,
+    pub cache: CacheConfig,
 }
 
 impl Default for Config {
@@ -34,6 +37,62 @@ impl Default for Config {
             api_key: None,
             timeout: std::time::Duration::from_secs(30),
             max_retries: 3,
+            cache: CacheConfig::default(),
+        }
+    }
+}
+
+impl Config {
+    /// Get the cache policy for clients of this configuration
+    ///
+    /// A persistent cache directory is narrowed to a subdirectory named after
+    /// a hash of the API URL and key, so entries fetched from one backend or
+    /// on behalf of one user are never served to another.
+    pub fn scoped_cache(&self) -> CacheConfig {
+        use sha2::{Digest, Sha256};
+
+        let mut hasher = Sha256::new();
+        hasher.update(self.api_url.as_bytes());
+        hasher.update([0]);
+        hasher.update(self.api_key.as_deref().unwrap_or_default().as_bytes());
+        let scope = hex::encode(&hasher.finalize()[..8]);
+
+        CacheConfig {
+            dir: self.cache.dir.as_ref().map(|dir| dir.join(scope)),
+            ..self.cache.clone()
+        }
+    }
+}
+
+/// Client-side cache policy
+#[derive(Debug, Clone)]
+pub struct CacheConfig {
+    /// How long fetched entries stay fresh
+    pub ttl: std::time::Duration,
+    /// How long a "not found" answer is remembered
+    pub negative_ttl: std::time::Duration,
+    /// How long an expired entry may still be served while it is refreshed
+    pub stale_while_revalidate: std::time::Duration,
+    /// Maximum number of cached entries (0 disables caching)
+    pub capacity: usize,
+    /// Directory of the persistent cache (None keeps the cache in memory)
+    ///
+    /// Clients keep their entries in a subdirectory per API URL and key; see
+    /// `Config::scoped_cache`.
+    pub dir: Option<std::path::PathBuf>,
+    /// Maximum size in bytes of each persistent cache namespace
+    pub max_disk_bytes: u64,
+}
+
+impl Default for CacheConfig {
+    fn default() -> Self {
+        Self {
+            ttl: std::time::Duration::from_secs(300),
+            negative_ttl: std::time::Duration::from_secs(30),
+            stale_while_revalidate: std::time::Duration::from_secs(60),
+            capacity: 1000,
+            dir: None,
+            max_disk_bytes: 25 * 1024 * 1024,
         }
     }
 }
@@ -64,6 +123,9 @@ mod tests {
         assert_eq!(config.api_key, None);
         assert_eq!(config.timeout, std::time::Duration::from_secs(30));
         assert_eq!(config.max_retries, 3);
+        assert_eq!(config.cache.ttl, std::time::Duration::from_secs(300));
+        assert_eq!(config.cache.stale_while_revalidate, std::time::Duration::from_secs(60));
+        assert_eq!(config.cache.capacity, 1000);
     }
 
     #[test]
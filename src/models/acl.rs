@@ -0,0 +1,177 @@
+use chrono::{DateTime, Utc};
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+use std::collections::HashSet;
+
+use super::user::Permission;
+
+/// Subject of an ACL entry
+///
+/// Principals serialize as `user:<id>` or `group:<id>`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Principal {
+    /// A single user, by ID
+    User(String),
+    /// Every member of a group, by group ID
+    Group(String),
+}
+
+impl Principal {
+    /// Check if the principal covers a user belonging to the given groups
+    pub fn includes(&self, user_id: &str, group_ids: &[&str]) -> bool {
+        match self {
+            Principal::User(id) => id == user_id,
+            Principal::Group(id) => group_ids.contains(&id.as_str()),
+        }
+    }
+}
+
+impl std::fmt::Display for Principal {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Principal::User(id) => write!(f, "user:{}", id),
+            Principal::Group(id) => write!(f, "group:{}", id),
+        }
+    }
+}
+
+impl std::str::FromStr for Principal {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.split_once(':') {
+            Some(("user", id)) if !id.is_empty() => Ok(Principal::User(id.to_string())),
+            Some(("group", id)) if !id.is_empty() => Ok(Principal::Group(id.to_string())),
+            _ => Err(format!("Invalid principal (expected user:<id> or group:<id>): {}", s)),
+        }
+    }
+}
+
+impl Serialize for Principal {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for Principal {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer)?.parse().map_err(de::Error::custom)
+    }
+}
+
+/// Access control entry granting permissions on a single resource
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AclEntry {
+    /// User or group the entry applies to
+    pub principal: Principal,
+    /// Permissions granted on the resource
+    pub permissions: HashSet<Permission>,
+    /// When the entry stops applying (ISO 8601), if ever
+    #[serde(default)]
+    pub expires_at: Option<String>,
+}
+
+impl AclEntry {
+    /// Create an entry granting permissions to a principal
+    pub fn new(principal: Principal, permissions: impl IntoIterator<Item = Permission>) -> Self {
+        Self {
+            principal,
+            permissions: permissions.into_iter().collect(),
+            expires_at: None,
+        }
+    }
+
+    /// Make the entry expire at the given time
+    pub fn with_expiry(mut self, expires_at: DateTime<Utc>) -> Self {
+        self.expires_at = Some(expires_at.to_rfc3339());
+        self
+    }
+
+    /// Check that the entry grants something and has a readable expiry
+    pub fn validate(&self) -> Result<(), String> {
+        if self.permissions.is_empty() {
+            return Err(format!("ACL entry for {} grants no permissions", self.principal));
+        }
+        if let Some(expires_at) = &self.expires_at {
+            DateTime::parse_from_rfc3339(expires_at)
+                .map_err(|e| format!("Invalid ACL expiry {}: {}", expires_at, e))?;
+        }
+        Ok(())
+    }
+
+    /// Check if the entry has expired at the given time
+    ///
+    /// Entries with an unreadable expiry count as expired.
+    pub fn is_expired_at(&self, now: DateTime<Utc>) -> bool {
+        self.expires_at.as_deref().is_some_and(|expires_at| {
+            DateTime::parse_from_rfc3339(expires_at).map_or(true, |expires_at| expires_at <= now)
+        })
+    }
+
+    /// Check if the entry currently grants a permission to a user belonging to the given groups
+    pub fn permits(&self, user_id: &str, group_ids: &[&str], permission: &Permission) -> bool {
+        self.permissions.contains(permission)
+            && self.principal.includes(user_id, group_ids)
+            && !self.is_expired_at(Utc::now())
+    }
+}
+
+/// Change made to an access control list
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AclAction {
+    /// An entry was added or replaced
+    Grant,
+    /// An entry was removed
+    Revoke,
+}
+
+impl std::fmt::Display for AclAction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AclAction::Grant => write!(f, "grant"),
+            AclAction::Revoke => write!(f, "revoke"),
+        }
+    }
+}
+
+/// Audit record of a change to a resource's access control list
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AclEvent {
+    /// What was done
+    pub action: AclAction,
+    /// Entry granted, or the entry that was revoked
+    pub entry: AclEntry,
+    /// ID of the user who made the change
+    pub actor: String,
+    /// When the change was made (ISO 8601)
+    pub at: String,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+
+    #[test]
+    fn test_entry_applies_until_expiry() {
+        assert_eq!("group:ops".parse(), Ok(Principal::Group("ops".to_string())));
+        assert!("team:ops".parse::<Principal>().is_err());
+        assert_eq!(
+            serde_json::to_value(Principal::User("alice".to_string())).unwrap(),
+            serde_json::json!("user:alice")
+        );
+
+        let entry = AclEntry::new(Principal::Group("ops".to_string()), [Permission::ReadResource]);
+        assert!(entry.permits("bob", &["ops"], &Permission::ReadResource));
+        assert!(!entry.permits("bob", &["ops"], &Permission::UpdateResource));
+        assert!(!entry.permits("bob", &["dev"], &Permission::ReadResource));
+
+        let expired = entry.clone().with_expiry(Utc::now() - Duration::minutes(1));
+        assert!(!expired.permits("bob", &["ops"], &Permission::ReadResource));
+        assert!(!entry.clone().with_expiry(Utc::now() + Duration::minutes(1)).is_expired_at(Utc::now()));
+
+        let garbled = AclEntry { expires_at: Some("soon".to_string()), ..entry };
+        assert!(garbled.validate().is_err());
+        assert!(garbled.is_expired_at(Utc::now()));
+    }
+}
@@ -0,0 +1,108 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+
+use super::user::Permission;
+
+/// Kind of group
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum GroupKind {
+    /// General group of users
+    #[default]
+    Group,
+    /// Team working on shared resources
+    Team,
+}
+
+impl std::fmt::Display for GroupKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            GroupKind::Group => write!(f, "group"),
+            GroupKind::Team => write!(f, "team"),
+        }
+    }
+}
+
+/// Group of users sharing permissions and resources
+///
+/// Members inherit the group's permissions, and may act on resources owned
+/// by the group as if they owned them.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Group {
+    /// Unique group ID
+    pub id: String,
+    /// Display name
+    pub name: String,
+    /// Kind of group
+    #[serde(default)]
+    pub kind: GroupKind,
+    /// Group description
+    pub description: Option<String>,
+    /// IDs of the member users
+    #[serde(default)]
+    pub members: HashSet<String>,
+    /// Permissions granted to every member
+    #[serde(default)]
+    pub permissions: HashSet<Permission>,
+    /// Creation timestamp (ISO 8601)
+    pub created_at: String,
+}
+
+impl Group {
+    /// Create a new group with no members
+    pub fn new(id: &str, name: &str) -> Self {
+        Self {
+            id: id.to_string(),
+            name: name.to_string(),
+            kind: GroupKind::Group,
+            description: None,
+            members: HashSet::new(),
+            permissions: HashSet::new(),
+            created_at: chrono::Utc::now().to_rfc3339(),
+        }
+    }
+
+    /// Create a new team with no members
+    pub fn team(id: &str, name: &str) -> Self {
+        Self::new(id, name).with_kind(GroupKind::Team)
+    }
+
+    /// Set the kind of group
+    pub fn with_kind(mut self, kind: GroupKind) -> Self {
+        self.kind = kind;
+        self
+    }
+
+    /// Set the description
+    pub fn with_description(mut self, description: &str) -> Self {
+        self.description = Some(description.to_string());
+        self
+    }
+
+    /// Add a member
+    pub fn with_member(mut self, user_id: &str) -> Self {
+        self.members.insert(user_id.to_string());
+        self
+    }
+
+    /// Grant a permission to every member
+    pub fn with_permission(mut self, permission: Permission) -> Self {
+        self.permissions.insert(permission);
+        self
+    }
+
+    /// Add a member, returning false if they already belong to the group
+    pub fn add_member(&mut self, user_id: &str) -> bool {
+        self.members.insert(user_id.to_string())
+    }
+
+    /// Remove a member, returning false if they did not belong to the group
+    pub fn remove_member(&mut self, user_id: &str) -> bool {
+        self.members.remove(user_id)
+    }
+
+    /// Check if a user belongs to the group
+    pub fn has_member(&self, user_id: &str) -> bool {
+        self.members.contains(user_id)
+    }
+}
@@ -0,0 +1,120 @@
+use serde::{Deserialize, Serialize};
+
+/// Kind of link from one resource to another
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LinkKind {
+    /// The target contains the linking resource, e.g. a project holding a document
+    Parent,
+    /// The linking resource mentions the target
+    Reference,
+    /// The target is a media resource attached to the linking resource
+    Attachment,
+}
+
+impl std::fmt::Display for LinkKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LinkKind::Parent => write!(f, "parent"),
+            LinkKind::Reference => write!(f, "reference"),
+            LinkKind::Attachment => write!(f, "attachment"),
+        }
+    }
+}
+
+impl std::str::FromStr for LinkKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "parent" => Ok(LinkKind::Parent),
+            "reference" => Ok(LinkKind::Reference),
+            "attachment" => Ok(LinkKind::Attachment),
+            _ => Err(format!("Invalid link kind: {}", s)),
+        }
+    }
+}
+
+/// What deleting the target of a link does to the linking resource
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OnDelete {
+    /// The target can't be deleted while the link exists
+    #[default]
+    Restrict,
+    /// The linking resource is deleted along with the target
+    Cascade,
+}
+
+/// Typed link from a resource to another, by ID
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct Link {
+    /// Kind of link
+    pub kind: LinkKind,
+    /// ID of the linked resource
+    pub target: String,
+    /// What deleting the target does to the linking resource
+    #[serde(default)]
+    pub on_delete: OnDelete,
+}
+
+impl Link {
+    /// Create a link that keeps its target from being deleted
+    pub fn new(kind: LinkKind, target: &str) -> Self {
+        Self {
+            kind,
+            target: target.to_string(),
+            on_delete: OnDelete::Restrict,
+        }
+    }
+
+    /// Link to the resource containing this one
+    pub fn parent(target: &str) -> Self {
+        Self::new(LinkKind::Parent, target)
+    }
+
+    /// Link to a resource this one mentions
+    pub fn reference(target: &str) -> Self {
+        Self::new(LinkKind::Reference, target)
+    }
+
+    /// Link to a media resource attached to this one
+    pub fn attachment(target: &str) -> Self {
+        Self::new(LinkKind::Attachment, target)
+    }
+
+    /// Delete the linking resource along with the target, instead of restricting deletion
+    pub fn cascading(mut self) -> Self {
+        self.on_delete = OnDelete::Cascade;
+        self
+    }
+}
+
+impl std::fmt::Display for Link {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:{}", self.kind, self.target)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{Resource, ResourceData, ResourceType};
+
+    #[test]
+    fn test_links_serialize_and_validate() {
+        let link: Link = serde_json::from_str(r#"{"kind": "parent", "target": "prj-1"}"#).unwrap();
+        assert_eq!(link, Link::parent("prj-1"));
+        assert_eq!(serde_json::to_value(Link::attachment("med-1").cascading()).unwrap()["on_delete"], "cascade");
+
+        let resource = Resource::new("doc-1", ResourceData::new("Notes", ResourceType::Document))
+            .with_link(Link::parent("prj-1"))
+            .with_link(Link::reference("doc-2"));
+        assert_eq!(resource.parent(), Some("prj-1"));
+        assert_eq!(resource.links_to("doc-2").count(), 1);
+        assert!(resource.validate_links().is_ok());
+
+        assert!(resource.clone().with_link(Link::parent("prj-2")).validate_links().is_err());
+        assert!(resource.with_link(Link::reference("doc-1")).validate_links().is_err());
+    }
+}
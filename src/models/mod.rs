@@ -2,12 +2,24 @@
 //!
 //! Contains data models and persistence functionality.
 
+pub mod acl;
+pub mod group;
+pub mod link;
 pub mod resource;
+pub mod revision;
+pub mod schema;
 pub mod user;
 pub mod persistence;
+pub mod token;
 
+pub use acl::{AclAction, AclEntry, AclEvent, Principal};
+pub use group::{Group, GroupKind};
+pub use link::{Link, LinkKind, OnDelete};
 pub use resource::{Resource, ResourceData, ResourceType};
-pub use user::{User, UserRole, Permission};
+pub use revision::{FieldChange, Revision};
+pub use schema::{FieldSchema, FieldType, Schema, Schemas};
+pub use token::{Token, TokenKind};
+pub use user::{AccountStatus, User, UserRole, Permission};
 
 /// Database connection configuration
 #[derive(Debug, Clone)]
@@ -1,4 +1,4 @@
-use crate::models::{Resource, User};
+use crate::models::{Group, Resource, Revision, Token, User};
 use async_trait::async_trait;
 use std::collections::HashMap;
 use std::sync::Arc;
@@ -23,6 +23,52 @@ pub trait Repository<T, ID> {
     async fn count(&self) -> Result<usize, PersistenceError>;
 }
 
+/// Repository for resources, recording a revision of their data on save
+///
+/// Saves that leave the data unchanged, such as ACL updates, don't add a
+/// revision. Deleting a resource deletes its revisions too, so a resource
+/// created later under the same ID starts a fresh history.
+///
+/// Saving a resource that is already stored fails with a conflict unless it
+/// carries the stored version; each save increments the version. Links
+/// between resources are stored as given, callers enforce their integrity.
+#[async_trait]
+pub trait ResourceRepository: Repository<Resource, String> {
+    /// List the revisions of a resource, oldest first
+    async fn revisions(&self, id: &str) -> Result<Vec<Revision>, PersistenceError>;
+    
+    /// Find a single revision of a resource
+    async fn revision(&self, id: &str, number: u64) -> Result<Option<Revision>, PersistenceError>;
+    
+    /// Credit another user with every revision a user made, returning how many changed
+    ///
+    /// Revisions are otherwise immutable; this exists so accounts can be erased.
+    async fn reassign_revisions(&self, user_id: &str, replacement: &str) -> Result<usize, PersistenceError>;
+    
+    /// Find the resources whose parent is the given resource, by ID
+    async fn find_children(&self, id: &str) -> Result<Vec<Resource>, PersistenceError>;
+    
+    /// Find the resources with any link to the given resource, by ID
+    async fn find_backlinks(&self, id: &str) -> Result<Vec<Resource>, PersistenceError>;
+}
+
+/// Repository for groups, with membership lookups
+#[async_trait]
+pub trait GroupRepository: Repository<Group, String> {
+    /// Find the groups a user belongs to
+    async fn find_by_member(&self, user_id: &str) -> Result<Vec<Group>, PersistenceError>;
+}
+
+/// Repository for access tokens, looked up by the hash of their secret
+#[async_trait]
+pub trait TokenRepository: Repository<Token, String> {
+    /// Find the token whose secret has the given hash
+    async fn find_by_secret_hash(&self, secret_hash: &str) -> Result<Option<Token>, PersistenceError>;
+    
+    /// Find the tokens issued to a user
+    async fn find_by_user(&self, user_id: &str) -> Result<Vec<Token>, PersistenceError>;
+}
+
 /// Persistence errors
 #[derive(Debug, thiserror::Error)]
 pub enum PersistenceError {
@@ -46,6 +92,10 @@ pub enum PersistenceError {
     #[error("Unique constraint violation: {0}")]
     UniqueConstraintViolation(String),
     
+    /// Entity was changed since the version being saved was read
+    #[error("Version conflict: {0}")]
+    Conflict(String),
+    
     /// Transaction error
     #[error("Transaction error: {0}")]
     TransactionError(String),
@@ -54,6 +104,7 @@ pub enum PersistenceError {
 /// In-memory repository implementation for Resource
 pub struct InMemoryResourceRepository {
     resources: Arc<RwLock<HashMap<String, Resource>>>,
+    revisions: Arc<RwLock<HashMap<String, Vec<Revision>>>>,
 }
 
 impl InMemoryResourceRepository {
@@ -61,14 +112,32 @@ impl InMemoryResourceRepository {
     pub fn new() -> Self {
         Self {
             resources: Arc::new(RwLock::new(HashMap::new())),
+            revisions: Arc::new(RwLock::new(HashMap::new())),
         }
     }
 }
 
 #[async_trait]
 impl Repository<Resource, String> for InMemoryResourceRepository {
-    async fn save(&self, resource: Resource) -> Result<Resource, PersistenceError> {
+    async fn save(&self, mut resource: Resource) -> Result<Resource, PersistenceError> {
         let mut resources = self.resources.write().await;
+        let mut revisions = self.revisions.write().await;
+        
+        // Refuse to overwrite changes the caller hasn't seen
+        let stored = resources.get(&resource.id).map(|r| r.version);
+        if let Some(stored) = stored.filter(|&stored| stored != resource.version) {
+            return Err(PersistenceError::Conflict(format!(
+                "{} is at version {}, not {}",
+                resource.id, stored, resource.version
+            )));
+        }
+        resource.version = stored.unwrap_or(0) + 1;
+        
+        // Record a revision if the data changed since the latest one
+        let history = revisions.entry(resource.id.clone()).or_default();
+        if history.last().is_none_or(|latest| latest.data != resource.data) {
+            history.push(Revision::of(&resource, history.len() as u64 + 1));
+        }
         
         // Clone the resource before inserting it
         let resource_clone = resource.clone();
@@ -84,6 +153,8 @@ impl Repository<Resource, String> for InMemoryResourceRepository {
     
     async fn delete(&self, id: &String) -> Result<bool, PersistenceError> {
         let mut resources = self.resources.write().await;
+        let mut revisions = self.revisions.write().await;
+        revisions.remove(id);
         Ok(resources.remove(id).is_some())
     }
     
@@ -98,6 +169,45 @@ impl Repository<Resource, String> for InMemoryResourceRepository {
     }
 }
 
+#[async_trait]
+impl ResourceRepository for InMemoryResourceRepository {
+    async fn revisions(&self, id: &str) -> Result<Vec<Revision>, PersistenceError> {
+        let revisions = self.revisions.read().await;
+        Ok(revisions.get(id).cloned().unwrap_or_default())
+    }
+    
+    async fn revision(&self, id: &str, number: u64) -> Result<Option<Revision>, PersistenceError> {
+        let revisions = self.revisions.read().await;
+        Ok(revisions.get(id).and_then(|history| history.iter().find(|r| r.number == number)).cloned())
+    }
+    
+    async fn reassign_revisions(&self, user_id: &str, replacement: &str) -> Result<usize, PersistenceError> {
+        let mut revisions = self.revisions.write().await;
+        let mut changed = 0;
+        for revision in revisions.values_mut().flatten() {
+            if revision.changed_by.as_deref() == Some(user_id) {
+                revision.changed_by = Some(replacement.to_string());
+                changed += 1;
+            }
+        }
+        Ok(changed)
+    }
+    
+    async fn find_children(&self, id: &str) -> Result<Vec<Resource>, PersistenceError> {
+        let resources = self.resources.read().await;
+        let mut children: Vec<Resource> = resources.values().filter(|r| r.parent() == Some(id)).cloned().collect();
+        children.sort_by(|a, b| a.id.cmp(&b.id));
+        Ok(children)
+    }
+    
+    async fn find_backlinks(&self, id: &str) -> Result<Vec<Resource>, PersistenceError> {
+        let resources = self.resources.read().await;
+        let mut linking: Vec<Resource> = resources.values().filter(|r| r.links_to(id).next().is_some()).cloned().collect();
+        linking.sort_by(|a, b| a.id.cmp(&b.id));
+        Ok(linking)
+    }
+}
+
 impl Default for InMemoryResourceRepository {
     fn default() -> Self {
         Self::new()
@@ -157,10 +267,143 @@ impl Default for InMemoryUserRepository {
     }
 }
 
+/// In-memory repository implementation for Group
+pub struct InMemoryGroupRepository {
+    groups: Arc<RwLock<HashMap<String, Group>>>,
+}
+
+impl InMemoryGroupRepository {
+    /// Create a new empty in-memory group repository
+    pub fn new() -> Self {
+        Self {
+            groups: Arc::new(RwLock::new(HashMap::new())),
+        }
+    }
+}
+
+#[async_trait]
+impl Repository<Group, String> for InMemoryGroupRepository {
+    async fn save(&self, group: Group) -> Result<Group, PersistenceError> {
+        let mut groups = self.groups.write().await;
+        
+        // Clone the group before inserting it
+        let group_clone = group.clone();
+        groups.insert(group.id.clone(), group);
+        
+        Ok(group_clone)
+    }
+    
+    async fn find_by_id(&self, id: &String) -> Result<Option<Group>, PersistenceError> {
+        let groups = self.groups.read().await;
+        Ok(groups.get(id).cloned())
+    }
+    
+    async fn delete(&self, id: &String) -> Result<bool, PersistenceError> {
+        let mut groups = self.groups.write().await;
+        Ok(groups.remove(id).is_some())
+    }
+    
+    async fn find_all(&self) -> Result<Vec<Group>, PersistenceError> {
+        let groups = self.groups.read().await;
+        Ok(groups.values().cloned().collect())
+    }
+    
+    async fn count(&self) -> Result<usize, PersistenceError> {
+        let groups = self.groups.read().await;
+        Ok(groups.len())
+    }
+}
+
+#[async_trait]
+impl GroupRepository for InMemoryGroupRepository {
+    async fn find_by_member(&self, user_id: &str) -> Result<Vec<Group>, PersistenceError> {
+        let groups = self.groups.read().await;
+        Ok(groups.values().filter(|g| g.has_member(user_id)).cloned().collect())
+    }
+}
+
+impl Default for InMemoryGroupRepository {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// In-memory repository implementation for Token
+pub struct InMemoryTokenRepository {
+    tokens: Arc<RwLock<HashMap<String, Token>>>,
+}
+
+impl InMemoryTokenRepository {
+    /// Create a new empty in-memory token repository
+    pub fn new() -> Self {
+        Self {
+            tokens: Arc::new(RwLock::new(HashMap::new())),
+        }
+    }
+}
+
+#[async_trait]
+impl Repository<Token, String> for InMemoryTokenRepository {
+    async fn save(&self, token: Token) -> Result<Token, PersistenceError> {
+        let mut tokens = self.tokens.write().await;
+        
+        // Secrets must stay unique, as they are looked up by hash
+        if tokens.values().any(|t| t.secret_hash == token.secret_hash && t.id != token.id) {
+            return Err(PersistenceError::UniqueConstraintViolation(format!("token secret for {}", token.id)));
+        }
+        
+        let token_clone = token.clone();
+        tokens.insert(token.id.clone(), token);
+        
+        Ok(token_clone)
+    }
+    
+    async fn find_by_id(&self, id: &String) -> Result<Option<Token>, PersistenceError> {
+        let tokens = self.tokens.read().await;
+        Ok(tokens.get(id).cloned())
+    }
+    
+    async fn delete(&self, id: &String) -> Result<bool, PersistenceError> {
+        let mut tokens = self.tokens.write().await;
+        Ok(tokens.remove(id).is_some())
+    }
+    
+    async fn find_all(&self) -> Result<Vec<Token>, PersistenceError> {
+        let tokens = self.tokens.read().await;
+        Ok(tokens.values().cloned().collect())
+    }
+    
+    async fn count(&self) -> Result<usize, PersistenceError> {
+        let tokens = self.tokens.read().await;
+        Ok(tokens.len())
+    }
+}
+
+#[async_trait]
+impl TokenRepository for InMemoryTokenRepository {
+    async fn find_by_secret_hash(&self, secret_hash: &str) -> Result<Option<Token>, PersistenceError> {
+        let tokens = self.tokens.read().await;
+        Ok(tokens.values().find(|t| t.secret_hash == secret_hash).cloned())
+    }
+    
+    async fn find_by_user(&self, user_id: &str) -> Result<Vec<Token>, PersistenceError> {
+        let tokens = self.tokens.read().await;
+        Ok(tokens.values().filter(|t| t.user_id == user_id).cloned().collect())
+    }
+}
+
+impl Default for InMemoryTokenRepository {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 /// Factory for creating repositories
 pub struct RepositoryFactory {
-    resource_repository: Arc<dyn Repository<Resource, String> + Send + Sync>,
+    resource_repository: Arc<dyn ResourceRepository + Send + Sync>,
     user_repository: Arc<dyn Repository<User, String> + Send + Sync>,
+    group_repository: Arc<dyn GroupRepository + Send + Sync>,
+    token_repository: Arc<dyn TokenRepository + Send + Sync>,
 }
 
 impl RepositoryFactory {
@@ -169,11 +412,13 @@ impl RepositoryFactory {
         Self {
             resource_repository: Arc::new(InMemoryResourceRepository::new()),
             user_repository: Arc::new(InMemoryUserRepository::new()),
+            group_repository: Arc::new(InMemoryGroupRepository::new()),
+            token_repository: Arc::new(InMemoryTokenRepository::new()),
         }
     }
     
     /// Get the resource repository
-    pub fn resource_repository(&self) -> Arc<dyn Repository<Resource, String> + Send + Sync> {
+    pub fn resource_repository(&self) -> Arc<dyn ResourceRepository + Send + Sync> {
         self.resource_repository.clone()
     }
     
@@ -181,10 +426,47 @@ impl RepositoryFactory {
     pub fn user_repository(&self) -> Arc<dyn Repository<User, String> + Send + Sync> {
         self.user_repository.clone()
     }
+    
+    /// Get the group repository
+    pub fn group_repository(&self) -> Arc<dyn GroupRepository + Send + Sync> {
+        self.group_repository.clone()
+    }
+    
+    /// Get the token repository
+    pub fn token_repository(&self) -> Arc<dyn TokenRepository + Send + Sync> {
+        self.token_repository.clone()
+    }
 }
 
 impl Default for RepositoryFactory {
     fn default() -> Self {
         Self::new_in_memory()
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{ResourceData, ResourceType};
+
+    #[tokio::test]
+    async fn test_saves_must_carry_the_stored_version() {
+        let repository = InMemoryResourceRepository::new();
+        let resource = Resource::new("doc-1", ResourceData::new("Notes", ResourceType::Document));
+        let saved = repository.save(resource.clone()).await.unwrap();
+        assert_eq!(saved.version, 1);
+
+        // A copy without a version, or with an outdated one, is rejected and changes nothing
+        assert!(matches!(repository.save(resource).await, Err(PersistenceError::Conflict(_))));
+        let mut edited = repository.save(saved.clone()).await.unwrap();
+        assert_eq!(edited.version, 2);
+        let mut stale = saved;
+        stale.data.name = "Stale".to_string();
+        assert!(matches!(repository.save(stale).await, Err(PersistenceError::Conflict(_))));
+        assert_eq!(repository.find_by_id(&"doc-1".to_string()).await.unwrap().unwrap().version, 2);
+
+        edited.data.name = "Edited".to_string();
+        assert_eq!(repository.save(edited).await.unwrap().version, 3);
+        assert_eq!(repository.revisions("doc-1").await.unwrap().len(), 2);
+    }
+}
@@ -1,9 +1,16 @@
-use serde::{Deserialize, Serialize};
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+use serde_json::Value;
 use std::collections::HashMap;
 
+use super::acl::{AclAction, AclEntry, AclEvent, Principal};
+use super::link::{Link, LinkKind};
+use super::user::Permission;
+
 /// Resource type enum
-#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
-#[serde(rename_all = "lowercase")]
+///
+/// Types serialize as their lowercase name; names other than the built-in
+/// types are custom types, which must be registered before use.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub enum ResourceType {
     /// Document resource (for text content)
     Document,
@@ -17,6 +24,8 @@ pub enum ResourceType {
     Media,
     /// Any resource type (wildcard for processors)
     Any,
+    /// User-defined resource type
+    Custom(String),
 }
 
 impl std::fmt::Display for ResourceType {
@@ -28,12 +37,56 @@ impl std::fmt::Display for ResourceType {
             ResourceType::Settings => write!(f, "settings"),
             ResourceType::Media => write!(f, "media"),
             ResourceType::Any => write!(f, "any"),
+            ResourceType::Custom(name) => write!(f, "{}", name),
         }
     }
 }
 
+impl std::str::FromStr for ResourceType {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "document" => Ok(ResourceType::Document),
+            "user" => Ok(ResourceType::User),
+            "project" => Ok(ResourceType::Project),
+            "settings" => Ok(ResourceType::Settings),
+            "media" => Ok(ResourceType::Media),
+            "any" => Ok(ResourceType::Any),
+            name if is_valid_type_name(name) => Ok(ResourceType::Custom(name.to_string())),
+            _ => Err(format!("Invalid resource type name: {}", s)),
+        }
+    }
+}
+
+impl Serialize for ResourceType {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for ResourceType {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer)?.parse().map_err(de::Error::custom)
+    }
+}
+
+impl ResourceType {
+    /// Check if this is a user-defined type
+    pub fn is_custom(&self) -> bool {
+        matches!(self, ResourceType::Custom(_))
+    }
+}
+
+// Helper to check that a type name is a short lowercase identifier
+fn is_valid_type_name(name: &str) -> bool {
+    name.len() <= 32
+        && name.starts_with(|c: char| c.is_ascii_lowercase())
+        && name.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_' || c == '-')
+}
+
 /// Resource data model
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct ResourceData {
     /// Resource name
     pub name: String,
@@ -41,8 +94,8 @@ pub struct ResourceData {
     pub resource_type: ResourceType,
     /// Resource description
     pub description: Option<String>,
-    /// Resource data fields (key-value pairs)
-    pub data: HashMap<String, String>,
+    /// Resource data fields, checked against the schema of the resource type
+    pub data: HashMap<String, Value>,
     /// Resource metadata
     pub metadata: HashMap<String, String>,
 }
@@ -60,8 +113,8 @@ impl ResourceData {
     }
     
     /// Add a data field
-    pub fn with_data(mut self, key: &str, value: &str) -> Self {
-        self.data.insert(key.to_string(), value.to_string());
+    pub fn with_data(mut self, key: &str, value: impl Into<Value>) -> Self {
+        self.data.insert(key.to_string(), value.into());
         self
     }
     
@@ -89,8 +142,32 @@ pub struct Resource {
     pub created_at: String,
     /// Last update timestamp (ISO 8601)
     pub updated_at: String,
+    /// ID of the user who made the last change, if known
+    #[serde(default)]
+    pub updated_by: Option<String>,
+    /// Number of times the resource was saved, 0 until it is first stored
+    ///
+    /// Saves must carry the stored version, so stale copies can't overwrite
+    /// newer changes.
+    #[serde(default)]
+    pub version: u64,
     /// Resource owner (user ID)
     pub owner_id: Option<String>,
+    /// Group owning the resource, whose members act as owners
+    #[serde(default)]
+    pub owner_group_id: Option<String>,
+    /// Groups whose members may read the resource
+    #[serde(default)]
+    pub shared_group_ids: Vec<String>,
+    /// Access control entries granting permissions on this resource
+    #[serde(default)]
+    pub acl: Vec<AclEntry>,
+    /// Audit trail of changes to the access control entries
+    #[serde(default)]
+    pub acl_log: Vec<AclEvent>,
+    /// Links to other resources
+    #[serde(default)]
+    pub links: Vec<Link>,
 }
 
 impl Resource {
@@ -103,7 +180,14 @@ impl Resource {
             data,
             created_at: now.clone(),
             updated_at: now,
+            updated_by: None,
+            version: 0,
             owner_id: None,
+            owner_group_id: None,
+            shared_group_ids: Vec::new(),
+            acl: Vec::new(),
+            acl_log: Vec::new(),
+            links: Vec::new(),
         }
     }
     
@@ -113,11 +197,59 @@ impl Resource {
         self
     }
     
+    /// Set the group owning the resource
+    pub fn with_owner_group(mut self, group_id: &str) -> Self {
+        self.owner_group_id = Some(group_id.to_string());
+        self
+    }
+    
+    /// Share the resource with a group
+    pub fn shared_with(mut self, group_id: &str) -> Self {
+        if !self.is_shared_with(group_id) {
+            self.shared_group_ids.push(group_id.to_string());
+        }
+        self
+    }
+    
+    /// Add a link to another resource
+    pub fn with_link(mut self, link: Link) -> Self {
+        if !self.links.contains(&link) {
+            self.links.push(link);
+        }
+        self
+    }
+    
+    /// Get the ID of the resource containing this one, if any
+    pub fn parent(&self) -> Option<&str> {
+        self.links.iter().find(|link| link.kind == LinkKind::Parent).map(|link| link.target.as_str())
+    }
+    
+    /// Get the links pointing at the given resource
+    pub fn links_to<'a>(&'a self, id: &'a str) -> impl Iterator<Item = &'a Link> {
+        self.links.iter().filter(move |link| link.target == id)
+    }
+    
+    /// Check that the links are well formed, without looking at their targets
+    pub fn validate_links(&self) -> Result<(), String> {
+        if self.links.iter().any(|link| link.target.is_empty() || link.target == self.id) {
+            return Err(format!("Resource {} has a link without a valid target", self.id));
+        }
+        if self.links.iter().filter(|link| link.kind == LinkKind::Parent).count() > 1 {
+            return Err(format!("Resource {} has more than one parent", self.id));
+        }
+        Ok(())
+    }
+    
     /// Update the resource's updated_at timestamp to now
     pub fn touch(&mut self) {
         self.updated_at = chrono::Utc::now().to_rfc3339();
     }
     
+    /// Get the entity tag of this version, for `ETag` and `If-Match` headers
+    pub fn etag(&self) -> String {
+        format!("\"{}\"", self.version)
+    }
+    
     /// Check if the resource is owned by the given user
     pub fn is_owned_by(&self, user_id: &str) -> bool {
         match &self.owner_id {
@@ -125,4 +257,96 @@ impl Resource {
             None => false,
         }
     }
+    
+    /// Check if the resource has an owning user or group
+    pub fn has_owner(&self) -> bool {
+        self.owner_id.is_some() || self.owner_group_id.is_some()
+    }
+    
+    /// Check if the resource is owned by the given group
+    pub fn is_owned_by_group(&self, group_id: &str) -> bool {
+        self.owner_group_id.as_deref() == Some(group_id)
+    }
+    
+    /// Check if the resource is shared with the given group
+    pub fn is_shared_with(&self, group_id: &str) -> bool {
+        self.shared_group_ids.iter().any(|id| id == group_id)
+    }
+    
+    /// Add an access control entry on behalf of a user, replacing any entry for the same principal
+    pub fn grant(&mut self, entry: AclEntry, actor: &str) {
+        self.acl.retain(|e| e.principal != entry.principal);
+        self.acl.push(entry.clone());
+        self.record_acl_event(AclAction::Grant, entry, actor);
+    }
+    
+    /// Remove a principal's access control entry on behalf of a user
+    pub fn revoke(&mut self, principal: &Principal, actor: &str) -> bool {
+        match self.acl.iter().position(|e| e.principal == *principal) {
+            Some(index) => {
+                let entry = self.acl.remove(index);
+                self.record_acl_event(AclAction::Revoke, entry, actor);
+                true
+            }
+            None => false,
+        }
+    }
+    
+    /// Check if an unexpired access control entry grants a permission to a user in the given groups
+    pub fn acl_permits(&self, user_id: &str, group_ids: &[&str], permission: &Permission) -> bool {
+        self.acl.iter().any(|e| e.permits(user_id, group_ids, permission))
+    }
+    
+    /// Replace every reference to a user, as owner, ACL principal, ACL actor or last editor
+    ///
+    /// Returns true if the resource referred to the user.
+    pub fn replace_user(&mut self, user_id: &str, replacement: &str) -> bool {
+        let old = Principal::User(user_id.to_string());
+        let new = Principal::User(replacement.to_string());
+        let mut replaced = false;
+        
+        if self.is_owned_by(user_id) {
+            self.owner_id = Some(replacement.to_string());
+            replaced = true;
+        }
+        let entries = self.acl.iter_mut().chain(self.acl_log.iter_mut().map(|event| &mut event.entry));
+        for entry in entries.filter(|entry| entry.principal == old) {
+            entry.principal = new.clone();
+            replaced = true;
+        }
+        for event in self.acl_log.iter_mut().filter(|event| event.actor == user_id) {
+            event.actor = replacement.to_string();
+            replaced = true;
+        }
+        if self.updated_by.as_deref() == Some(user_id) {
+            self.updated_by = Some(replacement.to_string());
+            replaced = true;
+        }
+        
+        replaced
+    }
+    
+    /// Copy the resource without its access control entries, for checks that must not rely on them
+    pub fn without_acl(&self) -> Resource {
+        Resource {
+            acl: Vec::new(),
+            acl_log: Vec::new(),
+            ..self.clone()
+        }
+    }
+    
+    // Helper to append to the ACL audit trail
+    fn record_acl_event(&mut self, action: AclAction, entry: AclEntry, actor: &str) {
+        self.acl_log.push(AclEvent {
+            action,
+            entry,
+            actor: actor.to_string(),
+            at: chrono::Utc::now().to_rfc3339(),
+        });
+    }
+    
+    /// Check if another version of the resource has the same owners
+    pub fn has_same_owners(&self, other: &Resource) -> bool {
+        self.owner_id == other.owner_id && self.owner_group_id == other.owner_group_id
+    }
 }
\ No newline at end of file
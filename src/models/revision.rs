@@ -0,0 +1,131 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{BTreeSet, HashMap};
+
+use super::resource::{Resource, ResourceData};
+
+/// Immutable snapshot of a resource's data, recorded when it is saved
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Revision {
+    /// ID of the resource
+    pub resource_id: String,
+    /// Revision number, starting at 1
+    pub number: u64,
+    /// Resource data as of this revision
+    pub data: ResourceData,
+    /// ID of the user who made the change, if known
+    pub changed_by: Option<String>,
+    /// Update timestamp of the resource when saved (ISO 8601)
+    pub updated_at: String,
+}
+
+impl Revision {
+    /// Record the current state of a resource as the given revision
+    pub fn of(resource: &Resource, number: u64) -> Self {
+        Self {
+            resource_id: resource.id.clone(),
+            number,
+            data: resource.data.clone(),
+            changed_by: resource.updated_by.clone(),
+            updated_at: resource.updated_at.clone(),
+        }
+    }
+}
+
+/// Change to a single field between two versions of resource data
+///
+/// Fields are named `name`, `type` and `description`, or `data.<key>` and
+/// `metadata.<key>` for map entries. A missing side means the field was
+/// added or removed.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FieldChange {
+    /// Name of the field
+    pub field: String,
+    /// Value before the change
+    pub before: Option<Value>,
+    /// Value after the change
+    pub after: Option<Value>,
+}
+
+impl std::fmt::Display for FieldChange {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match (&self.before, &self.after) {
+            (Some(before), Some(after)) => write!(f, "~ {}: {} -> {}", self.field, before, after),
+            (None, Some(after)) => write!(f, "+ {}: {}", self.field, after),
+            (Some(before), None) => write!(f, "- {}: {}", self.field, before),
+            (None, None) => write!(f, "  {}", self.field),
+        }
+    }
+}
+
+/// Compare two versions of resource data, listing changed fields in a stable order
+pub fn diff(before: &ResourceData, after: &ResourceData) -> Vec<FieldChange> {
+    let mut changes = Vec::new();
+    let mut push = |field: String, before: Option<Value>, after: Option<Value>| {
+        if before != after {
+            changes.push(FieldChange { field, before, after });
+        }
+    };
+
+    push("name".to_string(), Some(before.name.as_str().into()), Some(after.name.as_str().into()));
+    push(
+        "type".to_string(),
+        Some(before.resource_type.to_string().into()),
+        Some(after.resource_type.to_string().into()),
+    );
+    push(
+        "description".to_string(),
+        before.description.as_deref().map(Value::from),
+        after.description.as_deref().map(Value::from),
+    );
+
+    for key in keys(&before.data, &after.data) {
+        push(format!("data.{}", key), before.data.get(key).cloned(), after.data.get(key).cloned());
+    }
+    for key in keys(&before.metadata, &after.metadata) {
+        push(
+            format!("metadata.{}", key),
+            before.metadata.get(key).map(|v| Value::from(v.as_str())),
+            after.metadata.get(key).map(|v| Value::from(v.as_str())),
+        );
+    }
+
+    changes
+}
+
+// Helper to collect the keys of two maps in sorted order
+fn keys<'a, V>(before: &'a HashMap<String, V>, after: &'a HashMap<String, V>) -> BTreeSet<&'a String> {
+    before.keys().chain(after.keys()).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::ResourceType;
+
+    #[test]
+    fn test_diff_lists_changed_fields() {
+        let before = ResourceData::new("Plan", ResourceType::Document)
+            .with_data("content", "draft")
+            .with_data("status", "open")
+            .with_metadata("lang", "en");
+        let after = ResourceData::new("Final plan", ResourceType::Document)
+            .with_description("Signed off")
+            .with_data("content", "final")
+            .with_data("reviewer", "bob")
+            .with_metadata("lang", "en");
+
+        let changes: Vec<String> = diff(&before, &after).iter().map(|c| c.to_string()).collect();
+        assert_eq!(
+            changes,
+            vec![
+                r#"~ name: "Plan" -> "Final plan""#,
+                r#"+ description: "Signed off""#,
+                r#"~ data.content: "draft" -> "final""#,
+                r#"+ data.reviewer: "bob""#,
+                r#"- data.status: "open""#,
+            ]
+        );
+        assert!(diff(&after, &after).is_empty());
+    }
+}
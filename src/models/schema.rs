@@ -0,0 +1,424 @@
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use serde_json::{Number, Value};
+use std::collections::{BTreeMap, HashMap};
+
+use crate::utils::validation::{validate_all, ValidationError, ValidationResult};
+
+use super::resource::{ResourceData, ResourceType};
+
+/// Type of a data field value
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FieldType {
+    /// Text
+    String,
+    /// Whole number
+    Integer,
+    /// Any number
+    Number,
+    /// `true` or `false`
+    Boolean,
+    /// List of values
+    Array,
+    /// Nested object
+    Object,
+    /// Any value
+    #[default]
+    Any,
+}
+
+impl FieldType {
+    /// Check if a value is of this type
+    pub fn matches(&self, value: &Value) -> bool {
+        match self {
+            FieldType::String => value.is_string(),
+            FieldType::Integer => value.is_i64() || value.is_u64(),
+            FieldType::Number => value.is_number(),
+            FieldType::Boolean => value.is_boolean(),
+            FieldType::Array => value.is_array(),
+            FieldType::Object => value.is_object(),
+            FieldType::Any => true,
+        }
+    }
+
+    // Helper to read a value of this type from its string encoding
+    fn parse(&self, text: &str) -> Option<Value> {
+        let text = text.trim();
+        let value = match self {
+            FieldType::String | FieldType::Any => return None,
+            FieldType::Integer => Value::from(text.parse::<i64>().ok()?),
+            FieldType::Number => Value::Number(Number::from_f64(text.parse().ok()?)?),
+            FieldType::Boolean => Value::Bool(text.parse().ok()?),
+            FieldType::Array | FieldType::Object => serde_json::from_str(text).ok()?,
+        };
+        self.matches(&value).then_some(value)
+    }
+}
+
+impl std::fmt::Display for FieldType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FieldType::String => write!(f, "string"),
+            FieldType::Integer => write!(f, "integer"),
+            FieldType::Number => write!(f, "number"),
+            FieldType::Boolean => write!(f, "boolean"),
+            FieldType::Array => write!(f, "array"),
+            FieldType::Object => write!(f, "object"),
+            FieldType::Any => write!(f, "any"),
+        }
+    }
+}
+
+/// Constraints on a single data field
+///
+/// Ranges apply to numbers, lengths to strings (in characters) and arrays,
+/// and patterns to strings.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct FieldSchema {
+    /// Type values must have
+    #[serde(rename = "type")]
+    pub field_type: FieldType,
+    /// Whether the field must be present and not null
+    pub required: bool,
+    /// Smallest allowed number
+    pub minimum: Option<f64>,
+    /// Largest allowed number
+    pub maximum: Option<f64>,
+    /// Shortest allowed string or array
+    pub min_length: Option<usize>,
+    /// Longest allowed string or array
+    pub max_length: Option<usize>,
+    /// Regular expression strings must match
+    pub pattern: Option<String>,
+}
+
+impl FieldSchema {
+    /// Create an optional field of the given type
+    pub fn of(field_type: FieldType) -> Self {
+        Self {
+            field_type,
+            ..Self::default()
+        }
+    }
+
+    /// Make the field required
+    pub fn required(mut self) -> Self {
+        self.required = true;
+        self
+    }
+
+    /// Limit numbers to an inclusive range
+    pub fn with_range(mut self, minimum: Option<f64>, maximum: Option<f64>) -> Self {
+        self.minimum = minimum;
+        self.maximum = maximum;
+        self
+    }
+
+    /// Limit the length of strings and arrays
+    pub fn with_length(mut self, min_length: Option<usize>, max_length: Option<usize>) -> Self {
+        self.min_length = min_length;
+        self.max_length = max_length;
+        self
+    }
+
+    /// Require strings to match a regular expression
+    pub fn with_pattern(mut self, pattern: &str) -> Self {
+        self.pattern = Some(pattern.to_string());
+        self
+    }
+
+    /// Check a value against the constraints
+    pub fn validate(&self, name: &str, value: &Value) -> ValidationResult {
+        if value.is_null() {
+            return match self.required {
+                true => Err(ValidationError::RequiredFieldMissing(name.to_string())),
+                false => Ok(()),
+            };
+        }
+        if !self.field_type.matches(value) {
+            return Err(ValidationError::InvalidFieldValue(
+                name.to_string(),
+                format!("Expected {}, got {}", self.field_type, type_name(value)),
+            ));
+        }
+
+        if let Some(number) = value.as_f64() {
+            let below = self.minimum.is_some_and(|minimum| number < minimum);
+            let above = self.maximum.is_some_and(|maximum| number > maximum);
+            if below || above {
+                return Err(ValidationError::OutOfRange(
+                    name.to_string(),
+                    format!("Must be between {} and {}", bound(self.minimum), bound(self.maximum)),
+                ));
+            }
+        }
+
+        let length = match value {
+            Value::String(text) => Some(text.chars().count()),
+            Value::Array(items) => Some(items.len()),
+            _ => None,
+        };
+        if let Some(length) = length {
+            let short = self.min_length.is_some_and(|min_length| length < min_length);
+            let long = self.max_length.is_some_and(|max_length| length > max_length);
+            if short || long {
+                return Err(ValidationError::InvalidFieldLength(
+                    name.to_string(),
+                    format!(
+                        "Length must be between {} and {}",
+                        bound(self.min_length),
+                        bound(self.max_length)
+                    ),
+                ));
+            }
+        }
+
+        if let (Some(pattern), Some(text)) = (&self.pattern, value.as_str()) {
+            let regex = compile(name, pattern)?;
+            if !regex.is_match(text) {
+                return Err(ValidationError::InvalidFieldFormat(
+                    name.to_string(),
+                    format!("Must match {}", pattern),
+                ));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Schema of the data fields of a resource type
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Schema {
+    /// Constraints by field name
+    #[serde(default)]
+    pub fields: BTreeMap<String, FieldSchema>,
+    /// Whether fields not listed are rejected
+    #[serde(default)]
+    pub strict: bool,
+}
+
+impl Schema {
+    /// Create a schema accepting any fields
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add constraints on a field
+    pub fn with_field(mut self, name: &str, field: FieldSchema) -> Self {
+        self.fields.insert(name.to_string(), field);
+        self
+    }
+
+    /// Reject fields that aren't listed
+    pub fn strict(mut self) -> Self {
+        self.strict = true;
+        self
+    }
+
+    /// Check that the schema itself is usable, e.g. after loading it from a file
+    pub fn verify(&self) -> ValidationResult {
+        validate_all(
+            self.fields
+                .iter()
+                .filter_map(|(name, field)| field.pattern.as_deref().map(|pattern| compile(name, pattern).map(|_| ())))
+                .collect(),
+        )
+    }
+
+    /// Check data fields against the schema, reporting every problem
+    pub fn validate(&self, data: &HashMap<String, Value>) -> ValidationResult {
+        let mut results: Vec<ValidationResult> = self
+            .fields
+            .iter()
+            .map(|(name, field)| field.validate(name, data.get(name).unwrap_or(&Value::Null)))
+            .collect();
+
+        if self.strict {
+            let mut unknown: Vec<&String> = data.keys().filter(|name| !self.fields.contains_key(*name)).collect();
+            unknown.sort();
+            results.extend(unknown.into_iter().map(|name| {
+                Err(ValidationError::InvalidFieldValue(name.clone(), "Not defined by the schema".to_string()))
+            }));
+        }
+
+        validate_all(results)
+    }
+
+    /// Convert string-encoded values to the types the schema declares
+    ///
+    /// This reads data written when fields could only hold strings, e.g.
+    /// `"12"` for an integer or `"[1, 2]"` for an array. Values that don't
+    /// parse are left alone for `validate` to report. Returns the names of
+    /// the converted fields.
+    pub fn migrate(&self, data: &mut HashMap<String, Value>) -> Vec<String> {
+        let mut migrated = Vec::new();
+        for (name, field) in &self.fields {
+            let parsed = match data.get(name) {
+                Some(Value::String(text)) => field.field_type.parse(text),
+                _ => None,
+            };
+            if let Some(value) = parsed {
+                data.insert(name.clone(), value);
+                migrated.push(name.clone());
+            }
+        }
+        migrated
+    }
+}
+
+/// Data schemas by resource type
+///
+/// Types without a schema accept any data. The default set requires
+/// `content` on documents and a valid `email` on users.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Schemas {
+    #[serde(flatten)]
+    types: HashMap<ResourceType, Schema>,
+}
+
+impl Schemas {
+    /// Create a set without any schemas
+    pub fn empty() -> Self {
+        Self { types: HashMap::new() }
+    }
+
+    /// Set the schema of a resource type, replacing any previous one
+    pub fn with_schema(mut self, resource_type: ResourceType, schema: Schema) -> Self {
+        self.types.insert(resource_type, schema);
+        self
+    }
+
+    /// Get the schema of a resource type
+    pub fn get(&self, resource_type: &ResourceType) -> Option<&Schema> {
+        self.types.get(resource_type)
+    }
+
+    /// Check resource data against the schema of its type
+    pub fn validate(&self, data: &ResourceData) -> ValidationResult {
+        self.get(&data.resource_type).map_or(Ok(()), |schema| schema.validate(&data.data))
+    }
+
+    /// Convert string-encoded values to the types the schema of the data's type declares
+    pub fn migrate(&self, data: &mut ResourceData) -> Vec<String> {
+        match self.types.get(&data.resource_type) {
+            Some(schema) => schema.migrate(&mut data.data),
+            None => Vec::new(),
+        }
+    }
+}
+
+impl Default for Schemas {
+    fn default() -> Self {
+        Self::empty()
+            .with_schema(
+                ResourceType::Document,
+                Schema::new().with_field("content", FieldSchema::of(FieldType::String).required()),
+            )
+            .with_schema(
+                ResourceType::User,
+                Schema::new().with_field(
+                    "email",
+                    FieldSchema::of(FieldType::String).required().with_pattern(r"^[^@\s]+@[^@\s]+\.[^@\s]+$"),
+                ),
+            )
+    }
+}
+
+// Helper to compile a field's pattern
+fn compile(name: &str, pattern: &str) -> Result<Regex, ValidationError> {
+    Regex::new(pattern)
+        .map_err(|e| ValidationError::InvalidFieldFormat(name.to_string(), format!("Invalid pattern: {}", e)))
+}
+
+// Helper to name the type of a value in error messages
+fn type_name(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::Number(number) if number.is_f64() => "number",
+        Value::Number(_) => "integer",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    }
+}
+
+// Helper to show an optional bound
+fn bound<T: ToString>(value: Option<T>) -> String {
+    value.map_or_else(|| "any".to_string(), |value| value.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn task_schema() -> Schema {
+        Schema::new()
+            .with_field("title", FieldSchema::of(FieldType::String).required().with_length(Some(1), Some(20)))
+            .with_field("priority", FieldSchema::of(FieldType::Integer).with_range(Some(1.0), Some(5.0)))
+            .with_field("done", FieldSchema::of(FieldType::Boolean))
+            .with_field("tags", FieldSchema::of(FieldType::Array).with_length(None, Some(2)))
+            .with_field("code", FieldSchema::of(FieldType::String).with_pattern(r"^[A-Z]{3}-\d+$"))
+    }
+
+    fn data(value: Value) -> HashMap<String, Value> {
+        serde_json::from_value(value).unwrap()
+    }
+
+    #[test]
+    fn test_validate_types_ranges_and_patterns() {
+        let schema = task_schema();
+        let valid = data(json!({"title": "Ship", "priority": 3, "done": false, "tags": ["a"], "code": "OPS-12"}));
+        assert!(schema.validate(&valid).is_ok());
+
+        let invalid = data(json!({"priority": 9, "done": "no", "tags": [1, 2, 3], "code": "ops"}));
+        let Err(ValidationError::MultipleErrors(errors)) = schema.validate(&invalid) else {
+            panic!("expected several errors");
+        };
+        let messages: Vec<String> = errors.iter().map(ToString::to_string).collect();
+        assert_eq!(
+            messages,
+            vec![
+                "Invalid field format: code - Must match ^[A-Z]{3}-\\d+$",
+                "Invalid field value: done - Expected boolean, got string",
+                "Field value out of range: priority - Must be between 1 and 5",
+                "Invalid field length: tags - Length must be between any and 2",
+                "Required field missing: title",
+            ]
+        );
+
+        let extra = data(json!({"title": "Ship", "owner": "bob"}));
+        assert!(schema.validate(&extra).is_ok());
+        assert!(schema.strict().validate(&extra).is_err());
+        assert!(Schema::new().with_field("x", FieldSchema::default().with_pattern("(")).verify().is_err());
+    }
+
+    #[test]
+    fn test_migrate_string_values() {
+        let schema = task_schema();
+        let mut legacy = data(json!({"title": "Ship", "priority": "4", "done": "true", "tags": "[\"a\"]", "code": "OPS-1"}));
+        assert_eq!(schema.migrate(&mut legacy), vec!["done", "priority", "tags"]);
+        assert_eq!(legacy["priority"], json!(4));
+        assert_eq!(legacy["done"], json!(true));
+        assert_eq!(legacy["tags"], json!(["a"]));
+        assert!(schema.validate(&legacy).is_ok());
+
+        // Unparseable values stay as they were
+        let mut broken = data(json!({"title": "Ship", "priority": "high"}));
+        assert!(schema.migrate(&mut broken).is_empty());
+        assert!(schema.validate(&broken).is_err());
+
+        // Old string maps still deserialize
+        let old: ResourceData = serde_json::from_value(json!({
+            "name": "Notes", "resource_type": "document", "description": null,
+            "data": {"content": "text", "pages": "12"}, "metadata": {}
+        }))
+        .unwrap();
+        assert_eq!(old.data["pages"], json!("12"));
+        assert!(Schemas::default().validate(&old).is_ok());
+    }
+}
@@ -0,0 +1,132 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashSet;
+
+use super::user::Permission;
+
+/// Kind of access token
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TokenKind {
+    /// Short-lived token issued on login
+    Session,
+    /// Long-lived token created by a user for scripts and integrations
+    Personal,
+    /// Single-use token proving ownership of an email address
+    EmailVerification,
+    /// Single-use token allowing a password to be reset
+    PasswordReset,
+}
+
+impl TokenKind {
+    /// Prefix of secrets of this kind, to make leaked tokens easy to recognize
+    pub fn secret_prefix(&self) -> &'static str {
+        match self {
+            TokenKind::Session => "ses_",
+            TokenKind::Personal => "pat_",
+            TokenKind::EmailVerification => "evt_",
+            TokenKind::PasswordReset => "prt_",
+        }
+    }
+
+    /// Check if tokens of this kind authenticate API requests
+    pub fn authenticates(&self) -> bool {
+        matches!(self, TokenKind::Session | TokenKind::Personal)
+    }
+}
+
+/// Stored record of an issued access token
+///
+/// Only a hash of the secret is kept, and it is never serialized, so the
+/// secret can't be recovered from storage or API responses.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Token {
+    /// Unique token ID, used to manage the token without its secret
+    pub id: String,
+    /// ID of the user the token acts for
+    pub user_id: String,
+    /// Kind of token
+    pub kind: TokenKind,
+    /// Label given by the user
+    pub name: Option<String>,
+    /// SHA-256 hash of the secret, hex encoded
+    #[serde(default, skip_serializing)]
+    pub secret_hash: String,
+    /// Permissions the token is limited to, or `None` for all of the user's
+    pub scopes: Option<HashSet<Permission>>,
+    /// Creation timestamp (ISO 8601)
+    pub created_at: String,
+    /// Expiry timestamp (ISO 8601), if any
+    pub expires_at: Option<String>,
+    /// Timestamp of the last successful use (ISO 8601)
+    pub last_used_at: Option<String>,
+    /// Revocation timestamp (ISO 8601)
+    pub revoked_at: Option<String>,
+}
+
+impl Token {
+    /// Create a token record for a secret
+    pub fn new(id: &str, user_id: &str, kind: TokenKind, secret: &str) -> Self {
+        Self {
+            id: id.to_string(),
+            user_id: user_id.to_string(),
+            kind,
+            name: None,
+            secret_hash: hash_secret(secret),
+            scopes: None,
+            created_at: Utc::now().to_rfc3339(),
+            expires_at: None,
+            last_used_at: None,
+            revoked_at: None,
+        }
+    }
+
+    /// Set the label
+    pub fn with_name(mut self, name: &str) -> Self {
+        self.name = Some(name.to_string());
+        self
+    }
+
+    /// Limit the token to the given permissions
+    pub fn with_scopes(mut self, scopes: HashSet<Permission>) -> Self {
+        self.scopes = Some(scopes);
+        self
+    }
+
+    /// Make the token expire at the given time
+    pub fn with_expiry(mut self, expires_at: DateTime<Utc>) -> Self {
+        self.expires_at = Some(expires_at.to_rfc3339());
+        self
+    }
+
+    /// Mark the token as revoked
+    pub fn revoke(&mut self) {
+        if self.revoked_at.is_none() {
+            self.revoked_at = Some(Utc::now().to_rfc3339());
+        }
+    }
+
+    /// Check if the token can be used at the given time
+    ///
+    /// Tokens with an unreadable expiry are treated as expired.
+    pub fn is_active_at(&self, now: DateTime<Utc>) -> bool {
+        self.revoked_at.is_none()
+            && self.expires_at.as_deref().is_none_or(|expires_at| {
+                DateTime::parse_from_rfc3339(expires_at).is_ok_and(|expires_at| expires_at > now)
+            })
+    }
+
+    /// Check if the token's scopes include a permission
+    pub fn allows(&self, permission: &Permission) -> bool {
+        self.scopes.as_ref().is_none_or(|scopes| scopes.contains(permission))
+    }
+}
+
+/// Hash a token secret for storage and lookup
+///
+/// Secrets are long random strings, so a fast unsalted hash is enough to
+/// keep them from being usable if storage leaks.
+pub fn hash_secret(secret: &str) -> String {
+    hex::encode(Sha256::digest(secret.as_bytes()))
+}
@@ -1,9 +1,11 @@
-use serde::{Deserialize, Serialize};
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
 use std::collections::HashSet;
 
 /// User role enum
-#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
-#[serde(rename_all = "lowercase")]
+///
+/// Roles serialize as their lowercase name; names other than the built-in
+/// roles are custom roles, whose permissions are defined by a policy.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub enum UserRole {
     /// Administrator role with full access
     Admin,
@@ -15,6 +17,8 @@ pub enum UserRole {
     ReadOnly,
     /// Guest role with minimal access
     Guest,
+    /// Custom role defined in a policy
+    Custom(String),
 }
 
 impl std::fmt::Display for UserRole {
@@ -25,10 +29,88 @@ impl std::fmt::Display for UserRole {
             UserRole::User => write!(f, "user"),
             UserRole::ReadOnly => write!(f, "readonly"),
             UserRole::Guest => write!(f, "guest"),
+            UserRole::Custom(name) => write!(f, "{}", name),
+        }
+    }
+}
+
+impl std::str::FromStr for UserRole {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "admin" => Ok(UserRole::Admin),
+            "manager" => Ok(UserRole::Manager),
+            "user" => Ok(UserRole::User),
+            "readonly" => Ok(UserRole::ReadOnly),
+            "guest" => Ok(UserRole::Guest),
+            name if is_valid_role_name(name) => Ok(UserRole::Custom(name.to_string())),
+            _ => Err(format!("Invalid role name: {}", s)),
         }
     }
 }
 
+impl Serialize for UserRole {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for UserRole {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer)?.parse().map_err(de::Error::custom)
+    }
+}
+
+impl UserRole {
+    /// Get the permissions granted to this role by default
+    pub fn default_permissions(&self) -> HashSet<Permission> {
+        let permissions: &[Permission] = match self {
+            // Admins have all permissions
+            UserRole::Admin => &[
+                Permission::CreateResource,
+                Permission::ReadResource,
+                Permission::UpdateResource,
+                Permission::DeleteResource,
+                Permission::ManageUsers,
+                Permission::ManageSettings,
+                Permission::ViewReports,
+                Permission::ExportData,
+                Permission::ImportData,
+            ],
+            // Managers have most permissions, but not user management
+            UserRole::Manager => &[
+                Permission::CreateResource,
+                Permission::ReadResource,
+                Permission::UpdateResource,
+                Permission::DeleteResource,
+                Permission::ViewReports,
+                Permission::ExportData,
+                Permission::ImportData,
+            ],
+            // Standard users have basic permissions
+            UserRole::User => &[
+                Permission::CreateResource,
+                Permission::ReadResource,
+                Permission::UpdateResource,
+                Permission::DeleteResource,
+            ],
+            // Read-only users and guests can only read
+            UserRole::ReadOnly | UserRole::Guest => &[Permission::ReadResource],
+            // Custom roles only get what a policy grants them
+            UserRole::Custom(_) => &[],
+        };
+        
+        permissions.iter().cloned().collect()
+    }
+}
+
+// Helper to check that a role name is a lowercase identifier
+fn is_valid_role_name(name: &str) -> bool {
+    name.starts_with(|c: char| c.is_ascii_lowercase())
+        && name.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_' || c == '-')
+}
+
 /// Permission type for access control
 #[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum Permission {
@@ -71,6 +153,53 @@ impl std::fmt::Display for Permission {
     }
 }
 
+impl std::str::FromStr for Permission {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "create_resource" => Ok(Permission::CreateResource),
+            "read_resource" => Ok(Permission::ReadResource),
+            "update_resource" => Ok(Permission::UpdateResource),
+            "delete_resource" => Ok(Permission::DeleteResource),
+            "manage_users" => Ok(Permission::ManageUsers),
+            "manage_settings" => Ok(Permission::ManageSettings),
+            "view_reports" => Ok(Permission::ViewReports),
+            "export_data" => Ok(Permission::ExportData),
+            "import_data" => Ok(Permission::ImportData),
+            _ => match s.strip_prefix("custom:") {
+                Some(name) if !name.is_empty() => Ok(Permission::Custom(name.to_string())),
+                _ => Err(format!("Unknown permission: {}", s)),
+            },
+        }
+    }
+}
+
+/// Lifecycle stage of a user account
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AccountStatus {
+    /// Account can be used
+    Active,
+    /// Account was disabled and can be reactivated
+    Disabled,
+    /// Account was soft-deleted and can be reactivated until it is erased
+    Deleted,
+    /// Account was anonymized and can't be used again
+    Erased,
+}
+
+impl std::fmt::Display for AccountStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AccountStatus::Active => write!(f, "active"),
+            AccountStatus::Disabled => write!(f, "disabled"),
+            AccountStatus::Deleted => write!(f, "deleted"),
+            AccountStatus::Erased => write!(f, "erased"),
+        }
+    }
+}
+
 /// User model
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct User {
@@ -92,6 +221,30 @@ pub struct User {
     pub created_at: String,
     /// Last login timestamp (ISO 8601)
     pub last_login: Option<String>,
+    /// Password hash in PHC string format, never serialized
+    #[serde(default, skip_serializing)]
+    pub password_hash: Option<String>,
+    /// Consecutive failed logins since the last success or lockout
+    #[serde(default)]
+    pub failed_logins: u32,
+    /// Time until which logins are refused (ISO 8601)
+    #[serde(default)]
+    pub locked_until: Option<String>,
+    /// When the account was disabled (ISO 8601)
+    #[serde(default)]
+    pub disabled_at: Option<String>,
+    /// Why the account was disabled
+    #[serde(default)]
+    pub disabled_reason: Option<String>,
+    /// When the account was soft-deleted (ISO 8601)
+    #[serde(default)]
+    pub deleted_at: Option<String>,
+    /// When the account was anonymized (ISO 8601)
+    #[serde(default)]
+    pub erased_at: Option<String>,
+    /// ID of the placeholder taking over the account while it is being erased
+    #[serde(default)]
+    pub erased_as: Option<String>,
 }
 
 impl User {
@@ -109,6 +262,14 @@ impl User {
             email_verified: false,
             created_at: now,
             last_login: None,
+            password_hash: None,
+            failed_logins: 0,
+            locked_until: None,
+            disabled_at: None,
+            disabled_reason: None,
+            deleted_at: None,
+            erased_at: None,
+            erased_as: None,
         }
     }
     
@@ -135,62 +296,124 @@ impl User {
         self.last_login = Some(chrono::Utc::now().to_rfc3339());
     }
     
-    /// Check if the user has a specific permission
+    /// Check if the user has a password set
+    pub fn has_password(&self) -> bool {
+        self.password_hash.is_some()
+    }
+    
+    /// Check if logins are refused at the given time because of repeated failures
+    pub fn is_locked_at(&self, now: chrono::DateTime<chrono::Utc>) -> bool {
+        self.locked_until
+            .as_deref()
+            .and_then(|until| chrono::DateTime::parse_from_rfc3339(until).ok())
+            .is_some_and(|until| until > now)
+    }
+    
+    /// Get the lifecycle stage of the account
+    pub fn status(&self) -> AccountStatus {
+        if self.erased_at.is_some() {
+            AccountStatus::Erased
+        } else if self.deleted_at.is_some() {
+            AccountStatus::Deleted
+        } else if !self.enabled {
+            AccountStatus::Disabled
+        } else {
+            AccountStatus::Active
+        }
+    }
+    
+    /// Disable the account, recording why and when
+    pub fn disable(&mut self, reason: &str) {
+        self.enabled = false;
+        self.disabled_at = Some(chrono::Utc::now().to_rfc3339());
+        self.disabled_reason = Some(reason.to_string());
+    }
+    
+    /// Soft-delete the account, which refuses logins until it is reactivated
+    pub fn soft_delete(&mut self) {
+        self.enabled = false;
+        self.deleted_at = Some(chrono::Utc::now().to_rfc3339());
+    }
+    
+    /// Enable a disabled or soft-deleted account, clearing any lockout
+    pub fn reactivate(&mut self) {
+        self.enabled = true;
+        self.disabled_at = None;
+        self.disabled_reason = None;
+        self.deleted_at = None;
+        self.failed_logins = 0;
+        self.locked_until = None;
+    }
+    
+    /// Create a disabled placeholder for the user under a new ID, keeping nothing personal
+    pub fn anonymized(&self, id: &str) -> User {
+        let mut erased = User::new(id, &format!("{}@erased.invalid", id), "Erased user").with_role(UserRole::Guest);
+        erased.enabled = false;
+        erased.created_at = self.created_at.clone();
+        erased.deleted_at = self.deleted_at.clone();
+        erased.erased_at = Some(chrono::Utc::now().to_rfc3339());
+        erased
+    }
+    
+    /// Check if the user has a specific permission, explicitly or through their role
+    ///
+    /// Only knows the built-in role defaults, so it ignores policy files,
+    /// custom roles, deny rules and groups. Use `core::policy::Policy` instead.
+    #[deprecated(note = "ignores the policy and groups; use core::policy::Policy::evaluate")]
     pub fn has_permission(&self, permission: &Permission) -> bool {
-        // Admins have all permissions
+        // Admins have all permissions, including custom ones
         if self.role == UserRole::Admin {
             return true;
         }
         
-        // Check explicit permissions
-        self.permissions.contains(permission)
+        self.permissions.contains(permission) || self.role.default_permissions().contains(permission)
     }
     
     /// Get all permissions for this user, including role-based permissions
+    ///
+    /// Like `has_permission`, this ignores the policy and groups.
+    #[deprecated(note = "ignores the policy and groups; use core::policy::Policy::effective_permissions")]
     pub fn all_permissions(&self) -> HashSet<Permission> {
         let mut all_perms = self.permissions.clone();
-        
-        // Add role-based permissions
-        match self.role {
-            UserRole::Admin => {
-                // Admins have all permissions
-                all_perms.insert(Permission::CreateResource);
-                all_perms.insert(Permission::ReadResource);
-                all_perms.insert(Permission::UpdateResource);
-                all_perms.insert(Permission::DeleteResource);
-                all_perms.insert(Permission::ManageUsers);
-                all_perms.insert(Permission::ManageSettings);
-                all_perms.insert(Permission::ViewReports);
-                all_perms.insert(Permission::ExportData);
-                all_perms.insert(Permission::ImportData);
-            }
-            UserRole::Manager => {
-                // Managers have most permissions, but not user management
-                all_perms.insert(Permission::CreateResource);
-                all_perms.insert(Permission::ReadResource);
-                all_perms.insert(Permission::UpdateResource);
-                all_perms.insert(Permission::DeleteResource);
-                all_perms.insert(Permission::ViewReports);
-                all_perms.insert(Permission::ExportData);
-                all_perms.insert(Permission::ImportData);
-            }
-            UserRole::User => {
-                // Standard users have basic permissions
-                all_perms.insert(Permission::CreateResource);
-                all_perms.insert(Permission::ReadResource);
-                all_perms.insert(Permission::UpdateResource);
-                all_perms.insert(Permission::DeleteResource);
-            }
-            UserRole::ReadOnly => {
-                // Read-only users can only read
-                all_perms.insert(Permission::ReadResource);
-            }
-            UserRole::Guest => {
-                // Guests can only read
-                all_perms.insert(Permission::ReadResource);
-            }
-        }
-        
+        all_perms.extend(self.role.default_permissions());
         all_perms
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    #[allow(deprecated)]
+    fn test_has_permission_includes_role() {
+        let manager = User::new("m", "m@example.com", "Manager").with_role(UserRole::Manager);
+        assert!(manager.has_permission(&Permission::ViewReports));
+        assert!(!manager.has_permission(&Permission::ManageUsers));
+
+        let guest = User::new("g", "g@example.com", "Guest")
+            .with_role(UserRole::Guest)
+            .with_permission(Permission::ExportData);
+        assert!(guest.has_permission(&Permission::ReadResource));
+        assert!(guest.has_permission(&Permission::ExportData));
+        assert!(!guest.has_permission(&Permission::CreateResource));
+        assert_eq!(guest.all_permissions().len(), 2);
+    }
+
+    #[test]
+    fn test_roles_serialize_as_names() {
+        let user = User::new("a", "a@example.com", "A").with_role(UserRole::Custom("auditor".to_string()));
+        let json = serde_json::to_value(&user).unwrap();
+        assert_eq!(json["role"], "auditor");
+
+        let parsed: User = serde_json::from_value(json).unwrap();
+        assert_eq!(parsed.role, UserRole::Custom("auditor".to_string()));
+        assert_eq!("readonly".parse::<UserRole>(), Ok(UserRole::ReadOnly));
+        assert!("Not A Role".parse::<UserRole>().is_err());
+        assert_eq!("custom:billing.view".parse::<Permission>(), Ok(Permission::Custom("billing.view".to_string())));
+
+        // Password hashes never leave the process
+        let user = User { password_hash: Some("$argon2id$secret".to_string()), ..user };
+        assert!(!serde_json::to_string(&user).unwrap().contains("argon2id"));
+    }
+}
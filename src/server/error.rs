@@ -0,0 +1,103 @@
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use axum::Json;
+use thiserror::Error;
+
+use crate::core::CoreError;
+use crate::models::persistence::PersistenceError;
+
+/// Server error types
+#[derive(Error, Debug)]
+pub enum ServerError {
+    /// Required request header is missing or malformed
+    #[error("Missing or invalid header: {0}")]
+    InvalidHeader(String),
+
+    /// Request signature does not match the payload
+    #[error("Invalid signature")]
+    InvalidSignature,
+
+    /// Request timestamp is outside the accepted window
+    #[error("Timestamp outside tolerance window: {0}")]
+    TimestampOutOfRange(i64),
+
+    /// Request was already received once
+    #[error("Replayed request")]
+    ReplayDetected,
+
+    /// Request body could not be parsed
+    #[error("Invalid payload: {0}")]
+    InvalidPayload(String),
+
+    /// Caller could not be authenticated
+    #[error("Authentication required")]
+    Unauthorized,
+
+    /// Caller lacks the permission for the operation
+    #[error("Permission denied: {0}")]
+    Forbidden(String),
+
+    /// Requested entity does not exist
+    #[error("Not found: {0}")]
+    NotFound(String),
+
+    /// `If-Match` header doesn't match the stored version
+    #[error("Precondition failed: {0}")]
+    PreconditionFailed(String),
+
+    /// Error raised by the backing repository
+    #[error("Persistence error: {0}")]
+    Persistence(#[from] PersistenceError),
+
+    /// Error raised while processing the request
+    #[error("Processing failed: {0}")]
+    Processing(#[from] CoreError),
+
+    /// Error binding or running the HTTP server
+    #[error("Server error: {0}")]
+    Io(String),
+}
+
+impl ServerError {
+    /// Get the HTTP status code for this error
+    pub fn status_code(&self) -> StatusCode {
+        match self {
+            ServerError::InvalidHeader(_) | ServerError::InvalidPayload(_) => StatusCode::BAD_REQUEST,
+            ServerError::InvalidSignature
+            | ServerError::TimestampOutOfRange(_)
+            | ServerError::ReplayDetected
+            | ServerError::Unauthorized => StatusCode::UNAUTHORIZED,
+            ServerError::Forbidden(_) | ServerError::Processing(CoreError::PermissionDenied(_)) => {
+                StatusCode::FORBIDDEN
+            }
+            ServerError::NotFound(_)
+            | ServerError::Processing(CoreError::NotFound(_))
+            | ServerError::Persistence(PersistenceError::NotFoundError(_)) => StatusCode::NOT_FOUND,
+            ServerError::Processing(CoreError::AlreadyExists(_) | CoreError::Conflict(_))
+            | ServerError::Persistence(
+                PersistenceError::UniqueConstraintViolation(_) | PersistenceError::Conflict(_),
+            ) => StatusCode::CONFLICT,
+            ServerError::PreconditionFailed(_) => StatusCode::PRECONDITION_FAILED,
+            ServerError::Processing(CoreError::Validation(_))
+            | ServerError::Persistence(PersistenceError::ValidationError(_)) => StatusCode::UNPROCESSABLE_ENTITY,
+            ServerError::Processing(_) | ServerError::Persistence(_) | ServerError::Io(_) => {
+                StatusCode::INTERNAL_SERVER_ERROR
+            }
+        }
+    }
+}
+
+impl IntoResponse for ServerError {
+    fn into_response(self) -> Response {
+        let status = self.status_code();
+
+        if status.is_server_error() {
+            log::error!("Request failed: {}", self);
+        } else {
+            log::warn!("Request rejected: {}", self);
+        }
+
+        let body = Json(serde_json::json!({ "error": self.to_string() }));
+        (status, body).into_response()
+    }
+}
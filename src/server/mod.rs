@@ -0,0 +1,15 @@
+//! Server module for inbound HTTP endpoints
+//!
+//! Contains embeddable HTTP components that receive calls from external services
+//! and a REST backend serving the repositories for local development.
+
+pub mod error;
+pub mod rest;
+pub mod webhook;
+
+pub use error::ServerError;
+pub use rest::RestServer;
+pub use webhook::{WebhookConfig, WebhookReceiver};
+
+/// Default address for locally run servers
+pub const DEFAULT_BIND_ADDR: &str = "127.0.0.1:8080";
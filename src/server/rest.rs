@@ -0,0 +1,1442 @@
+use crate::core::credentials::PasswordManager;
+use crate::core::jwt::{Claims, JwtVerifier};
+use crate::core::policy::{Decision, Policy};
+use crate::core::processor::ProcessorRegistry;
+use crate::core::query::ListQuery;
+use crate::core::resource_types::{CustomType, TypeRegistry};
+use crate::core::session::{IssuedToken, SessionManager};
+use crate::core::CoreError;
+use crate::models::persistence::{GroupRepository, PersistenceError, Repository, RepositoryFactory, ResourceRepository};
+use crate::models::{
+    AclEntry, Group, LinkKind, OnDelete, Permission, Principal, Resource, ResourceType, Revision, Token, TokenKind, User,
+};
+use crate::utils::id::generate_prefixed_id;
+use axum::extract::{Path, Query, State};
+use axum::http::{header, HeaderMap, StatusCode, Uri};
+use axum::routing::{delete, get, post, put};
+use axum::{Json, Router};
+use serde::Deserialize;
+use std::collections::{HashMap, HashSet};
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Duration;
+
+use super::error::ServerError;
+
+type Resources = Arc<dyn ResourceRepository + Send + Sync>;
+type UserRepository = Arc<dyn Repository<User, String> + Send + Sync>;
+type Groups = Arc<dyn GroupRepository + Send + Sync>;
+
+/// Authenticated caller, with the groups they belong to
+#[derive(Debug, Clone)]
+pub struct Caller {
+    /// Calling user
+    pub user: User,
+    /// Groups the user is a member of
+    pub groups: Vec<Group>,
+    /// Session or personal token presented, if any
+    pub token: Option<Token>,
+    /// Claims of the JWT presented, if any
+    pub claims: Option<Claims>,
+}
+
+/// Body of a login request
+#[derive(Debug, Deserialize)]
+pub struct LoginRequest {
+    /// ID of the user logging in
+    pub user_id: String,
+    /// The user's password
+    pub password: String,
+}
+
+/// Body of a personal token request
+#[derive(Debug, Deserialize)]
+pub struct TokenRequest {
+    /// Label for the token
+    pub name: String,
+    /// Permissions the token is limited to
+    pub scopes: HashSet<Permission>,
+    /// Days until the token expires, or never if omitted
+    pub expires_in_days: Option<u64>,
+}
+
+/// Query parameters accepted by the user list endpoint
+#[derive(Debug, Default, Deserialize)]
+pub struct ListParams {
+    /// Maximum number of items to return
+    pub limit: Option<usize>,
+    /// Substring the item name must contain
+    pub filter: Option<String>,
+}
+
+/// REST backend exposing the repositories over HTTP
+///
+/// Routes mirror the endpoints `ResourceService` calls, so a service pointed
+/// at this server behaves as it would against the real API. Callers
+/// authenticate with a bearer API key mapped to a stored user, a session or
+/// personal token, or a JWT once a verifier is configured. Requests are
+/// authorized against a `Policy`, taking the caller's groups and the token's
+/// scopes or claimed permissions into account. Resources of custom types are
+/// only accepted once the type is in the server's `TypeRegistry`.
+pub struct RestServer {
+    resources: Resources,
+    users: UserRepository,
+    groups: Groups,
+    registry: Arc<ProcessorRegistry>,
+    types: Arc<TypeRegistry>,
+    api_keys: HashMap<String, String>,
+    policy: Arc<Policy>,
+    sessions: SessionManager,
+    passwords: PasswordManager,
+    jwt: Option<JwtVerifier>,
+}
+
+impl RestServer {
+    /// Create a new server over the factory's repositories
+    pub fn new(factory: &RepositoryFactory, registry: Arc<ProcessorRegistry>) -> Self {
+        Self {
+            resources: factory.resource_repository(),
+            users: factory.user_repository(),
+            groups: factory.group_repository(),
+            registry,
+            types: Arc::new(TypeRegistry::new()),
+            api_keys: HashMap::new(),
+            policy: Arc::new(Policy::default()),
+            sessions: SessionManager::new(factory),
+            passwords: PasswordManager::default(),
+            jwt: None,
+        }
+    }
+
+    /// Authorize requests against the given policy instead of the built-in one
+    pub fn with_policy(mut self, policy: Arc<Policy>) -> Self {
+        self.sessions = self.sessions.with_policy(policy.clone());
+        self.policy = policy;
+        self
+    }
+
+    /// Accept the custom resource types of a registry
+    ///
+    /// Register a `CustomTypeProcessor` for the same registry to apply the
+    /// types' schemas and processors.
+    pub fn with_types(mut self, types: Arc<TypeRegistry>) -> Self {
+        self.types = types;
+        self
+    }
+
+    /// Check login passwords with the given manager
+    pub fn with_password_manager(mut self, passwords: PasswordManager) -> Self {
+        self.passwords = passwords;
+        self
+    }
+
+    /// Accept JWTs checked by the given verifier
+    pub fn with_jwt_verifier(mut self, verifier: JwtVerifier) -> Self {
+        self.jwt = Some(verifier);
+        self
+    }
+
+    /// Set how long session tokens issued on login last
+    pub fn with_session_ttl(mut self, ttl: Duration) -> Self {
+        self.sessions = self.sessions.with_session_ttl(ttl);
+        self
+    }
+
+    /// Accept an API key on behalf of the given user
+    pub fn with_api_key(mut self, api_key: &str, user_id: &str) -> Self {
+        self.api_keys.insert(api_key.to_string(), user_id.to_string());
+        self
+    }
+
+    /// Resolve the calling user and their groups from the `Authorization` header
+    ///
+    /// The bearer credential is either a configured API key, a session or
+    /// personal token issued by this server, or a JWT naming a stored user.
+    pub async fn authenticate(&self, headers: &HeaderMap) -> Result<Caller, ServerError> {
+        let credential = headers
+            .get("authorization")
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.strip_prefix("Bearer "))
+            .ok_or(ServerError::Unauthorized)?;
+
+        let (user, token, claims) = match (self.api_keys.get(credential), &self.jwt) {
+            (Some(user_id), _) => {
+                let user = self.users.find_by_id(user_id).await?.ok_or(ServerError::Unauthorized)?;
+                (user, None, None)
+            }
+            (None, Some(verifier)) if credential.matches('.').count() == 2 => {
+                let claims = verifier.verify(credential).map_err(|e| {
+                    log::info!("Rejected JWT: {}", e);
+                    ServerError::Unauthorized
+                })?;
+                let user = self.users.find_by_id(&claims.sub).await?.ok_or(ServerError::Unauthorized)?;
+                (user, None, Some(claims))
+            }
+            (None, _) => match self.sessions.resolve(credential).await {
+                Ok(resolved) => (resolved.user, Some(resolved.token), None),
+                Err(CoreError::PermissionDenied(_)) => return Err(ServerError::Unauthorized),
+                Err(e) => return Err(e.into()),
+            },
+        };
+
+        if !user.enabled {
+            return Err(ServerError::Forbidden(format!("User {} is {}", user.id, user.status())));
+        }
+
+        let groups = self.groups.find_by_member(&user.id).await?;
+        Ok(Caller { user, groups, token, claims })
+    }
+
+    /// Resolve the calling user and require a permission
+    pub async fn authorize(&self, headers: &HeaderMap, permission: Permission) -> Result<Caller, ServerError> {
+        let caller = self.authenticate(headers).await?;
+        self.check(&caller, &permission, None)?;
+        Ok(caller)
+    }
+
+    /// Resolve the caller and a stored resource, requiring a permission on the resource
+    ///
+    /// ACL entries can grant a permission on a single resource, so the caller's
+    /// role alone only decides when the resource does not exist.
+    pub async fn authorize_resource(
+        &self,
+        headers: &HeaderMap,
+        id: &str,
+        permission: Permission,
+    ) -> Result<(Caller, Option<Resource>), ServerError> {
+        let caller = self.authenticate(headers).await?;
+        let existing = self.resources.find_by_id(&id.to_string()).await?;
+        self.check(&caller, &permission, existing.as_ref())?;
+        Ok((caller, existing))
+    }
+
+    /// Require a permission of a caller, optionally on a specific resource
+    ///
+    /// Callers using a scoped token or a JWT are also limited to what the
+    /// token grants.
+    pub fn check(&self, caller: &Caller, permission: &Permission, resource: Option<&Resource>) -> Result<(), ServerError> {
+        let token_denies = caller.token.as_ref().is_some_and(|token| !token.allows(permission))
+            || caller.claims.as_ref().is_some_and(|claims| !claims.allows(permission));
+        if token_denies {
+            return Err(ServerError::Forbidden(format!("Token does not grant permission {}", permission)));
+        }
+
+        match self.policy.evaluate_with_groups(&caller.user, &caller.groups, permission, resource) {
+            Decision::Allow => Ok(()),
+            Decision::Deny(reason) => Err(ServerError::Forbidden(reason)),
+        }
+    }
+
+    /// Create a resource, running it through the processor registry
+    pub async fn create_resource(&self, user: &User, mut resource: Resource) -> Result<Resource, ServerError> {
+        if resource.id.is_empty() {
+            resource.id = generate_prefixed_id(&self.types.id_prefix(&resource.data.resource_type));
+        }
+
+        if self.resources.find_by_id(&resource.id).await?.is_some() {
+            return Err(ServerError::Persistence(
+                PersistenceError::UniqueConstraintViolation(resource.id),
+            ));
+        }
+
+        if resource.owner_id.is_none() {
+            resource.owner_id = Some(user.id.clone());
+        }
+        resource.updated_by = Some(user.id.clone());
+
+        // Initial ACL entries are recorded as granted by the creator
+        resource.acl_log.clear();
+        for entry in std::mem::take(&mut resource.acl) {
+            entry.validate().map_err(ServerError::InvalidPayload)?;
+            resource.grant(entry, &user.id);
+        }
+
+        self.process(&mut resource).await?;
+        Ok(self.resources.save(resource).await?)
+    }
+
+    /// Update a resource, running it through the processor registry
+    pub async fn update_resource(&self, id: &str, mut resource: Resource) -> Result<Resource, ServerError> {
+        if resource.id != id {
+            return Err(ServerError::InvalidPayload("Resource ID mismatch".to_string()));
+        }
+
+        let existing = self
+            .resources
+            .find_by_id(&resource.id)
+            .await?
+            .ok_or_else(|| ServerError::NotFound(id.to_string()))?;
+
+        // Access control only changes through `share_resource` and `unshare_resource`
+        resource.created_at = existing.created_at;
+        resource.acl = existing.acl;
+        resource.acl_log = existing.acl_log;
+        resource.touch();
+
+        self.process(&mut resource).await?;
+        Ok(self.resources.save(resource).await?)
+    }
+
+    /// Grant a principal permissions on a resource, on behalf of a caller
+    ///
+    /// The caller needs update permission on the resource, and can only grant
+    /// permissions they hold on it themselves.
+    pub async fn share_resource(&self, caller: &Caller, mut resource: Resource, entry: AclEntry) -> Result<Resource, ServerError> {
+        entry.validate().map_err(ServerError::InvalidPayload)?;
+        self.check(caller, &Permission::UpdateResource, Some(&resource))?;
+        for permission in &entry.permissions {
+            self.check(caller, permission, Some(&resource))?;
+        }
+
+        resource.grant(entry, &caller.user.id);
+        resource.updated_by = Some(caller.user.id.clone());
+        resource.touch();
+        Ok(self.resources.save(resource).await?)
+    }
+
+    /// Remove a principal's ACL entry from a resource, on behalf of a caller
+    pub async fn unshare_resource(&self, caller: &Caller, mut resource: Resource, principal: &Principal) -> Result<Resource, ServerError> {
+        self.check(caller, &Permission::UpdateResource, Some(&resource))?;
+
+        if !resource.revoke(principal, &caller.user.id) {
+            return Err(ServerError::NotFound(format!("{} has no access entry on {}", principal, resource.id)));
+        }
+        resource.updated_by = Some(caller.user.id.clone());
+        resource.touch();
+        Ok(self.resources.save(resource).await?)
+    }
+
+    /// Restore a past revision's data as a new revision, on behalf of a caller
+    pub async fn restore_resource(&self, caller: &Caller, mut resource: Resource, number: u64) -> Result<Resource, ServerError> {
+        self.check(caller, &Permission::UpdateResource, Some(&resource))?;
+
+        let revision = self
+            .resources
+            .revision(&resource.id, number)
+            .await?
+            .ok_or_else(|| ServerError::NotFound(format!("Revision {} of {}", number, resource.id)))?;
+
+        resource.data = revision.data;
+        resource.updated_by = Some(caller.user.id.clone());
+        resource.touch();
+
+        self.process(&mut resource).await?;
+        Ok(self.resources.save(resource).await?)
+    }
+
+    /// Check a resource's links on behalf of a caller
+    ///
+    /// Linked resources must exist and be readable by the caller, attachments
+    /// must be media, and parents can't form a cycle. Links the previous
+    /// version of the resource already had are not checked again.
+    pub async fn check_links(&self, caller: &Caller, resource: &Resource, previous: Option<&Resource>) -> Result<(), ServerError> {
+        resource.validate_links().map_err(CoreError::Validation)?;
+
+        let new_links = resource.links.iter().filter(|link| previous.is_none_or(|p| !p.links.contains(link)));
+        for link in new_links {
+            let target = self
+                .resources
+                .find_by_id(&link.target)
+                .await?
+                .ok_or_else(|| CoreError::Validation(format!("Linked resource not found: {}", link.target)))?;
+            self.check(caller, &Permission::ReadResource, Some(&target))?;
+
+            if link.kind == LinkKind::Attachment && target.data.resource_type != ResourceType::Media {
+                return Err(CoreError::Validation(format!("Attachments must be media resources: {}", link.target)).into());
+            }
+        }
+
+        // Walk up the parents, which would lead back here if the new parent is a descendant
+        let mut seen = HashSet::new();
+        let mut ancestor = resource.parent().map(str::to_string);
+        while let Some(id) = ancestor.filter(|id| seen.insert(id.clone())) {
+            if id == resource.id {
+                return Err(CoreError::Validation(format!("Resource {} can't be its own ancestor", resource.id)).into());
+            }
+            ancestor = self.resources.find_by_id(&id).await?.and_then(|r| r.parent().map(str::to_string));
+        }
+
+        Ok(())
+    }
+
+    /// Delete a resource on behalf of a caller, returning the IDs of every resource deleted
+    ///
+    /// Resources linking to it with `OnDelete::Cascade` are deleted too, if the
+    /// caller may delete them; any other link to a deleted resource from one
+    /// that stays prevents the deletion.
+    pub async fn delete_resource(&self, caller: &Caller, id: &str) -> Result<Vec<String>, ServerError> {
+        let root = self
+            .resources
+            .find_by_id(&id.to_string())
+            .await?
+            .ok_or_else(|| ServerError::NotFound(id.to_string()))?;
+
+        let mut doomed = vec![root.id];
+        let mut restricted = Vec::new();
+        let mut next = 0;
+        while let Some(target) = doomed.get(next).cloned() {
+            for linking in self.resources.find_backlinks(&target).await? {
+                if doomed.contains(&linking.id) {
+                    continue;
+                }
+                if linking.links_to(&target).all(|link| link.on_delete == OnDelete::Cascade) {
+                    self.check(caller, &Permission::DeleteResource, Some(&linking))?;
+                    doomed.push(linking.id);
+                } else {
+                    restricted.push((linking.id, target.clone()));
+                }
+            }
+            next += 1;
+        }
+
+        // Links from resources deleted along the way don't hold anything back
+        if let Some((linking, target)) = restricted.iter().find(|(linking, _)| !doomed.contains(linking)) {
+            return Err(CoreError::Conflict(format!("{} is linked from {}", target, linking)).into());
+        }
+
+        for id in doomed.iter().rev() {
+            self.resources.delete(id).await?;
+        }
+        Ok(doomed)
+    }
+
+    /// Delete a user, revoking their tokens first so none outlive the account
+    pub async fn delete_user(&self, id: &str) -> Result<(), ServerError> {
+        if self.users.find_by_id(&id.to_string()).await?.is_none() {
+            return Err(ServerError::NotFound(id.to_string()));
+        }
+
+        self.sessions.revoke_all(id).await?;
+        self.users.delete(&id.to_string()).await?;
+        Ok(())
+    }
+
+    // Helper to check that a resource's type is known, then run it through the processors
+    async fn process(&self, resource: &mut Resource) -> Result<(), ServerError> {
+        self.types.check(&resource.data.resource_type)?;
+        Ok(self.registry.process(resource).await?)
+    }
+
+    /// Check a user's password and issue them a session token
+    ///
+    /// Every failure is reported as `ServerError::Unauthorized`, so callers
+    /// can't probe for user IDs or account state.
+    pub async fn login(&self, user_id: &str, password: &str) -> Result<IssuedToken, ServerError> {
+        let user = match self.passwords.login(self.users.as_ref(), user_id, password).await {
+            Ok(user) => user,
+            Err(CoreError::PermissionDenied(reason)) => {
+                log::info!("Login refused for {}: {}", user_id, reason);
+                return Err(ServerError::Unauthorized);
+            }
+            Err(e) => return Err(e.into()),
+        };
+
+        Ok(self.sessions.issue_session(&user).await?)
+    }
+
+    /// List resources matching the given query
+    pub async fn list_resources(&self, query: &ListQuery) -> Result<Vec<Resource>, ServerError> {
+        let mut resources = self.resources.find_all().await?;
+
+        // Default to ID order so paging is stable
+        resources.sort_by(|a, b| a.id.cmp(&b.id));
+        Ok(query.apply(resources))
+    }
+
+    /// Build a router serving the REST API, for embedding in a larger application
+    pub fn router(self: Arc<Self>) -> Router {
+        Router::new()
+            .route("/resources", get(list_resources).post(create_resource))
+            .route(
+                "/resources/:id",
+                get(get_resource).post(update_resource).put(update_resource).delete(delete_resource),
+            )
+            .route("/resources/:id/acl", put(share_resource))
+            .route("/resources/:id/children", get(list_children))
+            .route("/resources/:id/backlinks", get(list_backlinks))
+            .route("/resources/:id/acl/:principal", delete(unshare_resource))
+            .route("/resources/:id/revisions", get(list_revisions))
+            .route("/resources/:id/revisions/:number", get(get_revision))
+            .route("/resources/:id/revisions/:number/restore", post(restore_revision))
+            .route("/sessions", post(login).delete(logout))
+            .route("/tokens", get(list_tokens).post(create_token))
+            .route("/tokens/:id", delete(revoke_token))
+            .route("/types", get(list_types))
+            .route("/types/:name", get(get_type).put(register_type).delete(unregister_type))
+            .route("/users", get(list_users).post(create_user))
+            .route(
+                "/users/:id",
+                get(get_user).post(update_user).put(update_user).delete(delete_user),
+            )
+            .with_state(self)
+    }
+
+    /// Run a standalone HTTP server for the REST API
+    pub async fn serve(self, addr: SocketAddr) -> Result<(), ServerError> {
+        log::info!("REST server listening on {}", addr);
+
+        axum::Server::try_bind(&addr)
+            .map_err(|e| ServerError::Io(e.to_string()))?
+            .serve(Arc::new(self).router().into_make_service())
+            .await
+            .map_err(|e| ServerError::Io(e.to_string()))
+    }
+}
+
+type Shared = State<Arc<RestServer>>;
+
+async fn list_resources(
+    State(server): Shared,
+    headers: HeaderMap,
+    uri: Uri,
+) -> Result<Json<Vec<Resource>>, ServerError> {
+    let caller = server.authenticate(&headers).await?;
+    // The query is only parsed once the caller is known
+    let Query(query) = Query::<ListQuery>::try_from_uri(&uri).map_err(|e| ServerError::InvalidPayload(e.body_text()))?;
+    let denied = server.check(&caller, &Permission::ReadResource, None).err();
+
+    // Page over the resources the caller may read, which without read access
+    // are only those shared through ACL entries
+    let limit = query.limit;
+    let page = query.page;
+    let readable = readable(&server, &caller, server.list_resources(&query.unbounded()).await?);
+    if let Some(e) = denied.filter(|_| readable.is_empty()) {
+        return Err(e);
+    }
+
+    Ok(Json(ListQuery { limit, page, ..ListQuery::new() }.apply(readable)))
+}
+
+async fn create_resource(
+    State(server): Shared,
+    headers: HeaderMap,
+    Json(resource): Json<Resource>,
+) -> Result<(StatusCode, Json<Resource>), ServerError> {
+    let caller = server.authorize(&headers, Permission::CreateResource).await?;
+    server.check(&caller, &Permission::CreateResource, Some(&resource.without_acl()))?;
+    server.check_links(&caller, &resource, None).await?;
+    let created = server.create_resource(&caller.user, resource).await?;
+    Ok((StatusCode::CREATED, Json(created)))
+}
+
+async fn get_resource(
+    State(server): Shared,
+    headers: HeaderMap,
+    Path(id): Path<String>,
+) -> Result<Tagged, ServerError> {
+    let (_, resource) = server.authorize_resource(&headers, &id, Permission::ReadResource).await?;
+    resource.map(tagged).ok_or(ServerError::NotFound(id))
+}
+
+async fn update_resource(
+    State(server): Shared,
+    headers: HeaderMap,
+    Path(id): Path<String>,
+    Json(mut resource): Json<Resource>,
+) -> Result<Tagged, ServerError> {
+    let (caller, existing) = server.authorize_resource(&headers, &id, Permission::UpdateResource).await?;
+    if let Some(existing) = &existing {
+        check_if_match(&headers, existing, &mut resource)?;
+    }
+
+    // The stored resource decides, and a new owner must be covered too,
+    // without help from ACL entries
+    if existing.as_ref().is_some_and(|existing| !existing.has_same_owners(&resource)) {
+        server.check(&caller, &Permission::UpdateResource, Some(&resource.without_acl()))?;
+    }
+    server.check_links(&caller, &resource, existing.as_ref()).await?;
+
+    resource.updated_by = Some(caller.user.id);
+    server.update_resource(&id, resource).await.map(tagged)
+}
+
+async fn delete_resource(
+    State(server): Shared,
+    headers: HeaderMap,
+    Path(id): Path<String>,
+) -> Result<Json<Vec<String>>, ServerError> {
+    let (caller, _) = server.authorize_resource(&headers, &id, Permission::DeleteResource).await?;
+
+    // Respond with every resource deleted, so clients can drop cascaded ones too
+    let deleted = server.delete_resource(&caller, &id).await?;
+    log::info!("Deleted {} on behalf of {}", deleted.join(", "), caller.user.id);
+    Ok(Json(deleted))
+}
+
+async fn list_children(
+    State(server): Shared,
+    headers: HeaderMap,
+    Path(id): Path<String>,
+) -> Result<Json<Vec<Resource>>, ServerError> {
+    let (caller, existing) = server.authorize_resource(&headers, &id, Permission::ReadResource).await?;
+    existing.ok_or_else(|| ServerError::NotFound(id.clone()))?;
+
+    let children = server.resources.find_children(&id).await?;
+    Ok(Json(readable(&server, &caller, children)))
+}
+
+async fn list_backlinks(
+    State(server): Shared,
+    headers: HeaderMap,
+    Path(id): Path<String>,
+) -> Result<Json<Vec<Resource>>, ServerError> {
+    let (caller, existing) = server.authorize_resource(&headers, &id, Permission::ReadResource).await?;
+    existing.ok_or_else(|| ServerError::NotFound(id.clone()))?;
+
+    let linking = server.resources.find_backlinks(&id).await?;
+    Ok(Json(readable(&server, &caller, linking)))
+}
+
+// Helper to keep only the resources a caller may read
+fn readable(server: &RestServer, caller: &Caller, resources: Vec<Resource>) -> Vec<Resource> {
+    resources
+        .into_iter()
+        .filter(|r| server.check(caller, &Permission::ReadResource, Some(r)).is_ok())
+        .collect()
+}
+
+async fn share_resource(
+    State(server): Shared,
+    headers: HeaderMap,
+    Path(id): Path<String>,
+    Json(entry): Json<AclEntry>,
+) -> Result<Json<Resource>, ServerError> {
+    let (caller, existing) = server.authorize_resource(&headers, &id, Permission::UpdateResource).await?;
+    let resource = existing.ok_or(ServerError::NotFound(id))?;
+
+    server.share_resource(&caller, resource, entry).await.map(Json)
+}
+
+async fn unshare_resource(
+    State(server): Shared,
+    headers: HeaderMap,
+    Path((id, principal)): Path<(String, String)>,
+) -> Result<Json<Resource>, ServerError> {
+    let principal: Principal = principal.parse().map_err(ServerError::InvalidPayload)?;
+    let (caller, existing) = server.authorize_resource(&headers, &id, Permission::UpdateResource).await?;
+    let resource = existing.ok_or(ServerError::NotFound(id))?;
+
+    server.unshare_resource(&caller, resource, &principal).await.map(Json)
+}
+
+async fn login(
+    State(server): Shared,
+    Json(request): Json<LoginRequest>,
+) -> Result<(StatusCode, Json<IssuedToken>), ServerError> {
+    let issued = server.login(&request.user_id, &request.password).await?;
+    Ok((StatusCode::CREATED, Json(issued)))
+}
+
+async fn logout(State(server): Shared, headers: HeaderMap) -> Result<Json<Token>, ServerError> {
+    let caller = server.authenticate(&headers).await?;
+
+    match caller.token {
+        Some(token) if token.kind == TokenKind::Session => Ok(Json(server.sessions.revoke(&token.id).await?)),
+        _ => Err(ServerError::InvalidPayload("Only session tokens can be logged out".to_string())),
+    }
+}
+
+/// Resource response carrying its `ETag`
+type Tagged = ([(header::HeaderName, String); 1], Json<Resource>);
+
+// Helper to respond with a resource and its entity tag
+fn tagged(resource: Resource) -> Tagged {
+    ([(header::ETAG, resource.etag())], Json(resource))
+}
+
+// Helper to check an `If-Match` header against the stored resource
+//
+// A matching header stands in for the version in the body, so clients can
+// send either. Without the header the body's version is checked on save.
+fn check_if_match(headers: &HeaderMap, existing: &Resource, resource: &mut Resource) -> Result<(), ServerError> {
+    let Some(value) = headers.get(header::IF_MATCH) else {
+        return Ok(());
+    };
+    let value = value
+        .to_str()
+        .map_err(|_| ServerError::InvalidHeader(header::IF_MATCH.to_string()))?;
+
+    let current = existing.etag();
+    if !value.split(',').map(str::trim).any(|tag| tag == "*" || tag == current) {
+        return Err(ServerError::PreconditionFailed(format!(
+            "{} is at version {}",
+            existing.id, existing.version
+        )));
+    }
+
+    resource.version = existing.version;
+    Ok(())
+}
+
+// Helper to authenticate a caller allowed to manage tokens
+//
+// Personal tokens and JWTs can't manage tokens, so a limited token can't be
+// used to mint a broader one.
+async fn token_manager(server: &RestServer, headers: &HeaderMap) -> Result<Caller, ServerError> {
+    let caller = server.authenticate(headers).await?;
+    if caller.claims.is_some() || caller.token.as_ref().is_some_and(|token| token.kind == TokenKind::Personal) {
+        return Err(ServerError::Forbidden("Only sessions and API keys can manage tokens".to_string()));
+    }
+    Ok(caller)
+}
+
+async fn list_tokens(State(server): Shared, headers: HeaderMap) -> Result<Json<Vec<Token>>, ServerError> {
+    let caller = token_manager(&server, &headers).await?;
+    Ok(Json(server.sessions.tokens_for(&caller.user.id).await?))
+}
+
+async fn create_token(
+    State(server): Shared,
+    headers: HeaderMap,
+    Json(request): Json<TokenRequest>,
+) -> Result<(StatusCode, Json<IssuedToken>), ServerError> {
+    let caller = token_manager(&server, &headers).await?;
+    let ttl = match request.expires_in_days {
+        Some(days) => days
+            .checked_mul(24 * 60 * 60)
+            .map(|secs| Some(Duration::from_secs(secs)))
+            .ok_or_else(|| ServerError::InvalidPayload(format!("Token lifetime too long: {} days", days)))?,
+        None => None,
+    };
+
+    let issued = server
+        .sessions
+        .issue_personal_token(&caller.user, &request.name, request.scopes, ttl)
+        .await?;
+    Ok((StatusCode::CREATED, Json(issued)))
+}
+
+async fn revoke_token(
+    State(server): Shared,
+    headers: HeaderMap,
+    Path(id): Path<String>,
+) -> Result<Json<Token>, ServerError> {
+    let caller = token_manager(&server, &headers).await?;
+    let token = server.sessions.token(&id).await?;
+
+    // Users may always revoke their own tokens
+    if token.user_id != caller.user.id {
+        server.check(&caller, &Permission::ManageUsers, None)?;
+    }
+
+    Ok(Json(server.sessions.revoke(&id).await?))
+}
+
+async fn list_revisions(
+    State(server): Shared,
+    headers: HeaderMap,
+    Path(id): Path<String>,
+) -> Result<Json<Vec<Revision>>, ServerError> {
+    // History is only served for resources that still exist, whose owners decide who reads it
+    let (_, existing) = server.authorize_resource(&headers, &id, Permission::ReadResource).await?;
+    existing.ok_or_else(|| ServerError::NotFound(id.clone()))?;
+
+    Ok(Json(server.resources.revisions(&id).await?))
+}
+
+async fn get_revision(
+    State(server): Shared,
+    headers: HeaderMap,
+    Path((id, number)): Path<(String, u64)>,
+) -> Result<Json<Revision>, ServerError> {
+    let (_, existing) = server.authorize_resource(&headers, &id, Permission::ReadResource).await?;
+    existing.ok_or_else(|| ServerError::NotFound(id.clone()))?;
+
+    server
+        .resources
+        .revision(&id, number)
+        .await?
+        .map(Json)
+        .ok_or_else(|| ServerError::NotFound(format!("Revision {} of {}", number, id)))
+}
+
+async fn restore_revision(
+    State(server): Shared,
+    headers: HeaderMap,
+    Path((id, number)): Path<(String, u64)>,
+) -> Result<Json<Resource>, ServerError> {
+    let (caller, existing) = server.authorize_resource(&headers, &id, Permission::UpdateResource).await?;
+    let resource = existing.ok_or(ServerError::NotFound(id))?;
+
+    server.restore_resource(&caller, resource, number).await.map(Json)
+}
+
+async fn list_users(
+    State(server): Shared,
+    headers: HeaderMap,
+    Query(params): Query<ListParams>,
+) -> Result<Json<Vec<User>>, ServerError> {
+    server.authorize(&headers, Permission::ManageUsers).await?;
+
+    let mut users: Vec<User> = server
+        .users
+        .find_all()
+        .await?
+        .into_iter()
+        .filter(|u| params.filter.as_deref().is_none_or(|f| u.name.contains(f)))
+        .collect();
+
+    users.sort_by(|a, b| a.id.cmp(&b.id));
+    if let Some(limit) = params.limit {
+        users.truncate(limit);
+    }
+
+    Ok(Json(users))
+}
+
+async fn create_user(
+    State(server): Shared,
+    headers: HeaderMap,
+    Json(user): Json<User>,
+) -> Result<(StatusCode, Json<User>), ServerError> {
+    server.authorize(&headers, Permission::ManageUsers).await?;
+
+    if server.users.find_by_id(&user.id).await?.is_some() {
+        return Err(ServerError::Persistence(
+            PersistenceError::UniqueConstraintViolation(user.id),
+        ));
+    }
+
+    Ok((StatusCode::CREATED, Json(server.users.save(user).await?)))
+}
+
+async fn get_user(
+    State(server): Shared,
+    headers: HeaderMap,
+    Path(id): Path<String>,
+) -> Result<Json<User>, ServerError> {
+    let caller = server.authenticate(&headers).await?;
+
+    // Users may always read their own profile
+    if caller.user.id != id {
+        server.check(&caller, &Permission::ManageUsers, None)?;
+    }
+
+    server
+        .users
+        .find_by_id(&id)
+        .await?
+        .map(Json)
+        .ok_or(ServerError::NotFound(id))
+}
+
+async fn update_user(
+    State(server): Shared,
+    headers: HeaderMap,
+    Path(id): Path<String>,
+    Json(mut user): Json<User>,
+) -> Result<Json<User>, ServerError> {
+    server.authorize(&headers, Permission::ManageUsers).await?;
+
+    if user.id != id {
+        return Err(ServerError::InvalidPayload("User ID mismatch".to_string()));
+    }
+    let existing = server.users.find_by_id(&id).await?.ok_or(ServerError::NotFound(id))?;
+
+    // Password hashes are never sent to clients, so keep the stored one
+    user.password_hash = existing.password_hash;
+
+    Ok(Json(server.users.save(user).await?))
+}
+
+async fn delete_user(
+    State(server): Shared,
+    headers: HeaderMap,
+    Path(id): Path<String>,
+) -> Result<Json<bool>, ServerError> {
+    server.authorize(&headers, Permission::ManageUsers).await?;
+
+    server.delete_user(&id).await?;
+    Ok(Json(true))
+}
+
+async fn list_types(State(server): Shared, headers: HeaderMap) -> Result<Json<Vec<CustomType>>, ServerError> {
+    server.authenticate(&headers).await?;
+    Ok(Json(server.types.list()))
+}
+
+async fn get_type(
+    State(server): Shared,
+    headers: HeaderMap,
+    Path(name): Path<String>,
+) -> Result<Json<CustomType>, ServerError> {
+    server.authenticate(&headers).await?;
+    server.types.get(&name).map(Json).ok_or(ServerError::NotFound(name))
+}
+
+async fn register_type(
+    State(server): Shared,
+    headers: HeaderMap,
+    Path(name): Path<String>,
+    Json(definition): Json<CustomType>,
+) -> Result<Json<CustomType>, ServerError> {
+    server.authorize(&headers, Permission::ManageSettings).await?;
+
+    if definition.name != name {
+        return Err(ServerError::InvalidPayload("Type name mismatch".to_string()));
+    }
+    server.types.register(definition.clone())?;
+    Ok(Json(definition))
+}
+
+async fn unregister_type(
+    State(server): Shared,
+    headers: HeaderMap,
+    Path(name): Path<String>,
+) -> Result<Json<bool>, ServerError> {
+    server.authorize(&headers, Permission::ManageSettings).await?;
+
+    match server.types.unregister(&name) {
+        true => Ok(Json(true)),
+        false => Err(ServerError::NotFound(name)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::condition::Condition;
+    use crate::core::credentials::HashingConfig;
+    use crate::core::jwt::{JwtIssuer, JwtTokenProvider, SigningKey, VerificationKey};
+    use crate::api::ApiClient;
+    use crate::core::processor::{DocumentProcessor, SchemaProcessor};
+    use crate::core::resource_types::CustomTypeProcessor;
+    use crate::core::service::ResourceService;
+    use crate::core::user_service::UserService;
+    use crate::core::{CoreError, Service};
+    use crate::models::{FieldSchema, FieldType, Link, ResourceData, ResourceType, Schema, Schemas, UserRole};
+
+    struct Setup {
+        api_url: String,
+        admin: ResourceService,
+        writer: ResourceService,
+        reader: ResourceService,
+    }
+
+    // Build a server over a fresh in-memory store, with an admin, a writer and a reader
+    async fn test_server() -> RestServer {
+        let factory = RepositoryFactory::new_in_memory();
+        let users = factory.user_repository();
+        users.save(User::new("admin", "admin@example.com", "Admin").with_role(UserRole::Admin)).await.unwrap();
+        users.save(User::new("reader", "reader@example.com", "Reader").with_role(UserRole::ReadOnly)).await.unwrap();
+        let passwords = PasswordManager::new().with_hashing(HashingConfig { memory_kib: 64, iterations: 1, parallelism: 1 });
+        let mut writer = User::new("writer", "writer@example.com", "Writer");
+        passwords.set_password(&mut writer, "Correct-Horse-42").unwrap();
+        users.save(writer).await.unwrap();
+        factory.group_repository().save(Group::team("ops", "Operations").with_member("writer")).await.unwrap();
+
+        let registry = ProcessorRegistry::new();
+        registry.register(ResourceType::Document, Box::new(DocumentProcessor)).await;
+        registry.register(ResourceType::Any, Box::new(SchemaProcessor::default())).await;
+        let types = Arc::new(TypeRegistry::new());
+        registry.register(ResourceType::Any, Box::new(CustomTypeProcessor::new(types.clone()))).await;
+
+        RestServer::new(&factory, Arc::new(registry))
+            .with_types(types)
+            .with_api_key("admin-key", "admin")
+            .with_api_key("reader-key", "reader")
+            .with_api_key("writer-key", "writer")
+            .with_password_manager(passwords)
+            .with_jwt_verifier(JwtVerifier::new().with_key(VerificationKey::hs256("test", b"jwt-secret")))
+    }
+
+    // Serve a fresh test server and connect a service for each of its users
+    async fn setup() -> Setup {
+        let server = test_server().await;
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let app = Arc::new(server).router();
+        tokio::spawn(async move {
+            axum::Server::from_tcp(listener).unwrap().serve(app.into_make_service()).await.unwrap();
+        });
+
+        let api_url = format!("http://{}", addr);
+        Setup {
+            admin: service(api_url.clone(), "admin-key"),
+            writer: service(api_url.clone(), "writer-key"),
+            reader: service(api_url.clone(), "reader-key"),
+            api_url,
+        }
+    }
+
+    fn service(api_url: String, api_key: &str) -> ResourceService {
+        let mut config = crate::create_config(Some(api_url), Some(api_key.to_string()));
+        config.max_retries = 0;
+        ResourceService::new(config).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_resource_service_round_trip() {
+        let Setup { api_url, admin: service, .. } = setup().await;
+
+        let data = ResourceData::new("Notes", ResourceType::Document).with_data("content", "  text  ");
+        let created = service.create(Resource::new("doc-1", data)).await.unwrap();
+        assert_eq!(created.owner_id, Some("admin".to_string()));
+        assert_eq!(created.data.data["content"], "text");
+
+        let fetched = service.get("doc-1").await.unwrap();
+        assert_eq!(fetched.data.name, "Notes");
+
+        let listed = service.list(Some(10), Some("Note")).await.unwrap();
+        assert_eq!(listed.len(), 1);
+
+        let query = ListQuery::new().with_condition(Condition::parse("type:document owner:admin data.content:*").unwrap());
+        assert_eq!(service.list_with(&query).await.unwrap().len(), 1);
+        let query = ListQuery::new().with_condition(Condition::parse("type:document -owner:admin").unwrap());
+        assert!(service.list_with(&query).await.unwrap().is_empty());
+
+        // Only DELETE removes resources, never a GET that could be retried or prefetched
+        let response = reqwest::Client::new()
+            .get(format!("{}/resources/doc-1/delete", api_url))
+            .bearer_auth("admin-key")
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+        assert!(service.delete("doc-1").await.unwrap());
+        assert!(matches!(service.get("doc-1").await, Err(CoreError::NotFound(_))));
+    }
+
+    #[tokio::test]
+    async fn test_deeply_nested_queries_are_refused() {
+        let Setup { api_url, writer, .. } = setup().await;
+        let client = reqwest::Client::new();
+        let nested = "(".repeat(1000) + "owner:writer" + &")".repeat(1000);
+        let list = |key: &'static str| client.get(format!("{}/resources", api_url)).query(&[("q", &nested)]).bearer_auth(key).send();
+
+        // Callers are authenticated before the query is parsed, and the server keeps serving
+        assert_eq!(list("unknown-key").await.unwrap().status(), StatusCode::UNAUTHORIZED);
+        assert_eq!(list("writer-key").await.unwrap().status(), StatusCode::BAD_REQUEST);
+        assert!(writer.list(None, None).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_typed_data_and_schemas() {
+        let Setup { api_url, writer, .. } = setup().await;
+        let budget = FieldSchema::of(FieldType::Integer).required().with_range(Some(0.0), None);
+        let schemas = Schemas::default().with_schema(ResourceType::Project, Schema::new().with_field("budget", budget));
+        let service = writer.with_schemas(schemas);
+
+        // String-encoded values are read into the declared type before sending
+        let data = ResourceData::new("Plan", ResourceType::Project)
+            .with_data("budget", "1200")
+            .with_data("milestones", serde_json::json!([{"name": "beta", "done": true}]));
+        let created = service.create(Resource::new("prj-1", data)).await.unwrap();
+        assert_eq!(created.data.data["budget"], 1200);
+        assert_eq!(created.data.data["milestones"][0]["done"], true);
+        let query = ListQuery::new().with_condition(Condition::parse("data.budget>=1000").unwrap());
+        assert_eq!(service.list_with(&query).await.unwrap().len(), 1);
+
+        let negative = ResourceData::new("Plan", ResourceType::Project).with_data("budget", -5);
+        assert!(matches!(service.create(Resource::new("prj-2", negative)).await, Err(CoreError::Validation(_))));
+
+        // The server enforces its own schemas on whatever clients send
+        let untyped = Resource::new("doc-1", ResourceData::new("Notes", ResourceType::Document).with_data("content", 42));
+        let response = reqwest::Client::new()
+            .post(format!("{}/resources", api_url))
+            .bearer_auth("writer-key")
+            .json(&untyped)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+    }
+
+    #[tokio::test]
+    async fn test_custom_resource_types() {
+        let Setup { api_url, writer, .. } = setup().await;
+        let client = reqwest::Client::new();
+        let invoice = CustomType::new("invoice")
+            .with_id_prefix("inv")
+            .with_schema(Schema::new().with_field("amount", FieldSchema::of(FieldType::Number).required()));
+
+        // Only administrators define types
+        let register = |key: &'static str| {
+            client.put(format!("{}/types/invoice", api_url)).bearer_auth(key).json(&invoice).send()
+        };
+        assert_eq!(register("writer-key").await.unwrap().status(), StatusCode::FORBIDDEN);
+        assert_eq!(register("admin-key").await.unwrap().status(), StatusCode::OK);
+        let listed: Vec<CustomType> =
+            client.get(format!("{}/types", api_url)).bearer_auth("writer-key").send().await.unwrap().json().await.unwrap();
+        assert_eq!(listed, vec![invoice.clone()]);
+
+        // Resources of the type get its ID prefix and schema
+        let data = ResourceData::new("March", "invoice".parse().unwrap()).with_data("amount", "99.5");
+        let created = writer.create(Resource::new("", data)).await.unwrap();
+        assert!(created.id.starts_with("inv"));
+        assert_eq!(created.data.data["amount"], 99.5);
+
+        // The server refuses data breaking the schema and types it doesn't know
+        let unpaid = ResourceData::new("April", "invoice".parse().unwrap());
+        let receipt = ResourceData::new("Lunch", "receipt".parse().unwrap());
+        for data in [unpaid.clone(), receipt] {
+            let response = client
+                .post(format!("{}/resources", api_url))
+                .bearer_auth("writer-key")
+                .json(&Resource::new("", data))
+                .send()
+                .await
+                .unwrap();
+            assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+        }
+
+        // Clients knowing the type check it before sending
+        let types = TypeRegistry::new();
+        types.register(invoice).unwrap();
+        let typed = writer.with_types(Arc::new(types));
+        assert!(matches!(typed.create(Resource::new("", unpaid)).await, Err(CoreError::Validation(_))));
+    }
+
+    #[tokio::test]
+    async fn test_resource_links() {
+        let Setup { admin, writer, .. } = setup().await;
+        let create = |id: &str, resource_type: ResourceType, links: Vec<Link>| {
+            let data = ResourceData::new(id, resource_type).with_data("content", "text");
+            let resource = links.into_iter().fold(Resource::new(id, data), Resource::with_link);
+            writer.create(resource)
+        };
+        create("prj-1", ResourceType::Project, vec![]).await.unwrap();
+        create("med-1", ResourceType::Media, vec![]).await.unwrap();
+        create("doc-1", ResourceType::Document, vec![Link::parent("prj-1").cascading(), Link::attachment("med-1")])
+            .await
+            .unwrap();
+        create("doc-2", ResourceType::Document, vec![Link::reference("doc-1")]).await.unwrap();
+
+        // Targets must exist, attachments must be media and parents can't loop
+        assert!(matches!(create("doc-3", ResourceType::Document, vec![Link::reference("doc-9")]).await, Err(CoreError::Validation(_))));
+        assert!(matches!(create("doc-3", ResourceType::Document, vec![Link::attachment("prj-1")]).await, Err(CoreError::Validation(_))));
+        let project = writer.get("prj-1").await.unwrap().with_link(Link::parent("doc-1"));
+        assert!(matches!(writer.update("prj-1", project).await, Err(CoreError::Validation(_))));
+
+        let ids = |resources: Vec<Resource>| resources.into_iter().map(|r| r.id).collect::<Vec<_>>();
+        assert_eq!(ids(writer.children("prj-1").await.unwrap()), vec!["doc-1"]);
+        assert_eq!(ids(writer.backlinks("doc-1").await.unwrap()), vec!["doc-2"]);
+        assert_eq!(ids(writer.backlinks("med-1").await.unwrap()), vec!["doc-1"]);
+
+        // Restricting links hold deletes back, cascading ones take the linking resource along
+        assert!(matches!(writer.delete("med-1").await, Err(CoreError::Conflict(_))));
+        writer.delete("doc-2").await.unwrap();
+        assert!(writer.get("doc-1").await.is_ok());
+        assert!(writer.delete("prj-1").await.unwrap());
+        assert!(matches!(writer.get("doc-1").await, Err(CoreError::NotFound(_))));
+        assert!(admin.delete("med-1").await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_delete_walks_cascading_links() {
+        let server = test_server().await;
+        let writer = Caller {
+            user: server.users.find_by_id(&"writer".to_string()).await.unwrap().unwrap(),
+            groups: Vec::new(),
+            token: None,
+            claims: None,
+        };
+        let save = |id: &str, owner: &str, links: Vec<Link>| {
+            let data = ResourceData::new(id, ResourceType::Project);
+            let resource = links.into_iter().fold(Resource::new(id, data).with_owner(owner), Resource::with_link);
+            server.resources.save(resource)
+        };
+        let remaining = || async {
+            let mut ids: Vec<String> = server.resources.find_all().await.unwrap().into_iter().map(|r| r.id).collect();
+            ids.sort();
+            ids
+        };
+
+        // Deleting A cascades to B, which C restricts, so nothing is deleted
+        save("a", "writer", vec![]).await.unwrap();
+        save("b", "writer", vec![Link::parent("a").cascading()]).await.unwrap();
+        save("c", "writer", vec![Link::reference("b")]).await.unwrap();
+        let restricted = server.delete_resource(&writer, "a").await;
+        assert!(matches!(restricted, Err(ServerError::Processing(CoreError::Conflict(msg))) if msg == "b is linked from c"));
+        assert_eq!(remaining().await, vec!["a", "b", "c"]);
+
+        // Cascades stop at resources the caller may not delete
+        let mut c = server.resources.find_by_id(&"c".to_string()).await.unwrap().unwrap();
+        c.links = vec![Link::parent("a").cascading(), Link::reference("b")];
+        server.resources.save(c).await.unwrap();
+        save("d", "admin", vec![Link::reference("a").cascading()]).await.unwrap();
+        assert!(matches!(server.delete_resource(&writer, "a").await, Err(ServerError::Forbidden(_))));
+        assert_eq!(remaining().await, vec!["a", "b", "c", "d"]);
+
+        // Restricting links from resources deleted along the way don't hold anything back
+        server.resources.delete(&"d".to_string()).await.unwrap();
+        let mut deleted = server.delete_resource(&writer, "a").await.unwrap();
+        deleted.sort();
+        assert_eq!(deleted, vec!["a", "b", "c"]);
+        assert!(remaining().await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_permissions_enforced() {
+        let Setup { api_url, admin, writer, reader } = setup().await;
+        let data = ResourceData::new("Plan", ResourceType::Project);
+        assert!(matches!(reader.create(Resource::new("prj-1", data)).await, Err(CoreError::PermissionDenied(_))));
+
+        // Writers only act on their own resources
+        admin.create(Resource::new("prj-admin", ResourceData::new("Admin plan", ResourceType::Project))).await.unwrap();
+        writer.create(Resource::new("prj-writer", ResourceData::new("Writer plan", ResourceType::Project))).await.unwrap();
+        assert!(matches!(writer.delete("prj-admin").await, Err(CoreError::PermissionDenied(_))));
+        let visible: Vec<String> = writer.list(None, None).await.unwrap().into_iter().map(|r| r.id).collect();
+        assert_eq!(visible, vec!["prj-writer"]);
+
+        let anonymous = service(api_url, "unknown-key");
+        let result = anonymous.get("prj-1").await;
+        assert!(matches!(result, Err(CoreError::PermissionDenied(_))));
+    }
+
+    #[tokio::test]
+    async fn test_group_owned_resources() {
+        let Setup { admin, writer, reader, .. } = setup().await;
+        let data = ResourceData::new("Runbook", ResourceType::Document).with_data("content", "Restart it");
+        admin.create(Resource::new("doc-ops", data).with_owner("admin").with_owner_group("ops")).await.unwrap();
+
+        // Members of the owning team act as owners
+        let mut fetched = writer.get("doc-ops").await.unwrap();
+        fetched.data.name = "Ops runbook".to_string();
+        assert_eq!(writer.update("doc-ops", fetched).await.unwrap().data.name, "Ops runbook");
+        assert!(matches!(reader.get("doc-ops").await, Err(CoreError::PermissionDenied(_))));
+    }
+
+    #[tokio::test]
+    async fn test_share_and_unshare() {
+        let Setup { writer, reader, .. } = setup().await;
+        writer.create(Resource::new("prj-1", ResourceData::new("Plan", ResourceType::Project))).await.unwrap();
+        assert!(matches!(reader.get("prj-1").await, Err(CoreError::PermissionDenied(_))));
+
+        let reader_id = Principal::User("reader".to_string());
+        let entry = AclEntry::new(reader_id.clone(), [Permission::ReadResource, Permission::UpdateResource]);
+        let shared = writer.share("prj-1", entry).await.unwrap();
+        assert_eq!(shared.acl_log.len(), 1);
+        assert_eq!(shared.acl_log[0].actor, "writer");
+
+        // The ACL grants update despite the reader's role, but not sharing further
+        let mut fetched = reader.get("prj-1").await.unwrap();
+        fetched.data.name = "Shared plan".to_string();
+        fetched.acl.clear();
+        let updated = reader.update("prj-1", fetched).await.unwrap();
+        assert_eq!(updated.acl.len(), 1);
+        let escalate = AclEntry::new(reader_id.clone(), [Permission::DeleteResource]);
+        assert!(matches!(reader.share("prj-1", escalate).await, Err(CoreError::PermissionDenied(_))));
+
+        // Only permissions the sharer holds can be granted
+        let too_much = AclEntry::new(reader_id.clone(), [Permission::ManageUsers]);
+        assert!(matches!(writer.share("prj-1", too_much).await, Err(CoreError::PermissionDenied(_))));
+
+        let unshared = writer.unshare("prj-1", &reader_id).await.unwrap();
+        assert!(unshared.acl.is_empty());
+        assert_eq!(unshared.acl_log.len(), 2);
+        assert!(matches!(writer.unshare("prj-1", &reader_id).await, Err(CoreError::NotFound(_))));
+        reader.invalidate_cache().await;
+        assert!(matches!(reader.get("prj-1").await, Err(CoreError::PermissionDenied(_))));
+    }
+
+    #[tokio::test]
+    async fn test_revision_history() {
+        let Setup { admin, writer, reader, .. } = setup().await;
+        let data = ResourceData::new("Plan", ResourceType::Document).with_data("content", "draft");
+        let mut resource = writer.create(Resource::new("doc-1", data)).await.unwrap();
+        resource.data.data.insert("content".to_string(), "final".into());
+        writer.update("doc-1", resource).await.unwrap();
+
+        // Sharing leaves the data alone, so it records no revision
+        let entry = AclEntry::new(Principal::User("reader".to_string()), [Permission::ReadResource]);
+        writer.share("doc-1", entry).await.unwrap();
+        let revisions = writer.revisions("doc-1").await.unwrap();
+        assert_eq!(revisions.iter().map(|r| r.number).collect::<Vec<_>>(), vec![1, 2]);
+        assert_eq!(revisions[1].changed_by.as_deref(), Some("writer"));
+
+        let changes: Vec<String> = writer.diff("doc-1", 1, 2).await.unwrap().iter().map(|c| c.to_string()).collect();
+        assert_eq!(changes, vec![r#"~ data.content: "draft" -> "final""#]);
+        assert!(matches!(writer.revision("doc-1", 9).await, Err(CoreError::NotFound(_))));
+
+        // Restoring adds a revision instead of rewriting history
+        let restored = writer.restore("doc-1", 1).await.unwrap();
+        assert_eq!(restored.data.data["content"], "draft");
+        assert_eq!(writer.get("doc-1").await.unwrap().data.data["content"], "draft");
+        assert_eq!(writer.revisions("doc-1").await.unwrap().len(), 3);
+
+        // Readers see the history but can't restore it, and deleted resources take theirs along
+        assert_eq!(reader.revisions("doc-1").await.unwrap().len(), 3);
+        assert!(matches!(reader.restore("doc-1", 2).await, Err(CoreError::PermissionDenied(_))));
+        writer.delete("doc-1").await.unwrap();
+        assert!(matches!(writer.revisions("doc-1").await, Err(CoreError::NotFound(_))));
+
+        // Whoever creates the ID again starts a fresh history
+        let data = ResourceData::new("Other plan", ResourceType::Document).with_data("content", "new");
+        admin.create(Resource::new("doc-1", data)).await.unwrap();
+        let revisions = admin.revisions("doc-1").await.unwrap();
+        assert_eq!(revisions.len(), 1);
+        assert_eq!(revisions[0].number, 1);
+        assert_eq!(revisions[0].changed_by.as_deref(), Some("admin"));
+        assert!(matches!(admin.revision("doc-1", 2).await, Err(CoreError::NotFound(_))));
+        assert!(matches!(admin.restore("doc-1", 3).await, Err(CoreError::NotFound(_))));
+    }
+
+    #[test]
+    fn test_if_match_checks_the_stored_version() {
+        let mut existing = Resource::new("prj-1", ResourceData::new("Plan", ResourceType::Project));
+        existing.version = 2;
+        let if_match = |value: &str| {
+            let mut headers = HeaderMap::new();
+            headers.insert(header::IF_MATCH, value.parse().unwrap());
+            headers
+        };
+
+        let mut resource = existing.clone();
+        resource.version = 1;
+        assert!(matches!(
+            check_if_match(&if_match("\"1\""), &existing, &mut resource),
+            Err(ServerError::PreconditionFailed(_))
+        ));
+        assert!(check_if_match(&HeaderMap::new(), &existing, &mut resource).is_ok());
+        assert_eq!(resource.version, 1);
+        assert!(check_if_match(&if_match("\"1\", \"2\""), &existing, &mut resource).is_ok());
+        assert_eq!(resource.version, 2);
+    }
+
+    #[tokio::test]
+    async fn test_stale_updates_conflict() {
+        let Setup { api_url, writer: alice, .. } = setup().await;
+        let bob = service(api_url.clone(), "writer-key");
+        let created = alice.create(Resource::new("prj-1", ResourceData::new("Plan", ResourceType::Project))).await.unwrap();
+        assert_eq!(created.version, 1);
+
+        let mut first = alice.get("prj-1").await.unwrap();
+        let mut second = bob.get("prj-1").await.unwrap();
+        first.data.name = "Alice's plan".to_string();
+        second.data.name = "Bob's plan".to_string();
+        assert_eq!(alice.update("prj-1", first).await.unwrap().version, 2);
+        assert!(matches!(bob.update("prj-1", second.clone()).await, Err(CoreError::Conflict(_))));
+        assert_eq!(bob.get("prj-1").await.unwrap().data.name, "Alice's plan");
+
+        // The header decides when sent, the body's version otherwise
+        let http = reqwest::Client::new();
+        let url = format!("{}/resources/prj-1", api_url);
+        let response = http.get(&url).bearer_auth("writer-key").send().await.unwrap();
+        assert_eq!(response.headers()["etag"], "\"2\"");
+        let response = http.put(&url).bearer_auth("writer-key").header("if-match", "\"1\"").json(&second).send().await.unwrap();
+        assert_eq!(response.status(), StatusCode::PRECONDITION_FAILED);
+        let response = http.put(&url).bearer_auth("writer-key").json(&second).send().await.unwrap();
+        assert_eq!(response.status(), StatusCode::CONFLICT);
+        let response = http.put(&url).bearer_auth("writer-key").header("if-match", "*").json(&second).send().await.unwrap();
+        assert_eq!(response.headers()["etag"], "\"3\"");
+        alice.invalidate_cache().await;
+        assert_eq!(alice.get("prj-1").await.unwrap().data.name, "Bob's plan");
+    }
+
+    #[tokio::test]
+    async fn test_sessions_and_tokens() {
+        let Setup { api_url, .. } = setup().await;
+        let client = reqwest::Client::new();
+        let login = |password: &str| {
+            let body = serde_json::json!({ "user_id": "writer", "password": password });
+            client.post(format!("{}/sessions", api_url)).json(&body).send()
+        };
+
+        assert_eq!(login("wrong").await.unwrap().status(), StatusCode::UNAUTHORIZED);
+        let issued: serde_json::Value = login("Correct-Horse-42").await.unwrap().json().await.unwrap();
+        let session = issued["secret"].as_str().unwrap().to_string();
+        assert!(issued["token"]["expires_at"].is_string());
+
+        // A session acts with all of the user's permissions
+        let writer = service(api_url.clone(), &session);
+        writer.create(Resource::new("prj-1", ResourceData::new("Plan", ResourceType::Project))).await.unwrap();
+
+        let body = serde_json::json!({ "name": "ci", "scopes": ["ReadResource"], "expires_in_days": 30 });
+        let response = client.post(format!("{}/tokens", api_url)).bearer_auth(&session).json(&body).send().await.unwrap();
+        assert_eq!(response.status(), StatusCode::CREATED);
+        let personal: serde_json::Value = response.json().await.unwrap();
+        let pat = personal["secret"].as_str().unwrap().to_string();
+
+        // Personal tokens are held to their scopes and can't mint further tokens
+        let scoped = service(api_url.clone(), &pat);
+        assert_eq!(scoped.get("prj-1").await.unwrap().data.name, "Plan");
+        assert!(matches!(scoped.delete("prj-1").await, Err(CoreError::PermissionDenied(_))));
+        let response = client.post(format!("{}/tokens", api_url)).bearer_auth(&pat).json(&body).send().await.unwrap();
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+        let body = serde_json::json!({ "name": "admin", "scopes": ["ManageUsers"] });
+        let response = client.post(format!("{}/tokens", api_url)).bearer_auth(&session).json(&body).send().await.unwrap();
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+        // Lifetimes too long to represent are refused rather than crashing the request
+        for (days, status) in [(u64::MAX, StatusCode::BAD_REQUEST), (1_000_000_000, StatusCode::UNPROCESSABLE_ENTITY)] {
+            let body = serde_json::json!({ "name": "forever", "scopes": ["ReadResource"], "expires_in_days": days });
+            let response = client.post(format!("{}/tokens", api_url)).bearer_auth(&session).json(&body).send().await.unwrap();
+            assert_eq!(response.status(), status);
+        }
+
+        let listed: Vec<Token> = client.get(format!("{}/tokens", api_url)).bearer_auth(&session).send().await.unwrap().json().await.unwrap();
+        assert_eq!(listed.len(), 2);
+
+        let pat_id = personal["token"]["id"].as_str().unwrap();
+        let response = client.delete(format!("{}/tokens/{}", api_url, pat_id)).bearer_auth(&session).send().await.unwrap();
+        assert!(response.json::<Token>().await.unwrap().revoked_at.is_some());
+        scoped.invalidate_cache().await;
+        assert!(matches!(scoped.get("prj-1").await, Err(CoreError::PermissionDenied(_))));
+
+        let response = client.delete(format!("{}/sessions", api_url)).bearer_auth(&session).send().await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        writer.invalidate_cache().await;
+        assert!(matches!(writer.get("prj-1").await, Err(CoreError::PermissionDenied(_))));
+    }
+
+    #[tokio::test]
+    async fn test_deleted_users_lose_their_tokens() {
+        let server = test_server().await;
+        let writer = server.users.find_by_id(&"writer".to_string()).await.unwrap().unwrap();
+        let session = server.sessions.issue_session(&writer).await.unwrap();
+        let pat = server.sessions.issue_personal_token(&writer, "ci", [Permission::ReadResource].into(), None).await.unwrap();
+
+        server.delete_user("writer").await.unwrap();
+        assert!(matches!(server.delete_user("writer").await, Err(ServerError::NotFound(_))));
+        server.users.save(User::new("writer", "new-writer@example.com", "New writer")).await.unwrap();
+        for secret in [&session.secret, &pat.secret] {
+            assert!(matches!(server.sessions.resolve(secret).await, Err(CoreError::PermissionDenied(_))));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_jwt_callers() {
+        let Setup { api_url, .. } = setup().await;
+        let jwt_service = |user: User, key: &[u8]| {
+            let issuer = Arc::new(JwtIssuer::new(SigningKey::hs256("test", key)));
+            let config = crate::create_config(Some(api_url.clone()), None);
+            let client = ApiClient::new(config).unwrap().with_token_provider(Arc::new(JwtTokenProvider::new(issuer, user)));
+            ResourceService::with_client(Arc::new(client))
+        };
+
+        let writer = jwt_service(User::new("writer", "writer@example.com", "Writer"), b"jwt-secret");
+        let created = writer.create(Resource::new("prj-1", ResourceData::new("Plan", ResourceType::Project))).await.unwrap();
+        assert_eq!(created.owner_id, Some("writer".to_string()));
+
+        // Claims can narrow what the stored user may do, but never widen it
+        let reader = jwt_service(User::new("writer", "writer@example.com", "Writer").with_role(UserRole::ReadOnly), b"jwt-secret");
+        assert!(reader.get("prj-1").await.is_ok());
+        assert!(matches!(reader.delete("prj-1").await, Err(CoreError::PermissionDenied(_))));
+        let forged = jwt_service(User::new("reader", "reader@example.com", "Reader").with_role(UserRole::Admin), b"jwt-secret");
+        assert!(matches!(forged.delete("prj-1").await, Err(CoreError::PermissionDenied(_))));
+
+        let untrusted = jwt_service(User::new("writer", "writer@example.com", "Writer"), b"other-secret");
+        assert!(matches!(untrusted.get("prj-1").await, Err(CoreError::PermissionDenied(_))));
+    }
+
+    #[tokio::test]
+    async fn test_user_service_round_trip() {
+        let Setup { admin, reader, .. } = setup().await;
+        let users = UserService::with_client(admin.client());
+
+        let created = users.create(User::new("alice", "alice@example.com", "Alice")).await.unwrap();
+        assert!(matches!(users.create(created.clone()).await, Err(CoreError::AlreadyExists(_))));
+
+        let renamed = User { name: "Alice Smith".to_string(), ..created };
+        users.update("alice", renamed).await.unwrap();
+        assert_eq!(users.get("alice").await.unwrap().name, "Alice Smith");
+        assert_eq!(users.list(None, Some("Smith")).await.unwrap().len(), 1);
+
+        assert!(users.delete("alice").await.unwrap());
+        assert!(matches!(users.get("alice").await, Err(CoreError::NotFound(_))));
+
+        let result = UserService::with_client(reader.client()).list(None, None).await;
+        assert!(matches!(result, Err(CoreError::PermissionDenied(_))));
+    }
+}
@@ -0,0 +1,324 @@
+use crate::core::processor::ProcessorRegistry;
+use crate::models::Resource;
+use axum::body::Bytes;
+use axum::extract::State;
+use axum::http::HeaderMap;
+use axum::routing::post;
+use axum::{Json, Router};
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::Mutex;
+
+use super::error::ServerError;
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// Header carrying the payload signature (`sha256=<hex digest>`)
+pub const SIGNATURE_HEADER: &str = "x-webhook-signature";
+
+/// Header carrying the Unix timestamp the payload was signed at
+pub const TIMESTAMP_HEADER: &str = "x-webhook-timestamp";
+
+/// Default accepted clock difference between sender and receiver in seconds
+pub const DEFAULT_TOLERANCE_SECS: u64 = 300;
+
+/// Default path the receiver listens on
+pub const DEFAULT_WEBHOOK_PATH: &str = "/webhooks/resources";
+
+/// Webhook receiver configuration
+#[derive(Debug, Clone)]
+pub struct WebhookConfig {
+    pub secret: String,
+    pub tolerance: Duration,
+    pub path: String,
+}
+
+impl WebhookConfig {
+    /// Create a new configuration with the given shared secret
+    pub fn new(secret: &str) -> Self {
+        Self {
+            secret: secret.to_string(),
+            tolerance: Duration::from_secs(DEFAULT_TOLERANCE_SECS),
+            path: DEFAULT_WEBHOOK_PATH.to_string(),
+        }
+    }
+
+    /// Set the accepted timestamp tolerance
+    pub fn with_tolerance(mut self, tolerance: Duration) -> Self {
+        self.tolerance = tolerance;
+        self
+    }
+
+    /// Set the path the receiver listens on
+    pub fn with_path(mut self, path: &str) -> Self {
+        self.path = path.to_string();
+        self
+    }
+}
+
+/// Kind of change a webhook notifies about
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum WebhookEvent {
+    /// Resource was created
+    Created,
+    /// Resource was updated
+    Updated,
+    /// Resource was deleted
+    Deleted,
+}
+
+/// Webhook request body
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WebhookPayload {
+    /// Kind of change
+    pub event: WebhookEvent,
+    /// Resource the change applies to
+    pub resource: Resource,
+}
+
+/// Compute the signature header value for a payload
+///
+/// The signature is an HMAC-SHA256 over `"{timestamp}.{body}"`.
+pub fn sign_payload(secret: &str, timestamp: i64, body: &[u8]) -> String {
+    let mac = new_mac(secret, timestamp, body);
+    format!("sha256={}", hex::encode(mac.finalize().into_bytes()))
+}
+
+/// Verify a signature header value against a payload in constant time
+pub fn verify_signature(
+    secret: &str,
+    timestamp: i64,
+    body: &[u8],
+    signature: &str,
+) -> Result<(), ServerError> {
+    new_mac(secret, timestamp, body)
+        .verify_slice(&decode_signature(signature)?)
+        .map_err(|_| ServerError::InvalidSignature)
+}
+
+// Helper to extract the digest bytes from a `sha256=<hex>` signature, in either case
+fn decode_signature(signature: &str) -> Result<Vec<u8>, ServerError> {
+    signature
+        .strip_prefix("sha256=")
+        .and_then(|hex_digest| hex::decode(hex_digest).ok())
+        .ok_or(ServerError::InvalidSignature)
+}
+
+// Helper to build a MAC primed with the signed content
+fn new_mac(secret: &str, timestamp: i64, body: &[u8]) -> HmacSha256 {
+    let mut mac = HmacSha256::new_from_slice(secret.as_bytes())
+        .expect("HMAC accepts keys of any length");
+    mac.update(timestamp.to_string().as_bytes());
+    mac.update(b".");
+    mac.update(body);
+    mac
+}
+
+/// Embeddable receiver for resource change webhooks
+///
+/// Verifies signatures and timestamps, rejects replayed deliveries, and runs
+/// the received resource through the processor registry.
+pub struct WebhookReceiver {
+    config: WebhookConfig,
+    registry: Arc<ProcessorRegistry>,
+    seen: Mutex<HashMap<Vec<u8>, i64>>,
+}
+
+impl WebhookReceiver {
+    /// Create a new receiver dispatching into the given registry
+    pub fn new(config: WebhookConfig, registry: Arc<ProcessorRegistry>) -> Self {
+        Self {
+            config,
+            registry,
+            seen: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Get the receiver configuration
+    pub fn config(&self) -> &WebhookConfig {
+        &self.config
+    }
+
+    /// Verify and process a raw webhook delivery
+    pub async fn handle(&self, headers: &HeaderMap, body: &[u8]) -> Result<WebhookPayload, ServerError> {
+        let timestamp = header_value(headers, TIMESTAMP_HEADER)?
+            .parse::<i64>()
+            .map_err(|_| ServerError::InvalidHeader(TIMESTAMP_HEADER.to_string()))?;
+        let signature = header_value(headers, SIGNATURE_HEADER)?;
+
+        // Verify before touching any state so unsigned requests cannot poison the replay cache
+        verify_signature(&self.config.secret, timestamp, body, signature)?;
+
+        let now = chrono::Utc::now().timestamp();
+        let tolerance = self.config.tolerance.as_secs();
+        if now.abs_diff(timestamp) > tolerance {
+            return Err(ServerError::TimestampOutOfRange(timestamp));
+        }
+
+        // Deliveries are told apart by digest, as the same one can be written in either case
+        {
+            let digest = decode_signature(signature)?;
+            let mut seen = self.seen.lock().await;
+            seen.retain(|_, ts| now.abs_diff(*ts) <= tolerance);
+
+            if seen.contains_key(&digest) {
+                return Err(ServerError::ReplayDetected);
+            }
+            seen.insert(digest, timestamp);
+        }
+
+        let mut payload: WebhookPayload = serde_json::from_slice(body)
+            .map_err(|e| ServerError::InvalidPayload(e.to_string()))?;
+
+        match payload.event {
+            WebhookEvent::Deleted => {
+                log::info!("Resource deleted upstream: id={}", payload.resource.id);
+            }
+            WebhookEvent::Created | WebhookEvent::Updated => {
+                self.registry.process(&mut payload.resource).await?;
+            }
+        }
+
+        Ok(payload)
+    }
+
+    /// Build a router serving this receiver, for embedding in a larger application
+    pub fn router(self: Arc<Self>) -> Router {
+        let path = self.config.path.clone();
+
+        Router::new()
+            .route(&path, post(receive))
+            .with_state(self)
+    }
+
+    /// Run a standalone HTTP server for this receiver
+    pub async fn serve(self, addr: SocketAddr) -> Result<(), ServerError> {
+        log::info!("Webhook receiver listening on {}{}", addr, self.config.path);
+
+        axum::Server::try_bind(&addr)
+            .map_err(|e| ServerError::Io(e.to_string()))?
+            .serve(Arc::new(self).router().into_make_service())
+            .await
+            .map_err(|e| ServerError::Io(e.to_string()))
+    }
+}
+
+// Helper to read a required header as a string
+fn header_value<'a>(headers: &'a HeaderMap, name: &str) -> Result<&'a str, ServerError> {
+    headers
+        .get(name)
+        .and_then(|v| v.to_str().ok())
+        .ok_or_else(|| ServerError::InvalidHeader(name.to_string()))
+}
+
+async fn receive(
+    State(receiver): State<Arc<WebhookReceiver>>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Result<Json<WebhookPayload>, ServerError> {
+    receiver.handle(&headers, &body).await.map(Json)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::processor::DocumentProcessor;
+    use crate::models::{ResourceData, ResourceType};
+
+    const SECRET: &str = "test-secret";
+
+    fn signed_headers(timestamp: i64, body: &[u8]) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(TIMESTAMP_HEADER, timestamp.to_string().parse().unwrap());
+        headers.insert(SIGNATURE_HEADER, sign_payload(SECRET, timestamp, body).parse().unwrap());
+        headers
+    }
+
+    fn document_body() -> Vec<u8> {
+        let data = ResourceData::new("Notes", ResourceType::Document).with_data("content", "  hello  ");
+        let payload = WebhookPayload {
+            event: WebhookEvent::Created,
+            resource: Resource::new("doc-1", data),
+        };
+        serde_json::to_vec(&payload).unwrap()
+    }
+
+    async fn receiver() -> WebhookReceiver {
+        let registry = ProcessorRegistry::new();
+        registry.register(ResourceType::Document, Box::new(DocumentProcessor)).await;
+        WebhookReceiver::new(WebhookConfig::new(SECRET), Arc::new(registry))
+    }
+
+    #[test]
+    fn test_sign_and_verify() {
+        let signature = sign_payload(SECRET, 1_700_000_000, b"{}");
+        assert!(signature.starts_with("sha256="));
+        assert!(verify_signature(SECRET, 1_700_000_000, b"{}", &signature).is_ok());
+        assert!(verify_signature(SECRET, 1_700_000_001, b"{}", &signature).is_err());
+        assert!(verify_signature("other-secret", 1_700_000_000, b"{}", &signature).is_err());
+        assert!(verify_signature(SECRET, 1_700_000_000, b"{}", "sha256=zz").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_handle_processes_resource() {
+        let receiver = receiver().await;
+        let body = document_body();
+        let headers = signed_headers(chrono::Utc::now().timestamp(), &body);
+
+        let payload = receiver.handle(&headers, &body).await.unwrap();
+        assert_eq!(payload.event, WebhookEvent::Created);
+        assert_eq!(payload.resource.data.data["content"], "hello");
+    }
+
+    #[tokio::test]
+    async fn test_handle_rejects_tampered_body() {
+        let receiver = receiver().await;
+        let body = document_body();
+        let headers = signed_headers(chrono::Utc::now().timestamp(), &body);
+
+        let result = receiver.handle(&headers, b"{\"tampered\":true}").await;
+        assert!(matches!(result, Err(ServerError::InvalidSignature)));
+    }
+
+    #[tokio::test]
+    async fn test_handle_rejects_stale_and_replayed() {
+        let receiver = receiver().await;
+        let body = document_body();
+
+        let stale = chrono::Utc::now().timestamp() - DEFAULT_TOLERANCE_SECS as i64 - 1;
+        let result = receiver.handle(&signed_headers(stale, &body), &body).await;
+        assert!(matches!(result, Err(ServerError::TimestampOutOfRange(_))));
+
+        let headers = signed_headers(chrono::Utc::now().timestamp(), &body);
+        assert!(receiver.handle(&headers, &body).await.is_ok());
+        let result = receiver.handle(&headers, &body).await;
+        assert!(matches!(result, Err(ServerError::ReplayDetected)));
+
+        // Changing the case of the hex digest doesn't disguise a replay
+        let mut shouted = headers.clone();
+        let signature = headers[SIGNATURE_HEADER].to_str().unwrap().replace("sha256=", "");
+        shouted.insert(SIGNATURE_HEADER, format!("sha256={}", signature.to_uppercase()).parse().unwrap());
+        let result = receiver.handle(&shouted, &body).await;
+        assert!(matches!(result, Err(ServerError::ReplayDetected)));
+    }
+
+    #[tokio::test]
+    async fn test_extreme_timestamps_and_tolerances() {
+        let body = document_body();
+        let result = receiver().await.handle(&signed_headers(i64::MIN, &body), &body).await;
+        assert!(matches!(result, Err(ServerError::TimestampOutOfRange(i64::MIN))));
+
+        // A tolerance too large for a timestamp accepts everything instead of wrapping
+        let registry = Arc::new(ProcessorRegistry::new());
+        let config = WebhookConfig::new(SECRET).with_tolerance(Duration::from_secs(u64::MAX));
+        let receiver = WebhookReceiver::new(config, registry);
+        assert!(receiver.handle(&signed_headers(i64::MIN, &body), &body).await.is_ok());
+        assert!(receiver.handle(&signed_headers(i64::MAX, &body), &body).await.is_ok());
+    }
+}
@@ -29,6 +29,7 @@ mod api_tests {
             api_key: None,
             timeout: std::time::Duration::from_secs(1),
             max_retries: 3,
+            ..Config::default()
         };
         
         let client = ApiClient::new(config).unwrap();
@@ -72,6 +73,7 @@ mod api_tests {
             api_key: None,
             timeout: std::time::Duration::from_secs(1),
             max_retries: 3,
+            ..Config::default()
         };
         
         let client = ApiClient::new(config).unwrap();
@@ -101,6 +103,7 @@ mod api_tests {
             api_key: None,
             timeout: std::time::Duration::from_secs(1),
             max_retries: 3,
+            ..Config::default()
         };
         
         let client = ApiClient::new(config).unwrap();
@@ -43,6 +43,22 @@ pub fn generate_uuid() -> String {
     )
 }
 
+/// Generate a secret token with a prefix
+///
+/// # Arguments
+///
+/// * `prefix` - The prefix to use (e.g., "ses_" for session tokens)
+///
+/// # Returns
+///
+/// The prefix followed by 32 random bytes, hex encoded
+pub fn generate_secret(prefix: &str) -> String {
+    let mut bytes = [0u8; 32];
+    thread_rng().fill(&mut bytes);
+    
+    format!("{}{}", prefix, hex::encode(bytes))
+}
+
 /// Generate a short ID (6 characters) for display purposes
 ///
 /// # Returns
@@ -31,6 +31,70 @@ pub async fn retry_with_backoff<T, E, F, Fut>(
 where
     F: Fn() -> Fut,
     Fut: Future<Output = Result<T, E>>,
+{
+    retry_with_backoff_if(operation, |_| true, max_retries, initial_delay).await
+}
+
+/// Retry a future with exponential backoff while errors are retryable
+///
+/// Behaves like [`retry_with_backoff`], but stops as soon as `should_retry`
+/// returns `false` for an error.
+///
+/// # Arguments
+///
+/// * `operation` - The async function to retry
+/// * `should_retry` - Predicate deciding whether an error is worth retrying
+/// * `max_retries` - Maximum number of retry attempts
+/// * `initial_delay` - Initial delay duration in milliseconds
+///
+/// # Returns
+///
+/// The result of the async function or the last error if all retries fail
+pub async fn retry_with_backoff_if<T, E, F, Fut, P>(
+    operation: F,
+    should_retry: P,
+    max_retries: u32,
+    initial_delay: u64,
+) -> Result<T, E>
+where
+    F: Fn() -> Fut,
+    Fut: Future<Output = Result<T, E>>,
+    P: Fn(&E) -> bool,
+{
+    retry_with_delay(
+        operation,
+        |error, backoff| should_retry(error).then_some(backoff),
+        max_retries,
+        initial_delay,
+    )
+    .await
+}
+
+/// Retry a future, letting each error pick the delay before the next attempt
+///
+/// `retry_delay` receives the error and the exponential backoff delay that
+/// would apply, and returns how long to wait, or `None` to stop retrying.
+///
+/// # Arguments
+///
+/// * `operation` - The async function to retry
+/// * `retry_delay` - Chooses the delay before retrying an error, if any
+/// * `max_retries` - Maximum number of retry attempts
+/// * `initial_delay` - Initial backoff delay in milliseconds
+///
+/// # Returns
+///
+/// The result of the async function or the last error if all retries fail
+pub async fn retry_with_delay<T, E, F, Fut, P>(
+    operation: F,
+    retry_delay: P,
+    max_retries: u32,
+    initial_delay: u64,
+) -> Result<T, E>
+where
+    F: Fn() -> Fut,
+    Fut: Future<Output = Result<T, E>>,
+    P: Fn(&E, Duration) -> Option<Duration>,
 {
     let mut current_retry = 0;
     let mut delay = initial_delay;
@@ -42,9 +106,11 @@ where
                 if current_retry >= max_retries {
                     return Err(error);
                 }
+                let Some(wait) = retry_delay(&error, Duration::from_millis(delay)) else {
+                    return Err(error);
+                };
 
-                // Exponential backoff
-                tokio::time::sleep(Duration::from_millis(delay)).await;
+                tokio::time::sleep(wait).await;
                 delay *= 2; // Exponential growth
                 current_retry += 1;
             }
@@ -27,13 +27,13 @@ pub enum ValidationError {
     OutOfRange(String, String),
     
     /// Multiple validation errors
-    #[error("Multiple validation errors: {0} errors")]
+    #[error("Multiple validation errors: {} errors", .0.len())]
     MultipleErrors(Vec<ValidationError>),
 }
 
 /// Common validation patterns
 static EMAIL_REGEX: Lazy<Regex> = Lazy::new(|| {
-    Regex::new(r"^[a-zA-Z0-9.!#$%&'*+/=?^_`{|}~-]+@[a-zA-Z0-9](?:[a-zA-Z0-9-]{0,61}[a-zA-Z0-9])?(?:\.[a-zA-Z0-9](?:[a-zA-Z0-9-]{0,61}[a-zA-Z0-9])?)*$").unwrap()
+    Regex::new(r"^[a-zA-Z0-9.!#$%&'*+/=?^_`{|}~-]+@[a-zA-Z0-9](?:[a-zA-Z0-9-]{0,61}[a-zA-Z0-9])?(?:\.[a-zA-Z0-9](?:[a-zA-Z0-9-]{0,61}[a-zA-Z0-9])?)+$").unwrap()
 });
 
 static URL_REGEX: Lazy<Regex> = Lazy::new(|| {
@@ -141,10 +141,7 @@ pub fn validate_username(username: &str, field_name: &str) -> ValidationResult {
 pub fn validate_all(validations: Vec<ValidationResult>) -> ValidationResult {
     let errors: Vec<ValidationError> = validations
         .into_iter()
-        .filter_map(|result| match result {
-            Ok(()) => None,
-            Err(err) => Some(err),
-        })
+        .filter_map(Result::err)
         .collect();
     
     if errors.is_empty() {
@@ -164,7 +161,7 @@ pub fn validate_required_fields(
     let mut errors = Vec::new();
     
     for &field in required_fields {
-        if !data.contains_key(field) || data.get(field).map_or(true, |v| v.is_empty()) {
+        if data.get(field).is_none_or(|v| v.is_empty()) {
             errors.push(ValidationError::RequiredFieldMissing(field.to_string()));
         }
     }